@@ -0,0 +1,202 @@
+use common_x::restful::{
+    axum::{extract::State, response::IntoResponse},
+    ok, ok_simple,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::{
+    AppView,
+    api::{AdminSignedBody, ManagerSignedBody, SignedBody, SignedParam},
+    atproto::{NSID_LIKE, NSID_PROPOSAL, NSID_REPLY, list_records},
+    error::AppError,
+    lexicon::{administrator::Administrator, like::Like, proposal::Proposal, reply::Reply, schema},
+};
+
+#[derive(Debug, Default, Validate, Deserialize, Serialize, ToSchema)]
+#[serde(default)]
+pub struct ListAdminParams {
+    pub timestamp: i64,
+}
+
+impl SignedParam for ListAdminParams {
+    fn timestamp(&self) -> i64 {
+        self.timestamp
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/list",
+    description = "管理员列表",
+    request_body = SignedBody<ListAdminParams>
+)]
+pub async fn list(
+    State(state): State<AppView>,
+    AdminSignedBody(_body): ManagerSignedBody<ListAdminParams>,
+) -> Result<impl IntoResponse, AppError> {
+    let rows = Administrator::fetch_all(&state.db)
+        .await
+        .map_err(|e| AppError::ExecSqlFailed(e.to_string()))?;
+    Ok(ok(rows))
+}
+
+#[derive(Debug, Default, Validate, Deserialize, Serialize, ToSchema)]
+#[serde(default)]
+pub struct AddAdminParams {
+    #[validate(length(min = 1))]
+    pub did: String,
+    /// 按位组合：1 处理提案流程，2 管理管理员
+    #[validate(range(min = 1, max = 3))]
+    pub permission: i32,
+    pub timestamp: i64,
+}
+
+impl SignedParam for AddAdminParams {
+    fn timestamp(&self) -> i64 {
+        self.timestamp
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/add",
+    description = "添加管理员",
+    request_body = SignedBody<AddAdminParams>
+)]
+pub async fn add(
+    State(state): State<AppView>,
+    AdminSignedBody(body): ManagerSignedBody<AddAdminParams>,
+) -> Result<impl IntoResponse, AppError> {
+    Administrator::insert(&state.db, &body.params.did, body.params.permission)
+        .await
+        .map_err(|e| AppError::ExecSqlFailed(e.to_string()))?;
+
+    info!(
+        "administrator {} granted {} permission {}",
+        body.did, body.params.did, body.params.permission
+    );
+
+    Ok(ok_simple())
+}
+
+#[derive(Debug, Default, Validate, Deserialize, Serialize, ToSchema)]
+#[serde(default)]
+pub struct RemoveAdminParams {
+    #[validate(length(min = 1))]
+    pub did: String,
+    pub timestamp: i64,
+}
+
+impl SignedParam for RemoveAdminParams {
+    fn timestamp(&self) -> i64 {
+        self.timestamp
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/remove",
+    description = "移除管理员",
+    request_body = SignedBody<RemoveAdminParams>
+)]
+pub async fn remove(
+    State(state): State<AppView>,
+    AdminSignedBody(body): ManagerSignedBody<RemoveAdminParams>,
+) -> Result<impl IntoResponse, AppError> {
+    // avoid locking everyone out of admin management
+    if body.params.did == body.did {
+        return Err(AppError::ValidateFailed(
+            "cannot remove yourself".to_string(),
+        ));
+    }
+
+    let lines = Administrator::remove(&state.db, &body.params.did)
+        .await
+        .map_err(|e| AppError::ExecSqlFailed(e.to_string()))?;
+    if lines == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    info!("administrator {} revoked {}", body.did, body.params.did);
+
+    Ok(ok_simple())
+}
+
+#[derive(Debug, Default, Validate, Deserialize, Serialize, ToSchema)]
+#[serde(default)]
+pub struct ReindexParams {
+    /// 要从 PDS 重新同步的用户 DID
+    #[validate(length(min = 1))]
+    pub repo: String,
+    pub timestamp: i64,
+}
+
+impl SignedParam for ReindexParams {
+    fn timestamp(&self) -> i64 {
+        self.timestamp
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/reindex",
+    description = "从 PDS 补回用户缺失的提案、回复、点赞，已有记录不变",
+    request_body = SignedBody<ReindexParams>
+)]
+pub async fn reindex(
+    State(state): State<AppView>,
+    AdminSignedBody(body): AdminSignedBody<ReindexParams>,
+) -> Result<impl IntoResponse, AppError> {
+    let repo = &body.params.repo;
+
+    let mut counts = vec![];
+    // 只补写缺失的行：已有行不覆盖（提案内容的修改须经 relayer 的状态检查），
+    // 也不重放创建提案时的任务与时间线；中途超时可直接重试
+    for nsid in [NSID_PROPOSAL, NSID_REPLY, NSID_LIKE] {
+        let records = list_records(&state.pds, state.pds_timeout, repo, nsid)
+            .await
+            .map_err(|e| AppError::CallPdsFailed(e.to_string()))?;
+        let (mut restored, mut existing, mut skipped) = (0, 0, 0);
+        for record in records {
+            let (Some(uri), Some(cid), Some(value)) = (
+                record["uri"].as_str(),
+                record["cid"].as_str(),
+                record.get("value"),
+            ) else {
+                skipped += 1;
+                continue;
+            };
+            if let Err(e) = schema::validate_record(nsid, value) {
+                warn!("reindex skip invalid record {uri}: {e:?}");
+                skipped += 1;
+                continue;
+            }
+            let result = match nsid {
+                NSID_PROPOSAL => Proposal::restore(&state.db, repo, value, uri, cid).await,
+                NSID_REPLY => Reply::restore(&state.db, repo, value, uri, cid).await,
+                _ => Like::restore(&state.db, repo, value, uri, cid).await,
+            };
+            match result {
+                Ok(true) => restored += 1,
+                Ok(false) => existing += 1,
+                Err(e) => {
+                    error!("reindex {uri} failed: {e}");
+                    skipped += 1;
+                }
+            }
+        }
+        counts.push(json!({
+            "collection": nsid,
+            "restored": restored,
+            "existing": existing,
+            "skipped": skipped,
+        }));
+    }
+
+    info!("administrator {} reindexed {repo}", body.did);
+
+    Ok(ok(json!({ "repo": repo, "collections": counts })))
+}
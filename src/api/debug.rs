@@ -0,0 +1,18 @@
+use common_x::restful::{
+    axum::{Json, response::IntoResponse},
+    ok,
+};
+use serde_json::{Value, json};
+
+use crate::error::AppError;
+
+#[utoipa::path(
+    post,
+    path = "/api/debug/unsigned_bytes",
+    description = "返回 params 的 dag-cbor 编码（hex），用于核对客户端签名前的编码，仅 --apidoc 时开启"
+)]
+pub async fn unsigned_bytes(Json(params): Json<Value>) -> Result<impl IntoResponse, AppError> {
+    let bytes = serde_ipld_dagcbor::to_vec(&params)
+        .map_err(|e| AppError::ValidateFailed(format!("encode params failed: {e}")))?;
+    Ok(ok(json!({ "hex": hex::encode(bytes) })))
+}
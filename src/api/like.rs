@@ -3,7 +3,7 @@ use common_x::restful::{
     axum::{Json, extract::State, response::IntoResponse},
     ok,
 };
-use sea_query::{BinOper, Expr, ExprTrait, Func, Order, PostgresQueryBuilder};
+use sea_query::{Alias, Expr, ExprTrait, Order, PostgresQueryBuilder};
 use sea_query_sqlx::SqlxBinder;
 use serde::Deserialize;
 use serde_json::{Value, json};
@@ -13,7 +13,7 @@ use validator::Validate;
 
 use crate::{
     AppView,
-    api::{ToTimestamp, build_authors},
+    api::{build_authors, decode_cursor, encode_cursor},
     error::AppError,
     lexicon::like::{Like, LikeRow, LikeView},
 };
@@ -23,9 +23,10 @@ use crate::{
 pub struct LikeQuery {
     /// filter by user's DID
     pub repo: Option<String>,
-    /// filter by like to (DID)
+    /// filter by liked record uri (proposal / reply)
+    #[serde(alias = "uri")]
     pub to: Option<String>,
-    /// pagination cursor (usually timestamp of the last item seen)
+    /// pagination cursor (opaque, returned by the previous page)
     pub cursor: Option<String>,
     /// number of items to return
     pub limit: u64,
@@ -55,7 +56,10 @@ pub async fn list_like(state: &AppView, query: LikeQuery) -> Result<Value, AppEr
     query
         .validate()
         .map_err(|e| AppError::ValidateFailed(e.to_string()))?;
-    let (sql, values) = sea_query::Query::select()
+
+    // 同一用户对同一目标可能有多条点赞记录（取消后重新点赞），只保留最新的一条
+    let latest = sea_query::Query::select()
+        .distinct_on([(Like::Table, Like::Repo), (Like::Table, Like::To)])
         .columns([
             (Like::Table, Like::Uri),
             (Like::Table, Like::Cid),
@@ -68,29 +72,59 @@ pub async fn list_like(state: &AppView, query: LikeQuery) -> Result<Value, AppEr
         .and_where_option(
             query
                 .repo
-                .map(|p| Expr::col((Like::Table, Like::Repo)).eq(&p)),
+                .as_ref()
+                .map(|p| Expr::col((Like::Table, Like::Repo)).eq(p)),
         )
-        .and_where_option(query.to.map(|t| Expr::col((Like::Table, Like::To)).eq(&t)))
         .and_where_option(
             query
-                .cursor
-                .and_then(|cursor| cursor.parse::<i64>().ok())
-                .map(|cursor| {
-                    Expr::col((Like::Table, Like::Created)).binary(
-                        BinOper::GreaterThan,
-                        Func::cust(ToTimestamp).args([Expr::val(cursor)]),
-                    )
-                }),
+                .to
+                .as_ref()
+                .map(|t| Expr::col((Like::Table, Like::To)).eq(t)),
         )
-        .order_by(Like::Created, Order::Asc)
+        .order_by((Like::Table, Like::Repo), Order::Asc)
+        .order_by((Like::Table, Like::To), Order::Asc)
+        .order_by((Like::Table, Like::Created), Order::Desc)
+        .to_owned();
+
+    let mut select = sea_query::Query::select();
+    select
+        .columns([
+            Like::Uri,
+            Like::Cid,
+            Like::Repo,
+            Like::To,
+            Like::Updated,
+            Like::Created,
+        ])
+        .from_subquery(latest.clone(), Alias::new("latest"));
+    if let Some(cursor) = query.cursor.filter(|c| !c.is_empty()) {
+        let (created, uri) = decode_cursor(&cursor)
+            .ok_or_else(|| AppError::ValidateFailed("invalid cursor".to_string()))?;
+        select.and_where(Expr::cust_with_values(
+            "(\"latest\".\"created\", \"latest\".\"uri\") < ($1::timestamptz, $2)",
+            [created, uri],
+        ));
+    }
+    let (sql, values) = select
+        .order_by(Like::Created, Order::Desc)
+        .order_by(Like::Uri, Order::Desc)
         .limit(std::cmp::min(query.limit, 100))
         .build_sqlx(PostgresQueryBuilder);
 
-    let rows: Vec<LikeRow> = query_as_with(&sql, values.clone())
+    let rows: Vec<LikeRow> = query_as_with(&sql, values)
         .fetch_all(&state.db)
         .await
         .map_err(|e| eyre!("exec sql failed: {e}"))?;
 
+    let (sql, values) = sea_query::Query::select()
+        .expr(Expr::col(Like::Uri).count())
+        .from_subquery(latest, Alias::new("latest"))
+        .build_sqlx(PostgresQueryBuilder);
+    let total: (i64,) = query_as_with(&sql, values)
+        .fetch_one(&state.db)
+        .await
+        .map_err(|e| eyre!("exec sql failed: {e}"))?;
+
     // Batch fetch authors to avoid N+1 queries
     let repos: Vec<&str> = rows.iter().map(|r| r.repo.as_str()).collect();
     let authors = build_authors(state, &repos).await;
@@ -110,14 +144,16 @@ pub async fn list_like(state: &AppView, query: LikeQuery) -> Result<Value, AppEr
         });
     }
 
-    let cursor = views.last().map(|r| r.created.timestamp());
+    let cursor = views.last().map(|r| encode_cursor(&r.created, &r.uri));
     let result = if let Some(cursor) = cursor {
         json!({
-            "cursor": cursor.to_string(),
+            "cursor": cursor,
+            "total": total.0,
             "likes": views
         })
     } else {
         json!({
+            "total": total.0,
             "likes": views
         })
     };
@@ -9,20 +9,40 @@ use common_x::restful::{
 use sea_query::{Expr, ExprTrait, Order, PostgresQueryBuilder};
 use sea_query_sqlx::SqlxBinder;
 use serde::Deserialize;
+use serde_json::json;
 use utoipa::IntoParams;
 use validator::Validate;
 
 use crate::{
     AppView,
+    api::build_authors,
     error::AppError,
-    lexicon::meeting::{Meeting, MeetingRow, MeetingState},
+    lexicon::meeting::{Meeting, MeetingRow, MeetingState, MeetingView},
 };
 
-#[derive(Debug, Default, Validate, Deserialize, IntoParams)]
+#[derive(Debug, Validate, Deserialize, IntoParams)]
 #[serde(default)]
 pub struct ProposalUriQuery {
     #[validate(length(min = 1))]
-    pub proposal: String,
+    #[serde(alias = "proposal")]
+    pub proposal_uri: String,
+    /// 会议状态，缺省为 Scheduled
+    pub state: Option<i32>,
+    #[validate(range(min = 1))]
+    pub page: u64,
+    #[validate(range(min = 1))]
+    pub per_page: u64,
+}
+
+impl Default for ProposalUriQuery {
+    fn default() -> Self {
+        Self {
+            proposal_uri: String::new(),
+            state: None,
+            page: 1,
+            per_page: 20,
+        }
+    }
 }
 
 #[utoipa::path(get, path = "/api/meeting", params(ProposalUriQuery))]
@@ -33,10 +53,14 @@ pub async fn get(
     query
         .validate()
         .map_err(|e| AppError::ValidateFailed(e.to_string()))?;
+    let meeting_state = query.state.unwrap_or(MeetingState::Scheduled as i32);
+    let offset = query.per_page * (query.page - 1);
     let (sql, values) = Meeting::build_select()
-        .and_where(Expr::col(Meeting::State).eq(MeetingState::Scheduled as i32))
-        .and_where(Expr::col(Meeting::ProposalUri).eq(&query.proposal))
+        .and_where(Expr::col(Meeting::State).eq(meeting_state))
+        .and_where(Expr::col(Meeting::ProposalUri).eq(&query.proposal_uri))
         .order_by(Meeting::Created, Order::Desc)
+        .limit(std::cmp::min(query.per_page, 100))
+        .offset(offset)
         .build_sqlx(PostgresQueryBuilder);
 
     let rows: Vec<MeetingRow> = sqlx::query_as_with(&sql, values)
@@ -44,5 +68,34 @@ pub async fn get(
         .await
         .map_err(|e| eyre!("exec sql failed: {e}"))?;
 
-    Ok(ok(rows))
+    let (sql, values) = sea_query::Query::select()
+        .expr(Expr::col((Meeting::Table, Meeting::Id)).count())
+        .from(Meeting::Table)
+        .and_where(Expr::col(Meeting::State).eq(meeting_state))
+        .and_where(Expr::col(Meeting::ProposalUri).eq(&query.proposal_uri))
+        .build_sqlx(PostgresQueryBuilder);
+    let total: (i64,) = sqlx::query_as_with(&sql, values)
+        .fetch_one(&state.db)
+        .await
+        .map_err(|e| eyre!("exec sql failed: {e}"))?;
+
+    let repos: Vec<&str> = rows.iter().map(|r| r.creator.as_str()).collect();
+    let authors = build_authors(&state, &repos).await;
+    let views = rows
+        .into_iter()
+        .map(|row| {
+            let creator = authors
+                .get(&row.creator)
+                .cloned()
+                .unwrap_or_else(|| json!({"did": &row.creator}));
+            MeetingView::build(row, creator)
+        })
+        .collect::<Vec<_>>();
+
+    Ok(ok(json!({
+        "rows": views,
+        "page": query.page,
+        "perPage": query.per_page,
+        "total": total.0
+    })))
 }
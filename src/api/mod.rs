@@ -1,19 +1,41 @@
+pub mod admin;
+pub mod debug;
 pub mod like;
 pub mod meeting;
+pub mod notification;
 pub mod proposal;
+pub mod ready;
 pub mod reply;
 pub mod repo;
+pub mod stream;
 pub mod task;
 pub mod timeline;
 pub mod vote;
 
+use std::net::{IpAddr, Ipv6Addr, SocketAddr};
+
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, Local, SecondsFormat};
 use color_eyre::eyre::{OptionExt, eyre};
+use common_x::restful::axum::{
+    Json,
+    body::{Body, to_bytes},
+    extract::{ConnectInfo, FromRequest, Request, State},
+    http::{
+        HeaderMap, HeaderName, HeaderValue,
+        header::{CONTENT_LENGTH, CONTENT_TYPE},
+    },
+    middleware::Next,
+    response::Response,
+};
+use futures::StreamExt;
 use k256::ecdsa::{Signature, VerifyingKey, signature::Verifier};
 use molecule::prelude::Entity;
 use sea_query::{Expr, ExprTrait, Order, PostgresQueryBuilder};
 use sea_query_sqlx::SqlxBinder;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use serde_json::{Value, json};
+use tracing::Instrument;
 use utoipa::{
     Modify, OpenApi, ToSchema,
     openapi::security::{ApiKey, ApiKeyValue, SecurityScheme},
@@ -23,11 +45,15 @@ use validator::Validate;
 use crate::{
     AppView,
     atproto::{NSID_PROFILE, get_record},
+    cache::RateLimiter,
+    ckb::{self, DidCellNotFound},
+    error::AppError,
     lexicon::{
         self,
+        administrator::{AdminPermission, Administrator, AdministratorRow},
         profile::{Profile, ProfileRow},
         proposal::ProposalState,
-        vote_meta::{VoteMeta, VoteMetaRow, VoteMetaState},
+        vote_meta::{VOTE_CANDIDATES, VoteMeta, VoteMetaRow, VoteMetaState},
         voter_list::{VoterList, VoterListRow},
     },
 };
@@ -37,14 +63,21 @@ use crate::{
     modifiers(&SecurityAddon),
     paths(
         repo::profile,
+        repo::activity,
+        debug::unsigned_bytes,
         proposal::list,
+        proposal::batch,
         proposal::detail,
+        proposal::reports,
+        proposal::votes,
+        proposal::progress,
         proposal::initiation_vote,
         proposal::update_receiver_addr,
         proposal::receiver_addr,
         proposal::list_self,
         proposal::replied,
         proposal::status,
+        proposal::stats,
         reply::list,
         like::list,
         vote::bind_list,
@@ -56,43 +89,82 @@ use crate::{
         vote::update_vote_tx_hash,
         vote::status,
         vote::detail,
+        vote::result,
+        vote::in_voter_list,
+        vote::verify_root,
+        vote::finalize,
         vote::list_self,
         timeline::get,
         task::get,
+        task::by_proposal,
+        task::claim,
+        task::reassign,
         task::send_funds,
         task::submit_milestone_report,
         task::submit_delay_report,
         task::create_meeting,
         task::submit_meeting_report,
+        task::cancel_meeting,
         task::submit_acceptance_report,
         task::rectification_vote,
         task::rectification,
         meeting::get,
+        notification::list,
+        notification::read,
+        admin::list,
+        admin::add,
+        admin::remove,
+        admin::reindex,
+        ready::voter_list,
+        stream::proposal,
     ),
     components(schemas(
         proposal::ProposalQuery,
+        proposal::VoteThreshold,
+        proposal::ProposalListResponse,
+        proposal::BatchQuery,
+        proposal::ProposalStatsResponse,
         SignedBody<proposal::InitiationParams>,
         SignedBody<proposal::ReceiverAddrParams>,
         reply::ReplyQuery,
         like::LikeQuery,
+        repo::ActivityResponse,
         SignedBody<vote::CreateVoteParams>,
         SignedBody<vote::UpdateTxParams>,
         SignedBody<vote::UpdateVoteTxParams>,
         vote::PrepareBody,
+        SignedBody<vote::FinalizeVoteParams>,
+        SignedBody<vote::VerifyRootParams>,
+        vote::WeightResponse,
+        vote::VoteDetailResponse,
         SignedBody<task::SendFundsParams>,
+        SignedBody<task::ClaimTaskParams>,
+        SignedBody<task::ReassignTaskParams>,
         SignedBody<task::SubmitReportParams>,
         SignedBody<task::CreateMeetingParams>,
         SignedBody<task::SubmitMeetingReportParams>,
+        SignedBody<task::CancelMeetingParams>,
         SignedBody<task::RectificationVoteParams>,
         SignedBody<task::RectificationParams>,
+        SignedBody<notification::ListNotificationParams>,
+        SignedBody<notification::ReadNotificationParams>,
+        SignedBody<admin::ListAdminParams>,
+        SignedBody<admin::AddAdminParams>,
+        SignedBody<admin::RemoveAdminParams>,
+        SignedBody<admin::ReindexParams>,
 
         // lexicon
+        lexicon::administrator::AdminPermission,
         lexicon::proposal::ProposalState,
         lexicon::task::TaskType,
         lexicon::task::TaskState,
+        lexicon::notification::NotificationType,
         lexicon::timeline::TimelineType,
         lexicon::vote_meta::VoteMetaState,
         lexicon::vote::VoteState,
+        lexicon::proposal::ProposalView,
+        lexicon::vote_meta::VoteMetaRow,
+        lexicon::vote::ViewerVote,
     ))
 )]
 pub struct ApiDoc;
@@ -119,6 +191,258 @@ impl sea_query::Iden for ToTimestamp {
     }
 }
 
+/// 改为 camelCase 之前响应中使用的 snake_case 字段名
+const LEGACY_SNAKE_CASE_KEYS: &[&str] = &[
+    "like_count",
+    "receiver_addr",
+    "vote_meta",
+    "start_time",
+    "end_time",
+    "proposal_uri",
+    "proposal_state",
+    "task_type",
+    "timeline_type",
+    "tx_hash",
+    "vote_meta_id",
+    "candidates_index",
+    "block_number",
+    "voter_list_id",
+    "root_hash",
+    "vote_sum",
+    "valid_vote_sum",
+    "valid_weight_sum",
+    "valid_votes",
+    "candidate_votes",
+    "ckb_addr",
+    "vote_index",
+    "per_page",
+    "in_progress_num",
+    "budget_amount_in_progress",
+    "smt_root_hash",
+    "smt_proof",
+    "vote_addr",
+    "onchain_candidates",
+    "candidates_matched",
+];
+
+fn to_snake_case(key: &str) -> String {
+    let mut snake = String::with_capacity(key.len() + 4);
+    for c in key.chars() {
+        if c.is_ascii_uppercase() {
+            snake.push('_');
+            snake.push(c.to_ascii_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
+
+fn restore_snake_case(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            let entries = std::mem::take(map);
+            for (key, mut v) in entries {
+                // record / message 是原样存储的数据，不做转换
+                if key != "record" && key != "message" {
+                    restore_snake_case(&mut v);
+                }
+                let snake = to_snake_case(&key);
+                if LEGACY_SNAKE_CASE_KEYS.contains(&snake.as_str()) {
+                    map.insert(snake, v);
+                } else {
+                    map.insert(key, v);
+                }
+            }
+        }
+        Value::Array(list) => list.iter_mut().for_each(restore_snake_case),
+        _ => {}
+    }
+}
+
+/// `--api-snake-case` 兼容模式：将 JSON 响应中的 camelCase 字段还原为旧的 snake_case
+pub async fn snake_case_response(response: Response) -> Response {
+    let is_json = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"));
+    if !is_json {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            error!("read response body failed: {e}");
+            return Response::from_parts(parts, Body::empty());
+        }
+    };
+    let Ok(mut value) = serde_json::from_slice::<Value>(&bytes) else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+    restore_snake_case(&mut value);
+    let bytes = serde_json::to_vec(&value).unwrap_or_else(|_| bytes.to_vec());
+    parts.headers.remove(CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(bytes))
+}
+
+pub const X_REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+/// 为每个请求分配 request id（优先沿用请求头 `X-Request-Id`），
+/// 请求内的日志都带上该 id，并通过响应头返回
+pub async fn request_id(request: Request, next: Next) -> Response {
+    let id = request
+        .headers()
+        .get(&X_REQUEST_ID)
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| !v.is_empty() && v.len() <= 64)
+        .map(str::to_owned)
+        .unwrap_or_else(|| format!("{:016x}", rand::random::<u64>()));
+    let span = info_span!(
+        "request",
+        request_id = %id,
+        method = %request.method(),
+        path = %request.uri().path(),
+    );
+    let mut response = next.run(request).instrument(span).await;
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(X_REQUEST_ID, value);
+    }
+    response
+}
+
+/// 签名写接口的限流配置，作为 `rate_limit` 中间件的状态
+#[derive(Clone)]
+pub struct RateLimit {
+    pub limiter: RateLimiter,
+    /// 部署在反向代理之后时开启，按 X-Forwarded-For 识别客户端
+    pub trusted_proxy: bool,
+}
+
+impl RateLimit {
+    pub fn new(per_minute: u32, trusted_proxy: bool) -> Self {
+        Self {
+            limiter: RateLimiter::new(per_minute),
+            trusted_proxy,
+        }
+    }
+}
+
+pub const X_FORWARDED_FOR: HeaderName = HeaderName::from_static("x-forwarded-for");
+
+/// 限流用的客户端 IP
+///
+/// 信任代理时取 X-Forwarded-For 最右一项，该项由代理追加，客户端无法伪造；
+/// 缺失或无法解析时退回对端地址
+pub fn client_ip(headers: &HeaderMap, peer: Option<IpAddr>, trusted_proxy: bool) -> Option<IpAddr> {
+    if trusted_proxy {
+        let forwarded = headers
+            .get_all(&X_FORWARDED_FOR)
+            .iter()
+            .next_back()
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.rsplit(',').next())
+            .and_then(|ip| ip.trim().parse().ok());
+        if forwarded.is_some() {
+            return forwarded;
+        }
+    }
+    peer
+}
+
+/// 限流的 key：IPv6 按 /64 计数，同一网段内轮换地址不能绕过限流
+pub fn rate_limit_key(ip: IpAddr) -> String {
+    match ip {
+        IpAddr::V4(ip) => ip.to_string(),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => ip.to_string(),
+            None => {
+                let prefix = Ipv6Addr::from(u128::from(ip) & !(u128::MAX >> 64));
+                format!("{prefix}/64")
+            }
+        },
+    }
+}
+
+/// 签名写接口的限流，按客户端 IP 计数
+///
+/// 此时签名尚未校验，body 中的 did 可随意填写，不能作为限流的 key
+pub async fn rate_limit(
+    State(config): State<RateLimit>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+    let key = client_ip(request.headers(), peer, config.trusted_proxy)
+        .map(rate_limit_key)
+        .unwrap_or_default();
+    config
+        .limiter
+        .check(&key)
+        .map_err(AppError::TooManyRequests)?;
+    Ok(next.run(request).await)
+}
+
+/// 游标为 base64(时间, uri)，时间相同时按 uri 继续翻页
+pub fn encode_cursor(updated: &DateTime<Local>, uri: &str) -> String {
+    URL_SAFE_NO_PAD.encode(format!(
+        "{},{uri}",
+        updated.to_rfc3339_opts(SecondsFormat::Micros, true)
+    ))
+}
+
+pub fn decode_cursor(cursor: &str) -> Option<(String, String)> {
+    let cursor = String::from_utf8(URL_SAFE_NO_PAD.decode(cursor).ok()?).ok()?;
+    let (updated, uri) = cursor.split_once(',')?;
+    DateTime::parse_from_rfc3339(updated).ok()?;
+    Some((updated.to_string(), uri.to_string()))
+}
+
+const AUTHOR_FILL_CONCURRENCY: usize = 8;
+
+async fn resolve_author(state: &AppView, repo: &str, profile: Option<Value>) -> (String, Value) {
+    let mut author = match profile {
+        Some(profile) => profile,
+        None => {
+            if let Ok(profile) =
+                get_record(&state.pds, state.pds_timeout, repo, NSID_PROFILE, "self")
+                    .await
+                    .and_then(|row| row.get("value").cloned().ok_or_eyre("NOT_FOUND"))
+                && Profile::validate(&profile).is_ok()
+            {
+                Profile::insert(&state.db, repo, profile.clone()).await.ok();
+                profile
+            } else {
+                json!({"did": repo})
+            }
+        }
+    };
+    author["did"] = Value::String(repo.to_owned());
+    let ckb_addr = if state.ckb_addr_cooldown.is_active() {
+        // ckb node recently failed, only serve what is already cached
+        state.ckb_addr_cache.get(ckb::strip_did_prefix(repo))
+    } else {
+        match state.ckb_addr_by_did(repo).await {
+            Ok(ckb_addr) => Some(ckb_addr),
+            Err(e) if e.downcast_ref::<DidCellNotFound>().is_some() => None,
+            Err(e) => {
+                warn!("resolve ckb address of {repo} failed, pause enrichment: {e}");
+                state.ckb_addr_cooldown.trip();
+                None
+            }
+        }
+    };
+    if let Some(ckb_addr) = ckb_addr {
+        author["ckbAddr"] = Value::String(ckb_addr);
+    }
+    (repo.to_owned(), author)
+}
+
 pub async fn build_author(state: &AppView, repo: &str) -> Value {
     let mut authors = build_authors(state, &[repo]).await;
     authors.remove(repo).unwrap_or_else(|| json!({"did": repo}))
@@ -129,6 +453,17 @@ pub async fn build_authors(
     repos: &[&str],
 ) -> std::collections::HashMap<String, Value> {
     let mut result = std::collections::HashMap::new();
+    let repos: Vec<&str> = repos
+        .iter()
+        .copied()
+        .filter(|repo| match state.author_cache.get(repo) {
+            Some(author) => {
+                result.insert((*repo).to_string(), author);
+                false
+            }
+            None => true,
+        })
+        .collect();
     if repos.is_empty() {
         return result;
     }
@@ -137,79 +472,128 @@ pub async fn build_authors(
     let (sql, values) = Profile::build_select()
         .and_where(Expr::col(Profile::Did).is_in(repos.iter().copied()))
         .build_sqlx(PostgresQueryBuilder);
-    let db_profiles: Vec<ProfileRow> = sqlx::query_as_with(&sql, values)
-        .fetch_all(&state.db)
-        .await
-        .unwrap_or_default();
-
-    for profile_row in db_profiles {
-        let repo = profile_row.did;
-        let mut author = profile_row.profile;
-        author["did"] = Value::String(repo.clone());
-        if let Ok(ckb_addr) = crate::ckb::get_ckb_addr_by_did(
-            &state.ckb_client,
-            &state.ckb_net,
-            repo.strip_prefix("did:web5")
-                .unwrap_or(&repo)
-                .strip_prefix("did:ckb")
-                .unwrap_or(&repo)
-                .strip_prefix("did:plc")
-                .unwrap_or(&repo),
-        )
-        .await
-        {
-            author["ckb_addr"] = Value::String(ckb_addr);
-        }
-        result.insert(repo, author);
-    }
-
-    // Fallback to individual fetch for missing profiles
-    for repo in repos {
-        if !result.contains_key(*repo) {
-            let mut author = if let Ok(profile) = get_record(&state.pds, repo, NSID_PROFILE, "self")
-                .await
-                .and_then(|row| row.get("value").cloned().ok_or_eyre("NOT_FOUND"))
-            {
-                Profile::insert(&state.db, repo, profile.clone()).await.ok();
-                profile
-            } else {
-                json!({"did": repo})
-            };
-            author["did"] = Value::String((*repo).to_owned());
-            if let Ok(ckb_addr) = crate::ckb::get_ckb_addr_by_did(
-                &state.ckb_client,
-                &state.ckb_net,
-                repo.strip_prefix("did:web5")
-                    .unwrap_or(repo)
-                    .strip_prefix("did:ckb")
-                    .unwrap_or(repo)
-                    .strip_prefix("did:plc")
-                    .unwrap_or(repo),
-            )
+    let mut db_profiles: std::collections::HashMap<String, Value> =
+        sqlx::query_as_with::<_, ProfileRow, _>(&sql, values)
+            .fetch_all(&state.db)
             .await
-            {
-                author["ckb_addr"] = Value::String(ckb_addr);
-            }
-            result.insert((*repo).to_string(), author);
-        }
-    }
+            .unwrap_or_default()
+            .into_iter()
+            .map(|row| (row.did, row.profile))
+            .collect();
+
+    // Resolve the rest (PDS fallback + ckb address) concurrently, bounded
+    let mut seen = std::collections::HashSet::new();
+    let tasks = repos
+        .iter()
+        .filter(|repo| seen.insert(**repo))
+        .map(|repo| (*repo, db_profiles.remove(*repo)))
+        .collect::<Vec<_>>();
+    let resolved: std::collections::HashMap<String, Value> = futures::stream::iter(tasks)
+        .map(|(repo, profile)| resolve_author(state, repo, profile))
+        .buffer_unordered(AUTHOR_FILL_CONCURRENCY)
+        .collect()
+        .await;
 
+    state.author_cache.insert_many(resolved.clone());
+    result.extend(resolved);
     result
 }
 
-pub trait SignedParam: Default + ToSchema + Serialize + Validate {
+pub trait SignedParam: ToSchema + Serialize + Validate {
     fn timestamp(&self) -> i64;
 }
+/// 签名请求体；`validate` 会一并校验 `params` 的字段规则
 #[derive(Default, ToSchema, Serialize, Deserialize, Validate)]
-pub struct SignedBody<SignedParam> {
-    pub params: SignedParam,
+pub struct SignedBody<T: Validate> {
+    #[validate(nested)]
+    pub params: T,
     pub did: String,
     pub signing_key_did: String,
     pub signed_bytes: String,
 }
 
+/// multicodec: secp256k1-pub
+const MULTICODEC_SECP256K1_PUB: u64 = 0xe7;
+/// multicodec: ed25519-pub
+const MULTICODEC_ED25519_PUB: u64 = 0xed;
+
+enum DidKey {
+    Secp256k1(VerifyingKey),
+    Ed25519(ed25519_dalek::VerifyingKey),
+}
+
+impl DidKey {
+    fn verify(&self, message: &[u8], signature: &[u8]) -> color_eyre::Result<()> {
+        match self {
+            DidKey::Secp256k1(key) => {
+                let signature = Signature::from_slice(signature)
+                    .map_err(|e| eyre!("invalid secp256k1 signature: {e}"))?;
+                key.verify(message, &signature)
+                    .map_err(|e| eyre!("verify signature failed: {e}"))
+            }
+            DidKey::Ed25519(key) => {
+                let signature = ed25519_dalek::Signature::from_slice(signature)
+                    .map_err(|e| eyre!("invalid ed25519 signature: {e}"))?;
+                key.verify_strict(message, &signature)
+                    .map_err(|e| eyre!("verify signature failed: {e}"))
+            }
+        }
+    }
+}
+
+/// 读取 unsigned varint，返回值与占用的字节数
+fn read_varint(bytes: &[u8]) -> Option<(u64, usize)> {
+    let mut value = 0u64;
+    for (i, byte) in bytes.iter().enumerate().take(9) {
+        value |= u64::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Some((value, i + 1));
+        }
+    }
+    None
+}
+
+/// 解析 `did:key:z...`，支持 secp256k1（压缩与非压缩 SEC1）与 ed25519 公钥
+fn parse_did_key(did: &str) -> color_eyre::Result<DidKey> {
+    let multibase = did
+        .strip_prefix("did:key:")
+        .ok_or_else(|| eyre!("signing_key_did is not a did:key: {did}"))?;
+    let encoded = multibase
+        .strip_prefix('z')
+        .ok_or_eyre("signing_key_did must be base58btc encoded (multibase 'z')")?;
+    let bytes = bs58::decode(encoded)
+        .into_vec()
+        .map_err(|e| eyre!("signing_key_did is not valid base58: {e}"))?;
+    let (codec, prefix_len) =
+        read_varint(&bytes).ok_or_eyre("signing_key_did has a malformed multicodec prefix")?;
+    let key = &bytes[prefix_len..];
+    match codec {
+        MULTICODEC_SECP256K1_PUB => VerifyingKey::from_sec1_bytes(key)
+            .map(DidKey::Secp256k1)
+            .map_err(|e| eyre!("signing_key_did has an invalid secp256k1 public key: {e}")),
+        MULTICODEC_ED25519_PUB => {
+            let key: &[u8; 32] = key.try_into().map_err(|_| {
+                eyre!(
+                    "signing_key_did has an invalid ed25519 public key length: {}",
+                    key.len()
+                )
+            })?;
+            ed25519_dalek::VerifyingKey::from_bytes(key)
+                .map(DidKey::Ed25519)
+                .map_err(|e| eyre!("signing_key_did has an invalid ed25519 public key: {e}"))
+        }
+        _ => Err(eyre!(
+            "signing_key_did uses unsupported key type (multicodec 0x{codec:x})"
+        )),
+    }
+}
+
 impl<T: SignedParam> SignedBody<T> {
-    pub async fn verify_signature(&self, indexer_did_url: &str) -> color_eyre::Result<()> {
+    pub async fn verify_signature(
+        &self,
+        indexer_did_url: &str,
+        indexer_timeout: std::time::Duration,
+    ) -> color_eyre::Result<()> {
         // verify timestamp
         let timestamp =
             chrono::DateTime::from_timestamp_secs(self.params.timestamp()).unwrap_or_default();
@@ -220,7 +604,7 @@ impl<T: SignedParam> SignedBody<T> {
         }
 
         // verify did
-        let did_doc = crate::indexer_did::did_document(indexer_did_url, &self.did)
+        let did_doc = crate::indexer_did::did_document(indexer_did_url, indexer_timeout, &self.did)
             .await
             .map_err(|e| eyre!("get did doc failed: {e}"))?;
 
@@ -234,21 +618,63 @@ impl<T: SignedParam> SignedBody<T> {
         }
 
         // verify signature
-        let verifying_key: VerifyingKey = self
-            .signing_key_did
-            .split_once("did:key:z")
-            .and_then(|(_, key)| {
-                let bytes = bs58::decode(key).into_vec().ok()?;
-                VerifyingKey::from_sec1_bytes(&bytes[2..]).ok()
-            })
-            .ok_or_eyre("invalid signing_key_did")?;
-        let signature = hex::decode(self.signed_bytes.clone())
-            .map(|bytes| Signature::from_slice(&bytes).map_err(|e| eyre!(e)))??;
+        let did_key = parse_did_key(&self.signing_key_did)?;
+        let signature = hex::decode(&self.signed_bytes)
+            .map_err(|e| eyre!("signed_bytes is not valid hex: {e}"))?;
 
         let unsigned_bytes = serde_ipld_dagcbor::to_vec(&self.params)?;
-        verifying_key
-            .verify(&unsigned_bytes, &signature)
-            .map_err(|e| eyre!("verify signature failed: {e}"))
+        did_key.verify(&unsigned_bytes, &signature)
+    }
+}
+
+/// 管理员签名请求：在进入 handler 前完成参数校验、管理员身份、权限位与签名校验，
+/// 默认要求 `AdminPermission::Process`
+pub struct AdminSignedBody<T: Validate, const PERMISSION: i32 = { AdminPermission::Process as i32 }>(
+    pub SignedBody<T>,
+);
+
+/// 管理管理员的签名请求，要求 `AdminPermission::Manage`
+pub type ManagerSignedBody<T> = AdminSignedBody<T, { AdminPermission::Manage as i32 }>;
+
+/// 调用方须为管理员且拥有 `permission` 中的权限位
+fn check_admin_permission(
+    admin_row: Option<&AdministratorRow>,
+    permission: i32,
+) -> Result<(), AppError> {
+    let admin_row =
+        admin_row.ok_or_else(|| AppError::ValidateFailed("not administrator".to_string()))?;
+    if admin_row.permission & permission == 0 {
+        return Err(AppError::ValidateFailed(
+            "administrator lacks the required permission".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+impl<T, const PERMISSION: i32> FromRequest<AppView> for AdminSignedBody<T, PERMISSION>
+where
+    T: SignedParam + DeserializeOwned + Send,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &AppView) -> Result<Self, Self::Rejection> {
+        let Json(body) = Json::<SignedBody<T>>::from_request(req, state)
+            .await
+            .map_err(|e| AppError::ValidateFailed(e.body_text()))?;
+
+        body.validate()
+            .map_err(|e| AppError::ValidateFailed(e.to_string()))?;
+
+        let admin_row = Administrator::get(&state.db, &body.did)
+            .await
+            .map_err(|e| AppError::ExecSqlFailed(e.to_string()))?;
+        check_admin_permission(admin_row.as_ref(), PERMISSION)?;
+
+        body.verify_signature(&state.indexer_did_url, state.indexer_timeout)
+            .await
+            .map_err(|e| AppError::ValidateFailed(e.to_string()))?;
+
+        Ok(Self(body))
     }
 }
 
@@ -291,16 +717,18 @@ pub async fn create_vote_tx(
             block_number: None,
             proposal_uri: proposal_uri.to_string(),
             voter_list_id: voter_list_row.id,
-            candidates: vec![
-                "Abstain".to_string(),
-                "Agree".to_string(),
-                "Against".to_string(),
-            ],
+            candidates: VOTE_CANDIDATES.map(str::to_string).to_vec(),
             start_time: 0,
             end_time: 0,
             creator: creator.to_string(),
             results: None,
             created: chrono::Local::now(),
+            duration_days: Some(
+                state
+                    .governance
+                    .vote_duration_days(proposal_state as i32)
+                    .try_into()?,
+            ),
         };
 
         vote_meta_row.id = VoteMeta::insert(&state.db, &vote_meta_row).await?;
@@ -318,7 +746,163 @@ pub async fn create_vote_tx(
         vec![]
     };
     Ok(json!({
-        "vote_meta": vote_meta_row,
+        "voteMeta": vote_meta_row,
         "outputsData": outputs_data
     }))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn admin(permission: i32) -> AdministratorRow {
+        AdministratorRow {
+            did: "did:plc:admin".to_string(),
+            permission,
+        }
+    }
+
+    #[test]
+    fn admin_permission_rejects_non_admin() {
+        let result = check_admin_permission(None, AdminPermission::Process as i32);
+        assert!(matches!(result, Err(AppError::ValidateFailed(msg)) if msg == "not administrator"));
+    }
+
+    #[test]
+    fn admin_permission_checks_bits() {
+        let process = AdminPermission::Process as i32;
+        let manage = AdminPermission::Manage as i32;
+        assert!(check_admin_permission(Some(&admin(process)), process).is_ok());
+        assert!(check_admin_permission(Some(&admin(process)), manage).is_err());
+        assert!(check_admin_permission(Some(&admin(manage)), process).is_err());
+        assert!(check_admin_permission(Some(&admin(process | manage)), manage).is_ok());
+    }
+
+    #[test]
+    fn cursor_round_trips() {
+        let updated = DateTime::parse_from_rfc3339("2026-01-02T03:04:05.123456Z")
+            .unwrap()
+            .with_timezone(&Local);
+        let uri = "at://did:plc:abc/app.dao.proposal/3k,x";
+        let (decoded_updated, decoded_uri) = decode_cursor(&encode_cursor(&updated, uri)).unwrap();
+        assert_eq!(
+            DateTime::parse_from_rfc3339(&decoded_updated).unwrap(),
+            updated
+        );
+        assert_eq!(decoded_uri, uri);
+    }
+
+    #[test]
+    fn cursor_rejects_garbage() {
+        assert!(decode_cursor("not base64!").is_none());
+        assert!(decode_cursor(&URL_SAFE_NO_PAD.encode("no separator")).is_none());
+        assert!(decode_cursor(&URL_SAFE_NO_PAD.encode("yesterday,at://x")).is_none());
+    }
+
+    fn did_key(codec: &[u8], key: &[u8]) -> String {
+        let bytes = [codec, key].concat();
+        format!("did:key:z{}", bs58::encode(bytes).into_string())
+    }
+
+    #[test]
+    fn parse_did_key_secp256k1() {
+        let key = k256::ecdsa::SigningKey::from_slice(&[1u8; 32]).unwrap();
+        let sec1 = key.verifying_key().to_sec1_bytes();
+        let did = did_key(&[0xe7, 0x01], &sec1);
+        assert!(
+            matches!(parse_did_key(&did), Ok(DidKey::Secp256k1(k)) if &k == key.verifying_key())
+        );
+    }
+
+    #[test]
+    fn parse_did_key_ed25519() {
+        let key = ed25519_dalek::SigningKey::from_bytes(&[1u8; 32]).verifying_key();
+        let did = did_key(&[0xed, 0x01], key.as_bytes());
+        assert!(matches!(parse_did_key(&did), Ok(DidKey::Ed25519(k)) if k == key));
+    }
+
+    #[test]
+    fn parse_did_key_rejects_invalid() {
+        assert!(parse_did_key("did:plc:abc").is_err());
+        assert!(parse_did_key("did:key:m123").is_err());
+        assert!(parse_did_key("did:key:z0OIl").is_err());
+        assert!(parse_did_key(&did_key(&[0xed, 0x01], &[1u8; 31])).is_err());
+        assert!(parse_did_key(&did_key(&[0x12, 0x00], &[1u8; 32])).is_err());
+    }
+
+    #[test]
+    fn rate_limit_key_masks_ipv6_to_64() {
+        let a: IpAddr = "2001:db8:1:2:aaaa::1".parse().unwrap();
+        let b: IpAddr = "2001:db8:1:2:bbbb::2".parse().unwrap();
+        let other: IpAddr = "2001:db8:1:3::1".parse().unwrap();
+        assert_eq!(rate_limit_key(a), "2001:db8:1:2::/64");
+        assert_eq!(rate_limit_key(a), rate_limit_key(b));
+        assert_ne!(rate_limit_key(a), rate_limit_key(other));
+        assert_eq!(rate_limit_key("192.0.2.1".parse().unwrap()), "192.0.2.1");
+        assert_eq!(
+            rate_limit_key("::ffff:192.0.2.1".parse().unwrap()),
+            "192.0.2.1"
+        );
+    }
+
+    #[test]
+    fn client_ip_honours_forwarded_for_only_behind_trusted_proxy() {
+        let peer: Option<IpAddr> = Some("10.0.0.1".parse().unwrap());
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            X_FORWARDED_FOR,
+            HeaderValue::from_static("198.51.100.7, 203.0.113.9"),
+        );
+        assert_eq!(client_ip(&headers, peer, false), peer);
+        // 最右一项由代理追加，左侧的值可被客户端伪造
+        assert_eq!(
+            client_ip(&headers, peer, true),
+            Some("203.0.113.9".parse().unwrap())
+        );
+        headers.insert(X_FORWARDED_FOR, HeaderValue::from_static("garbage"));
+        assert_eq!(client_ip(&headers, peer, true), peer);
+        assert_eq!(client_ip(&HeaderMap::new(), peer, true), peer);
+    }
+
+    #[test]
+    fn snake_case_restores_only_legacy_keys() {
+        let mut value = json!({
+            "uri": "at://did:plc:a/app.dao.proposal/1",
+            "author": { "displayName": "a", "ckbAddr": "ckb1a" },
+            "record": { "data": { "proposalType": "x", "startTime": "t" } },
+            "receiverAddr": "ckb1b",
+            "likeCount": "2",
+            "voteResult": 1,
+            "voteMeta": {
+                "proposalUri": "at://did:plc:a/app.dao.proposal/1",
+                "txHash": "0x00",
+                "startTime": 1,
+            },
+        });
+        restore_snake_case(&mut value);
+        assert_eq!(
+            value,
+            json!({
+                "uri": "at://did:plc:a/app.dao.proposal/1",
+                "author": { "displayName": "a", "ckb_addr": "ckb1a" },
+                "record": { "data": { "proposalType": "x", "startTime": "t" } },
+                "receiver_addr": "ckb1b",
+                "like_count": "2",
+                "voteResult": 1,
+                "vote_meta": {
+                    "proposal_uri": "at://did:plc:a/app.dao.proposal/1",
+                    "tx_hash": "0x00",
+                    "start_time": 1,
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn signed_body_validates_params() {
+        let mut body = SignedBody::<admin::ReindexParams>::default();
+        assert!(body.validate().is_err());
+        body.params.repo = "did:plc:a".to_string();
+        assert!(body.validate().is_ok());
+    }
+}
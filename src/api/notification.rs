@@ -0,0 +1,152 @@
+use color_eyre::eyre::eyre;
+use common_x::restful::{
+    axum::{Json, extract::State, response::IntoResponse},
+    ok,
+};
+use sea_query::{Expr, ExprTrait, Order, PostgresQueryBuilder};
+use sea_query_sqlx::SqlxBinder;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::{
+    AppView,
+    api::{SignedBody, SignedParam},
+    error::AppError,
+    lexicon::notification::{Notification, NotificationRow},
+};
+
+#[derive(Debug, Validate, Deserialize, Serialize, ToSchema)]
+#[serde(default)]
+pub struct ListNotificationParams {
+    /// 只返回未读通知
+    pub unread_only: bool,
+    #[validate(range(min = 1))]
+    pub page: u64,
+    #[validate(range(min = 1))]
+    pub per_page: u64,
+    pub timestamp: i64,
+}
+
+impl Default for ListNotificationParams {
+    fn default() -> Self {
+        Self {
+            unread_only: false,
+            page: 1,
+            per_page: 20,
+            timestamp: 0,
+        }
+    }
+}
+
+impl SignedParam for ListNotificationParams {
+    fn timestamp(&self) -> i64 {
+        self.timestamp
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/notification/list",
+    description = "自己的通知列表",
+    request_body = SignedBody<ListNotificationParams>
+)]
+pub async fn list(
+    State(state): State<AppView>,
+    Json(body): Json<SignedBody<ListNotificationParams>>,
+) -> Result<impl IntoResponse, AppError> {
+    body.validate()
+        .map_err(|e| AppError::ValidateFailed(e.to_string()))?;
+
+    body.verify_signature(&state.indexer_did_url, state.indexer_timeout)
+        .await
+        .map_err(|e| AppError::ValidateFailed(e.to_string()))?;
+
+    let params = &body.params;
+    let offset = params.per_page * (params.page - 1);
+
+    let (sql, values) = sea_query::Query::select()
+        .columns([
+            Notification::Id,
+            Notification::Recipient,
+            Notification::NotificationType,
+            Notification::Payload,
+            Notification::Read,
+            Notification::Created,
+        ])
+        .from(Notification::Table)
+        .and_where(Expr::col(Notification::Recipient).eq(&body.did))
+        .and_where_option(
+            params
+                .unread_only
+                .then(|| Expr::col(Notification::Read).eq(false)),
+        )
+        .order_by(Notification::Created, Order::Desc)
+        .order_by(Notification::Id, Order::Desc)
+        .limit(std::cmp::min(params.per_page, 100))
+        .offset(offset)
+        .build_sqlx(PostgresQueryBuilder);
+    let rows: Vec<NotificationRow> = sqlx::query_as_with(&sql, values)
+        .fetch_all(&state.db)
+        .await
+        .map_err(|e| eyre!("exec sql failed: {e}"))?;
+
+    // unread 始终为未读总数，不受 unread_only 影响
+    let (sql, values) = sea_query::Query::select()
+        .expr(Expr::col(Notification::Id).count())
+        .expr(Expr::cust("count(*) FILTER (WHERE NOT \"read\")"))
+        .from(Notification::Table)
+        .and_where(Expr::col(Notification::Recipient).eq(&body.did))
+        .build_sqlx(PostgresQueryBuilder);
+    let (total, unread): (i64, i64) = sqlx::query_as_with(&sql, values)
+        .fetch_one(&state.db)
+        .await
+        .map_err(|e| eyre!("exec sql failed: {e}"))?;
+
+    Ok(ok(json!({
+        "notifications": rows,
+        "unread": unread,
+        "page": params.page,
+        "perPage": params.per_page,
+        "total": if params.unread_only { unread } else { total },
+    })))
+}
+
+#[derive(Debug, Default, Validate, Deserialize, Serialize, ToSchema)]
+#[serde(default)]
+pub struct ReadNotificationParams {
+    /// 要标记已读的通知 id，为空时标记全部
+    pub ids: Vec<i32>,
+    pub timestamp: i64,
+}
+
+impl SignedParam for ReadNotificationParams {
+    fn timestamp(&self) -> i64 {
+        self.timestamp
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/notification/read",
+    description = "将自己的通知标记为已读",
+    request_body = SignedBody<ReadNotificationParams>
+)]
+pub async fn read(
+    State(state): State<AppView>,
+    Json(body): Json<SignedBody<ReadNotificationParams>>,
+) -> Result<impl IntoResponse, AppError> {
+    body.validate()
+        .map_err(|e| AppError::ValidateFailed(e.to_string()))?;
+
+    body.verify_signature(&state.indexer_did_url, state.indexer_timeout)
+        .await
+        .map_err(|e| AppError::ValidateFailed(e.to_string()))?;
+
+    let updated = Notification::mark_read(&state.db, &body.did, &body.params.ids)
+        .await
+        .map_err(|e| AppError::ExecSqlFailed(e.to_string()))?;
+
+    Ok(ok(json!({ "updated": updated })))
+}
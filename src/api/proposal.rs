@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use color_eyre::eyre::eyre;
 use common_x::restful::{
     axum::{
@@ -7,7 +9,7 @@ use common_x::restful::{
     },
     ok, ok_simple,
 };
-use sea_query::{BinOper, Expr, ExprTrait, Func, Order, PostgresQueryBuilder};
+use sea_query::{Expr, ExprTrait, Order, PostgresQueryBuilder};
 use sea_query_sqlx::SqlxBinder;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
@@ -17,82 +19,149 @@ use validator::Validate;
 
 use crate::{
     AppView,
-    api::{SignedBody, SignedParam, ToTimestamp, build_author, build_authors, create_vote_tx},
+    api::{
+        AdminSignedBody, SignedBody, SignedParam, build_author, build_authors, create_vote_tx,
+        decode_cursor, encode_cursor, vote,
+    },
     error::AppError,
+    governance::GovernanceParams,
     lexicon::{
-        administrator::{Administrator, AdministratorRow},
+        administrator::Administrator,
         meeting::{Meeting, MeetingRow, MeetingState},
-        proposal::{Proposal, ProposalRow, ProposalSample, ProposalState, ProposalView},
+        proposal::{
+            Proposal, ProposalData, ProposalRecord, ProposalRow, ProposalSample, ProposalState,
+            ProposalView,
+        },
         reply::{Reply, ReplySampleRow},
         task::{Task, TaskRow, TaskState, TaskType},
-        timeline::{Timeline, TimelineRow, TimelineType},
-        vote_meta::{VoteMeta, VoteMetaRow, VoteMetaState, VoteResult, VoteResults},
+        timeline::{Timeline, TimelineRow, TimelineType, TimelineView},
+        vote_meta::{
+            VOTE_CANDIDATES, VoteMeta, VoteMetaRow, VoteMetaState, VoteResult, VoteResults,
+        },
     },
 };
 
 #[derive(Debug, Validate, Deserialize, ToSchema)]
 #[serde(default)]
 pub struct ProposalQuery {
-    /// pagination cursor (usually timestamp of the last item seen)
+    /// pagination cursor (opaque, returned by the previous page)
     pub cursor: Option<String>,
     /// number of items to return
     pub limit: u64,
-    /// search keyword
+    /// search keyword, results are ranked by relevance and not paginated by cursor
     pub q: Option<String>,
-    /// filter by state
-    pub state: Option<i32>,
+    /// filter by state, a single state or a list of states
+    #[serde(deserialize_with = "deserialize_states")]
+    pub state: Vec<i32>,
     /// filter by user's DID
     pub repo: Option<String>,
     /// viewer's DID
     pub viewer: Option<String>,
 }
 
+fn deserialize_states<'de, D>(deserializer: D) -> Result<Vec<i32>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum States {
+        One(i32),
+        Many(Vec<i32>),
+    }
+    Ok(match Option::<States>::deserialize(deserializer)? {
+        None => vec![],
+        Some(States::One(state)) => vec![state],
+        Some(States::Many(states)) => states,
+    })
+}
+
 impl Default for ProposalQuery {
     fn default() -> Self {
         Self {
             cursor: None,
             limit: 20,
             q: None,
-            state: None,
+            state: vec![],
             repo: None,
             viewer: None,
         }
     }
 }
 
-#[utoipa::path(post, path = "/api/proposal/list")]
+/// 提案列表响应，`cursor` 在没有更多数据或按 `q` 搜索时省略
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ProposalListResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+    pub proposals: Vec<ProposalView>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/proposal/list",
+    responses((status = 200, body = ProposalListResponse))
+)]
 pub async fn list(
     State(state): State<AppView>,
     Json(query): Json<ProposalQuery>,
 ) -> Result<impl IntoResponse, AppError> {
-    let (sql, values) = Proposal::build_select(query.viewer)
+    if let Some(state) = query.state.iter().find(|s| !ProposalState::is_known(**s)) {
+        return Err(AppError::ValidateFailed(format!(
+            "unknown proposal state: {state}"
+        )));
+    }
+    let mut select = Proposal::build_select(query.viewer);
+    select
         .and_where_option(
             query
                 .repo
                 .map(|repo| Expr::col((Proposal::Table, Proposal::Repo)).eq(repo)),
         )
         .and_where_option(
-            query
-                .state
-                .map(|state| Expr::col((Proposal::Table, Proposal::State)).eq(state)),
-        )
-        .and_where_option(
-            query
-                .cursor
-                .and_then(|cursor| cursor.parse::<i64>().ok())
-                .map(|cursor| {
-                    Expr::col((Proposal::Table, Proposal::Updated)).binary(
-                        BinOper::SmallerThan,
-                        Func::cust(ToTimestamp).args([Expr::val(cursor)]),
-                    )
-                }),
-        )
-        .and_where_option(
-            query
-                .q
-                .map(|q| Expr::cust_with_values("record #>> '{{data,title}}' like CONCAT('%', $1, '%') or record #>> '{{data,goals}}' like CONCAT('%', $2, '%') or record #>> '{{data,team}}' like CONCAT('%', $3, '%')", [&q, &q, &q])),
-        )
-        .order_by(Proposal::Updated, Order::Desc)
+            (!query.state.is_empty())
+                .then(|| Expr::col((Proposal::Table, Proposal::State)).is_in(query.state)),
+        );
+    let q = query.q.filter(|q| !q.trim().is_empty());
+    let cursor = query.cursor.filter(|c| !c.is_empty());
+    // 搜索结果按相关度排序，(updated, uri) 游标无法续页
+    if q.is_some() && cursor.is_some() {
+        return Err(AppError::ValidateFailed(
+            "cursor is not supported together with q".to_string(),
+        ));
+    }
+    if let Some(cursor) = cursor {
+        let (updated, uri) = decode_cursor(&cursor)
+            .ok_or_else(|| AppError::ValidateFailed("invalid cursor".to_string()))?;
+        select.and_where(Expr::cust_with_values(
+            "(\"proposal\".\"updated\", \"proposal\".\"uri\") < ($1::timestamptz, $2)",
+            [updated, uri],
+        ));
+    }
+    if let Some(q) = &q {
+        // 'simple' 分词按空白切分，中文等无空格的标题再按子串匹配
+        let pattern = format!(
+            "%{}%",
+            q.replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_")
+        );
+        select
+            .and_where(Expr::cust_with_values(
+                "(\"proposal\".\"search\" @@ plainto_tsquery('simple', $1) OR concat_ws(' ', \"proposal\".\"record\"->'data'->>'title', \"proposal\".\"record\"->'data'->>'goals', \"proposal\".\"record\"->'data'->>'team') ILIKE $2)",
+                [q, &pattern],
+            ))
+            .order_by_expr(
+                Expr::cust_with_values(
+                    "ts_rank(\"proposal\".\"search\", plainto_tsquery('simple', $1))",
+                    [q],
+                ),
+                Order::Desc,
+            );
+    }
+    let (sql, values) = select
+        .order_by((Proposal::Table, Proposal::Updated), Order::Desc)
+        .order_by((Proposal::Table, Proposal::Uri), Order::Desc)
         .limit(std::cmp::min(query.limit, 100))
         .build_sqlx(PostgresQueryBuilder);
 
@@ -113,18 +182,74 @@ pub async fn list(
             .unwrap_or_else(|| json!({"did": &row.repo}));
         views.push(ProposalView::build(row, author, None));
     }
-    let cursor = views.last().map(|r| r.updated.timestamp());
-    let result = if let Some(cursor) = cursor {
-        json!({
-            "cursor": cursor.to_string(),
-            "proposals": views
-        })
-    } else {
-        json!({
-            "proposals": views
+    let cursor = views
+        .last()
+        .filter(|_| q.is_none())
+        .map(|r| encode_cursor(&r.updated, &r.uri));
+    Ok(ok(ProposalListResponse {
+        cursor,
+        proposals: views,
+    }))
+}
+
+#[derive(Debug, Default, Validate, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct BatchQuery {
+    /// proposal uris, at most 50
+    #[validate(length(min = 1, max = 50))]
+    pub uris: Vec<String>,
+    /// viewer's DID
+    pub viewer: Option<String>,
+}
+
+/// 按 uri 批量获取提案，结果保持请求中的顺序，未找到的 uri 省略
+#[utoipa::path(
+    post,
+    path = "/api/proposal/batch",
+    responses((status = 200, body = ProposalListResponse))
+)]
+pub async fn batch(
+    State(state): State<AppView>,
+    Json(query): Json<BatchQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    query
+        .validate()
+        .map_err(|e| AppError::ValidateFailed(e.to_string()))?;
+
+    let (sql, values) = Proposal::build_select(query.viewer)
+        .and_where(
+            Expr::col((Proposal::Table, Proposal::Uri))
+                .is_in(query.uris.iter().map(String::as_str)),
+        )
+        .build_sqlx(PostgresQueryBuilder);
+    let rows: Vec<ProposalRow> = query_as_with(&sql, values)
+        .fetch_all(&state.db)
+        .await
+        .map_err(|e| AppError::ExecSqlFailed(e.to_string()))?;
+
+    let repos: Vec<&str> = rows.iter().map(|r| r.repo.as_str()).collect();
+    let authors = build_authors(&state, &repos).await;
+
+    let mut rows = rows
+        .into_iter()
+        .map(|row| (row.uri.clone(), row))
+        .collect::<HashMap<_, _>>();
+    let proposals = query
+        .uris
+        .iter()
+        .filter_map(|uri| rows.remove(uri))
+        .map(|row| {
+            let author = authors
+                .get(&row.repo)
+                .cloned()
+                .unwrap_or_else(|| json!({"did": &row.repo}));
+            ProposalView::build(row, author, None)
         })
-    };
-    Ok(ok(result))
+        .collect();
+    Ok(ok(ProposalListResponse {
+        cursor: None,
+        proposals,
+    }))
 }
 
 #[derive(Debug, Validate, Deserialize, IntoParams)]
@@ -178,7 +303,7 @@ pub async fn receiver_addr(
     Ok(ok(json!({
         "rows": rows,
         "page": query.page,
-        "per_page": query.per_page,
+        "perPage": query.per_page,
         "total":  total.0
     })))
 }
@@ -236,7 +361,7 @@ pub async fn list_self(
     Ok(ok(json!({
         "rows": rows,
         "page": query.page,
-        "per_page": query.per_page,
+        "perPage": query.per_page,
         "total":  total.0
     })))
 }
@@ -287,7 +412,7 @@ pub async fn replied(
     let proposal_map = proposals
         .into_iter()
         .map(|p| (p.uri.clone(), p))
-        .collect::<std::collections::HashMap<_, _>>();
+        .collect::<HashMap<_, _>>();
 
     let mut views = vec![];
     for reply in replies {
@@ -311,7 +436,7 @@ pub async fn replied(
     Ok(ok(json!({
         "rows": views,
         "page": query.page,
-        "per_page": query.per_page,
+        "perPage": query.per_page,
         "total":  total.0
     })))
 }
@@ -335,8 +460,8 @@ pub async fn detail(
         .validate()
         .map_err(|e| AppError::ValidateFailed(e.to_string()))?;
 
-    let (sql, values) = Proposal::build_select(query.viewer)
-        .and_where(Expr::col(Proposal::Uri).eq(query.uri))
+    let (sql, values) = Proposal::build_select(query.viewer.clone())
+        .and_where(Expr::col(Proposal::Uri).eq(&query.uri))
         .build_sqlx(PostgresQueryBuilder);
 
     let row: ProposalRow = query_as_with(&sql, values.clone())
@@ -364,12 +489,187 @@ pub async fn detail(
         .await
         .ok();
 
+    let viewer_vote = match (&query.viewer, &vote_meta_row) {
+        (Some(viewer), Some(vote_meta_row))
+            if VoteMetaState::from(vote_meta_row.state) == VoteMetaState::Committed =>
+        {
+            vote::viewer_vote(&state, vote_meta_row, viewer)
+                .await
+                .map_err(|e| debug!("resolve viewer vote failed: {e}"))
+                .ok()
+        }
+        _ => None,
+    };
+
     let author = build_author(&state, &row.repo).await;
-    let view = ProposalView::build(row, author, vote_meta_row);
+    let mut view = ProposalView::build(row, author, vote_meta_row);
+    view.vote_result = view
+        .vote_meta
+        .as_ref()
+        .map(|vote_meta| vote_result(vote_meta, &view.record, &state.governance) as u32);
+    view.viewer_vote = viewer_vote;
 
     Ok(ok(view))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/proposal/reports",
+    params(UriQuery),
+    description = "提案的里程碑/延期/验收报告"
+)]
+pub async fn reports(
+    State(state): State<AppView>,
+    Query(query): Query<UriQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    query
+        .validate()
+        .map_err(|e| AppError::ValidateFailed(e.to_string()))?;
+
+    let (sql, values) = sea_query::Query::select()
+        .columns([
+            (Timeline::Table, Timeline::Id),
+            (Timeline::Table, Timeline::TimelineType),
+            (Timeline::Table, Timeline::Message),
+            (Timeline::Table, Timeline::Target),
+            (Timeline::Table, Timeline::Operator),
+            (Timeline::Table, Timeline::Timestamp),
+        ])
+        .from(Timeline::Table)
+        .and_where(Expr::col(Timeline::Target).eq(query.uri))
+        .and_where(Expr::col(Timeline::TimelineType).is_in([
+            TimelineType::SubmitMilestoneReport as i32,
+            TimelineType::SubmitDelayReport as i32,
+            TimelineType::SubmitAcceptanceReport as i32,
+        ]))
+        .order_by(Timeline::Timestamp, Order::Asc)
+        .build_sqlx(PostgresQueryBuilder);
+    let rows: Vec<TimelineRow> = query_as_with(&sql, values)
+        .fetch_all(&state.db)
+        .await
+        .map_err(|e| eyre!("exec sql failed: {e}"))?;
+
+    let repos: Vec<&str> = rows.iter().map(|r| r.operator.as_str()).collect();
+    let authors = build_authors(&state, &repos).await;
+
+    // message 即报告链接，operator 为提交人
+    let views = rows
+        .into_iter()
+        .map(|row| TimelineView {
+            id: row.id,
+            timeline_type: row.timeline_type,
+            operator: authors
+                .get(&row.operator)
+                .cloned()
+                .unwrap_or_else(|| json!({"did": &row.operator})),
+            message: TimelineView::parse_message(row.message),
+            target: row.target,
+            timestamp: row.timestamp,
+        })
+        .collect::<Vec<_>>();
+
+    Ok(ok(views))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/proposal/votes",
+    params(UriQuery),
+    description = "提案历次投票（立项/里程碑/延期等）及结果"
+)]
+pub async fn votes(
+    State(state): State<AppView>,
+    Query(query): Query<UriQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    query
+        .validate()
+        .map_err(|e| AppError::ValidateFailed(e.to_string()))?;
+
+    let (sql, value) = Proposal::build_sample()
+        .and_where(Expr::col(Proposal::Uri).eq(&query.uri))
+        .build_sqlx(PostgresQueryBuilder);
+    let proposal_sample: ProposalSample = query_as_with(&sql, value)
+        .fetch_one(&state.db)
+        .await
+        .map_err(|_| AppError::NotFound)?;
+
+    let (sql, value) = VoteMeta::build_select()
+        .and_where(Expr::col(VoteMeta::ProposalUri).eq(&query.uri))
+        .order_by(VoteMeta::Created, Order::Asc)
+        .build_sqlx(PostgresQueryBuilder);
+    let rows: Vec<VoteMetaRow> = query_as_with(&sql, value)
+        .fetch_all(&state.db)
+        .await
+        .map_err(|e| AppError::ExecSqlFailed(e.to_string()))?;
+
+    let views = rows
+        .into_iter()
+        .map(|row| {
+            let result = vote_result(&row, &proposal_sample.record, &state.governance);
+            json!({
+                "voteMeta": row,
+                "voteResult": result as u32,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    Ok(ok(views))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/proposal/progress",
+    params(UriQuery),
+    description = "提案里程碑进度：里程碑列表、当前里程碑、已完成里程碑及待处理的里程碑任务"
+)]
+pub async fn progress(
+    State(state): State<AppView>,
+    Query(query): Query<UriQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    query
+        .validate()
+        .map_err(|e| AppError::ValidateFailed(e.to_string()))?;
+
+    let (sql, value) = Proposal::build_sample()
+        .and_where(Expr::col(Proposal::Uri).eq(&query.uri))
+        .build_sqlx(PostgresQueryBuilder);
+    let proposal_sample: ProposalSample = query_as_with(&sql, value)
+        .fetch_one(&state.db)
+        .await
+        .map_err(|_| AppError::NotFound)?;
+    let milestones = proposal_sample
+        .parse_record()
+        .map_err(|e| AppError::ValidateFailed(e.to_string()))?
+        .data
+        .milestones;
+
+    // 启动金发放前没有进行中的里程碑；进入验收阶段后全部里程碑视为完成
+    let (current, completed) = match ProposalState::from(proposal_sample.state) {
+        ProposalState::Draft
+        | ProposalState::InitiationVote
+        | ProposalState::WaitingForStartFund => (None, 0),
+        ProposalState::WaitingForAcceptanceReport | ProposalState::Completed => {
+            (None, milestones.len())
+        }
+        _ => {
+            let index = proposal_sample.progress.max(0) as usize;
+            (Some(index), index.min(milestones.len()))
+        }
+    };
+
+    let tasks = Task::pending(&state.db, &query.uri, &TaskType::MILESTONE)
+        .await
+        .map_err(|e| AppError::ExecSqlFailed(e.to_string()))?;
+
+    Ok(ok(json!({
+        "milestones": milestones,
+        "current": current,
+        "completed": (0..completed).collect::<Vec<_>>(),
+        "state": proposal_sample.state,
+        "pendingTasks": tasks,
+    })))
+}
+
 #[derive(Debug, Default, Validate, Deserialize, IntoParams)]
 #[serde(default)]
 pub struct StateQuery {
@@ -405,17 +705,17 @@ pub async fn initiation_vote(
     body.validate()
         .map_err(|e| AppError::ValidateFailed(e.to_string()))?;
 
-    body.verify_signature(&state.indexer_did_url)
+    body.verify_signature(&state.indexer_did_url, state.indexer_timeout)
         .await
         .map_err(|e| AppError::ValidateFailed(e.to_string()))?;
 
     let SignedBody::<InitiationParams> { params, did, .. } = body;
 
-    let (sql, values) = Proposal::build_select(None)
+    let (sql, values) = Proposal::build_sample()
         .and_where(Expr::col(Proposal::Uri).eq(&params.proposal_uri))
         .build_sqlx(PostgresQueryBuilder);
 
-    let proposal_row: ProposalRow = query_as_with(&sql, values.clone())
+    let proposal_row: ProposalSample = query_as_with(&sql, values.clone())
         .fetch_one(&state.db)
         .await
         .map_err(|e| {
@@ -450,11 +750,15 @@ pub async fn initiation_vote(
         })?;
 
     // check proposer's weight > 10_000_000_000_000
-    let ckb_addr = crate::ckb::get_ckb_addr_by_did(&state.ckb_client, &state.ckb_net, &did).await?;
+    let ckb_addr = state
+        .ckb_addr_by_did(&did)
+        .await
+        .map_err(AppError::from_did_lookup)?;
     let weight: u64 = crate::indexer_bind::get_weight(
         state.ckb_net,
         &state.indexer_bind_url,
         &state.indexer_dao_url,
+        state.indexer_timeout,
         &ckb_addr,
         None,
     )
@@ -500,27 +804,13 @@ impl SignedParam for ReceiverAddrParams {
 #[utoipa::path(
     post,
     path = "/api/proposal/update_receiver_addr",
-    description = "更新项目金库地址"
+    description = "更新项目金库地址",
+    request_body = SignedBody<ReceiverAddrParams>
 )]
 pub async fn update_receiver_addr(
     State(state): State<AppView>,
-    Json(body): Json<SignedBody<ReceiverAddrParams>>,
+    AdminSignedBody(body): AdminSignedBody<ReceiverAddrParams>,
 ) -> Result<impl IntoResponse, AppError> {
-    body.validate()
-        .map_err(|e| AppError::ValidateFailed(e.to_string()))?;
-
-    let (sql, value) = Administrator::build_select()
-        .and_where(Expr::col(Administrator::Did).eq(body.did.clone()))
-        .build_sqlx(PostgresQueryBuilder);
-    let _admin_row: AdministratorRow = query_as_with(&sql, value)
-        .fetch_one(&state.db)
-        .await
-        .map_err(|e| AppError::ValidateFailed(format!("not administrator: {e}")))?;
-
-    body.verify_signature(&state.indexer_did_url)
-        .await
-        .map_err(|e| AppError::ValidateFailed(e.to_string()))?;
-
     let (sql, value) = Proposal::build_sample()
         .and_where(Expr::col(Proposal::Uri).eq(body.params.proposal_uri.clone()))
         .build_sqlx(PostgresQueryBuilder);
@@ -545,7 +835,8 @@ pub async fn update_receiver_addr(
         .await
         .map_err(|e| AppError::ValidateFailed(format!("vote meta not found: {e}")))?;
 
-    if vote_result(&vote_meta_row, &proposal_sample) != VoteResult::Agree {
+    let result = vote_result(&vote_meta_row, &proposal_sample.record, &state.governance);
+    if result != VoteResult::Agree {
         return Err(AppError::ValidateFailed(
             "only Agree vote result can update receiver addr".to_string(),
         ));
@@ -560,12 +851,12 @@ pub async fn update_receiver_addr(
 
     let admins = Administrator::fetch_all(&state.db)
         .await
-        .iter()
-        .map(|admin| admin.did.clone())
+        .map_err(|e| AppError::ExecSqlFailed(e.to_string()))?
+        .into_iter()
+        .map(|admin| admin.did)
         .collect();
-    Task::insert(
-        &state.db,
-        &TaskRow {
+    state
+        .insert_task(&TaskRow {
             id: 0,
             task_type: TaskType::SendInitialFund as i32,
             message: "SendInitialFund".to_string(),
@@ -576,11 +867,11 @@ pub async fn update_receiver_addr(
             state: TaskState::Unread as i32,
             updated: chrono::Local::now(),
             created: chrono::Local::now(),
-        },
-    )
-    .await
-    .map_err(|e| error!("insert task failed: {e}"))
-    .ok();
+            overdue: false,
+        })
+        .await
+        .map_err(|e| error!("insert task failed: {e}"))
+        .ok();
 
     Task::complete(
         &state.db,
@@ -612,126 +903,203 @@ pub async fn update_receiver_addr(
     Ok(ok_simple())
 }
 
-pub fn vote_result(vote_meta: &VoteMetaRow, proposal: &ProposalSample) -> VoteResult {
-    if let Some(results) = &vote_meta.results
-        && let Ok(results) = serde_json::from_value::<VoteResults>(results.clone())
-        && let Some(proposal_type) = proposal
-            .record
-            .pointer("/data/proposalType")
-            .and_then(|t| t.as_str())
-    {
-        return calculate_vote_result(vote_meta.proposal_state, proposal, results, proposal_type);
+pub fn vote_result(
+    vote_meta: &VoteMetaRow,
+    proposal_record: &serde_json::Value,
+    params: &GovernanceParams,
+) -> VoteResult {
+    let Some(results) = &vote_meta.results else {
+        return VoteResult::Voting;
+    };
+    let Ok(results) = serde_json::from_value::<VoteResults>(results.clone()) else {
+        return VoteResult::Voting;
+    };
+    // 结束时写入的结果为准，治理参数之后的调整不影响已结束的投票
+    if let Some(result) = results.result {
+        match VoteResult::try_from(result) {
+            Ok(result) => return result,
+            Err(value) => warn!("unknown stored vote result {value}, recalculate"),
+        }
+    }
+    match ProposalRecord::parse(&vote_meta.proposal_uri, proposal_record) {
+        Ok(record) => calculate_vote_result(
+            vote_meta.proposal_state,
+            &record.data,
+            &vote_meta.candidates,
+            results,
+            params,
+        ),
+        Err(e) => {
+            warn!("{e}");
+            VoteResult::Voting
+        }
     }
-    VoteResult::Voting
 }
 
-pub fn calculate_vote_result(
+/// 按候选项名称取得票权重，不依赖固定下标
+fn candidate_weight(candidates: &[String], results: &VoteResults, label: &str) -> Option<u64> {
+    let index = candidates.iter().position(|c| c == label)?;
+    results.candidate_votes.get(index).copied()
+}
+
+/// 某次投票适用的门槛，权重单位为 shannon
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct VoteThreshold {
+    /// 最低总票数
+    pub quorum: u64,
+    /// 立项 / 复审 / 整改投票为赞成比例下限，里程碑 / 延期投票为反对比例上限
+    pub ratio: f64,
+    /// 里程碑 / 延期投票按反对比例否决，未达最低总票数视为通过
+    pub rejection: bool,
+}
+
+/// 按提案类型、预算与投票阶段确定门槛，非投票阶段返回 `None`
+pub fn vote_threshold(
     proposal_state: i32,
-    proposal: &ProposalSample,
-    results: VoteResults,
-    proposal_type: &str,
-) -> VoteResult {
-    debug!(
-        "calculate_vote_result: proposal_type: {proposal_type}, proposal_state: {proposal_state}",
-    );
+    proposal: &ProposalData,
+    params: &GovernanceParams,
+) -> Option<VoteThreshold> {
+    let budget_proposal = proposal.is_budget_proposal();
     match ProposalState::from(proposal_state) {
-        ProposalState::InitiationVote | ProposalState::ReexamineVote => {
-            if proposal_type == "BudgetProposal" {
-                if results.valid_weight_sum >= 1_8500_0000_0000_0000 {
-                    let agree = results.candidate_votes[1] as f64 / results.valid_weight_sum as f64;
-                    if agree >= 0.67 {
-                        return VoteResult::Agree;
-                    } else {
-                        return VoteResult::AgreeLessThan67PCT;
-                    }
-                } else {
-                    return VoteResult::TotalLessThan185000000CKB;
-                }
-            } else if let Some(proposal_budget) = proposal
-                .record
-                .pointer("/data/budget")
-                .and_then(|t| t.as_str())
-                .and_then(|t| t.parse::<u64>().ok())
-            {
-                debug!("proposal_budget: {}", proposal_budget);
-                debug!("valid_weight_sum: {}", results.valid_weight_sum);
-                if results.valid_weight_sum >= (proposal_budget * 3_0000_0000) {
-                    let agree = results.candidate_votes[1] as f64 / results.valid_weight_sum as f64;
-                    if agree >= 0.51 {
-                        return VoteResult::Agree;
-                    } else {
-                        return VoteResult::AgreeLessThan51PCT;
-                    }
-                } else {
-                    return VoteResult::TotalLessThan3X;
-                }
+        ProposalState::InitiationVote
+        | ProposalState::ReexamineVote
+        | ProposalState::RectificationVote => {
+            if budget_proposal {
+                Some(VoteThreshold {
+                    quorum: params.budget_initiation_quorum,
+                    ratio: params.budget_initiation_approval,
+                    rejection: false,
+                })
+            } else {
+                Some(VoteThreshold {
+                    quorum: proposal
+                        .budget
+                        .saturating_mul(params.initiation_quorum_multiplier),
+                    ratio: params.initiation_approval,
+                    rejection: false,
+                })
             }
         }
         ProposalState::MilestoneVote | ProposalState::DelayVote => {
-            if proposal_type == "BudgetProposal" {
-                if results.valid_weight_sum >= 6200_0000_0000_0000 {
-                    let against =
-                        results.candidate_votes[2] as f64 / results.valid_weight_sum as f64;
-                    if against > 0.67 {
-                        return VoteResult::AgainstMoreThan67PCT;
-                    } else {
-                        return VoteResult::Agree;
-                    }
-                } else {
-                    return VoteResult::Agree;
-                }
-            } else if let Some(proposal_budget) = proposal
-                .record
-                .pointer("/data/budget")
-                .and_then(|t| t.as_str())
-                .and_then(|t| t.parse::<u64>().ok())
-            {
-                if results.valid_weight_sum >= (proposal_budget * 1_0000_0000) {
-                    let against =
-                        results.candidate_votes[2] as f64 / results.valid_weight_sum as f64;
-                    if against > 0.51 {
-                        return VoteResult::AgainstMoreThan51PCT;
-                    } else {
-                        return VoteResult::Agree;
-                    }
-                } else {
-                    return VoteResult::Agree;
-                }
+            if budget_proposal {
+                Some(VoteThreshold {
+                    quorum: params.budget_milestone_quorum,
+                    ratio: params.budget_milestone_rejection,
+                    rejection: true,
+                })
+            } else {
+                Some(VoteThreshold {
+                    quorum: proposal
+                        .budget
+                        .saturating_mul(params.milestone_quorum_multiplier),
+                    ratio: params.milestone_rejection,
+                    rejection: true,
+                })
             }
         }
-        ProposalState::RectificationVote => {
-            if proposal_type == "BudgetProposal" {
-                if results.valid_weight_sum >= 1_8500_0000_0000_0000 {
-                    let agree = results.candidate_votes[1] as f64 / results.valid_weight_sum as f64;
-                    if agree >= 0.67 {
-                        return VoteResult::Agree;
-                    } else {
-                        return VoteResult::AgreeLessThan67PCT;
-                    }
-                } else {
-                    return VoteResult::TotalLessThan185000000CKB;
-                }
-            } else if let Some(proposal_budget) = proposal
-                .record
-                .pointer("/data/budget")
-                .and_then(|t| t.as_str())
-                .and_then(|t| t.parse::<u64>().ok())
-            {
-                if results.valid_weight_sum >= (proposal_budget * 3_0000_0000) {
-                    let agree = results.candidate_votes[1] as f64 / results.valid_weight_sum as f64;
-                    if agree >= 0.51 {
-                        return VoteResult::Agree;
-                    } else {
-                        return VoteResult::AgreeLessThan51PCT;
-                    }
+        _ => None,
+    }
+}
+
+/// 与门槛比较的总票数，按配置决定是否计入弃权票
+pub fn quorum_weight(
+    candidates: &[String],
+    results: &VoteResults,
+    params: &GovernanceParams,
+) -> u64 {
+    if params.abstain_counts_for_quorum {
+        results.valid_weight_sum
+    } else {
+        let [abstain_label, _, _] = VOTE_CANDIDATES;
+        results
+            .valid_weight_sum
+            .saturating_sub(candidate_weight(candidates, results, abstain_label).unwrap_or(0))
+    }
+}
+
+/// 赞成（或里程碑类投票的反对）票占有效票权重的比例，无有效票时为 0
+pub fn decision_ratio(candidates: &[String], results: &VoteResults, rejection: bool) -> f64 {
+    let [_, agree_label, against_label] = VOTE_CANDIDATES;
+    let label = if rejection {
+        against_label
+    } else {
+        agree_label
+    };
+    match candidate_weight(candidates, results, label) {
+        Some(weight) if results.valid_weight_sum > 0 => {
+            weight as f64 / results.valid_weight_sum as f64
+        }
+        _ => 0.0,
+    }
+}
+
+pub fn calculate_vote_result(
+    proposal_state: i32,
+    proposal: &ProposalData,
+    candidates: &[String],
+    results: VoteResults,
+    params: &GovernanceParams,
+) -> VoteResult {
+    let [_, agree_label, against_label] = VOTE_CANDIDATES;
+    if candidate_weight(candidates, &results, agree_label).is_none()
+        || candidate_weight(candidates, &results, against_label).is_none()
+    {
+        warn!(
+            "vote candidates {candidates:?} with {} counts lack {agree_label}/{against_label}, result failed",
+            results.candidate_votes.len()
+        );
+        return VoteResult::Failed;
+    }
+    debug!(
+        "calculate_vote_result: proposal_type: {}, proposal_state: {proposal_state}",
+        proposal.proposal_type,
+    );
+    let budget_proposal = proposal.is_budget_proposal();
+    // 无有效票时不计算比例（0 / 0 为 NaN），按未达门槛处理
+    if results.valid_weight_sum == 0 {
+        warn!("no valid vote weight, proposal_state: {proposal_state}, treated as quorum not met");
+        return match ProposalState::from(proposal_state) {
+            ProposalState::InitiationVote
+            | ProposalState::ReexamineVote
+            | ProposalState::RectificationVote => {
+                if budget_proposal {
+                    VoteResult::TotalLessThan185000000CKB
                 } else {
-                    return VoteResult::TotalLessThan3X;
+                    VoteResult::TotalLessThan3X
                 }
             }
+            ProposalState::MilestoneVote | ProposalState::DelayVote => VoteResult::Agree,
+            _ => VoteResult::Failed,
+        };
+    }
+    let Some(threshold) = vote_threshold(proposal_state, proposal, params) else {
+        return VoteResult::Failed;
+    };
+    let quorum_weight = quorum_weight(candidates, &results, params);
+    debug!("quorum_weight: {quorum_weight}, threshold: {threshold:?}");
+    let ratio = decision_ratio(candidates, &results, threshold.rejection);
+    if threshold.rejection {
+        if quorum_weight < threshold.quorum || ratio <= threshold.ratio {
+            VoteResult::Agree
+        } else if budget_proposal {
+            VoteResult::AgainstMoreThan67PCT
+        } else {
+            VoteResult::AgainstMoreThan51PCT
+        }
+    } else if quorum_weight < threshold.quorum {
+        if budget_proposal {
+            VoteResult::TotalLessThan185000000CKB
+        } else {
+            VoteResult::TotalLessThan3X
         }
-        _ => (),
+    } else if ratio >= threshold.ratio {
+        VoteResult::Agree
+    } else if budget_proposal {
+        VoteResult::AgreeLessThan67PCT
+    } else {
+        VoteResult::AgreeLessThan51PCT
     }
-    VoteResult::Failed
 }
 
 #[utoipa::path(get, path = "/api/proposal/status")]
@@ -750,18 +1118,197 @@ pub async fn status(State(state): State<AppView>) -> Result<impl IntoResponse, A
 
     let mut budget_amount_in_progress = 0;
     for proposal in proposals.iter() {
-        if let Some(proposal_budget) = proposal
-            .record
-            .pointer("/data/budget")
-            .and_then(|t| t.as_str())
-            .and_then(|t| t.parse::<u64>().ok())
-        {
-            budget_amount_in_progress += proposal_budget;
+        match proposal.parse_record() {
+            Ok(record) => budget_amount_in_progress += record.data.budget,
+            Err(e) => warn!("{e}"),
         }
     }
 
     Ok(ok(json!({
-        "in_progress_num": proposals.len(),
-        "budget_amount_in_progress": budget_amount_in_progress.to_string()
+        "inProgressNum": proposals.len(),
+        "budgetAmountInProgress": budget_amount_in_progress.to_string()
     })))
 }
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[serde(default)]
+pub struct StatsQuery {
+    /// only count proposals of this DID
+    pub repo: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ProposalStatsResponse {
+    /// 各状态的提案数，键为状态值（与 `ProposalState` 一致），未出现的状态省略
+    pub by_state: HashMap<i32, i64>,
+    pub total: i64,
+    /// 处于投票阶段的提案数
+    pub voting: i64,
+    pub completed: i64,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/proposal/stats",
+    params(StatsQuery),
+    responses((status = 200, body = ProposalStatsResponse))
+)]
+pub async fn stats(
+    State(state): State<AppView>,
+    Query(query): Query<StatsQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let (sql, values) = sea_query::Query::select()
+        .column(Proposal::State)
+        .expr(Expr::col(Proposal::Uri).count())
+        .from(Proposal::Table)
+        .and_where_option(query.repo.map(|repo| Expr::col(Proposal::Repo).eq(repo)))
+        .group_by_col(Proposal::State)
+        .build_sqlx(PostgresQueryBuilder);
+    let rows: Vec<(i32, i64)> = query_as_with(&sql, values)
+        .fetch_all(&state.db)
+        .await
+        .map_err(|e| AppError::ExecSqlFailed(e.to_string()))?;
+
+    let mut stats = ProposalStatsResponse::default();
+    for (proposal_state, count) in rows {
+        // 按原始值计数，无法识别的旧值不并入 Draft
+        stats.by_state.insert(proposal_state, count);
+        stats.total += count;
+        if ProposalState::from(proposal_state).is_vote() {
+            stats.voting += count;
+        }
+        if proposal_state == ProposalState::Completed as i32 {
+            stats.completed += count;
+        }
+    }
+    Ok(ok(stats))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidates() -> Vec<String> {
+        VOTE_CANDIDATES.iter().map(|c| c.to_string()).collect()
+    }
+
+    /// 按 [弃权, 赞成, 反对] 的权重构造计票结果
+    fn results(abstain: u64, agree: u64, against: u64) -> VoteResults {
+        VoteResults {
+            vote_sum: 3,
+            valid_vote_sum: 3,
+            valid_weight_sum: abstain + agree + against,
+            valid_votes: vec![],
+            candidate_votes: vec![abstain, agree, against],
+            result: None,
+        }
+    }
+
+    fn proposal(proposal_type: &str, budget: u64) -> ProposalData {
+        ProposalData {
+            proposal_type: proposal_type.to_string(),
+            budget,
+            milestones: vec![],
+        }
+    }
+
+    fn params(abstain_counts_for_quorum: bool) -> GovernanceParams {
+        GovernanceParams {
+            abstain_counts_for_quorum,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn quorum_weight_excludes_abstain_when_configured() {
+        let results = results(10, 20, 30);
+        assert_eq!(quorum_weight(&candidates(), &results, &params(true)), 60);
+        assert_eq!(quorum_weight(&candidates(), &results, &params(false)), 50);
+    }
+
+    #[test]
+    fn abstain_decides_quorum_of_non_budget_proposal() {
+        // 预算 100 CKB，门槛 3 倍即 300 CKB；弃权 100 CKB 恰好补足门槛
+        let proposal = proposal("Other", 100);
+        let results = results(100_0000_0000, 160_0000_0000, 40_0000_0000);
+        let state = ProposalState::InitiationVote as i32;
+        assert_eq!(
+            calculate_vote_result(
+                state,
+                &proposal,
+                &candidates(),
+                results.clone(),
+                &params(true)
+            ),
+            VoteResult::Agree
+        );
+        assert_eq!(
+            calculate_vote_result(state, &proposal, &candidates(), results, &params(false)),
+            VoteResult::TotalLessThan3X
+        );
+    }
+
+    #[test]
+    fn abstain_decides_quorum_of_budget_proposal() {
+        // 门槛 1.85 亿 CKB，弃权 0.5 亿 CKB 恰好补足门槛，赞成比例不足 67%
+        let proposal = proposal("BudgetProposal", 1);
+        let results = results(
+            5000_0000_0000_0000,
+            1_2000_0000_0000_0000,
+            1500_0000_0000_0000,
+        );
+        let state = ProposalState::ReexamineVote as i32;
+        assert_eq!(
+            calculate_vote_result(
+                state,
+                &proposal,
+                &candidates(),
+                results.clone(),
+                &params(true)
+            ),
+            VoteResult::AgreeLessThan67PCT
+        );
+        assert_eq!(
+            calculate_vote_result(state, &proposal, &candidates(), results, &params(false)),
+            VoteResult::TotalLessThan185000000CKB
+        );
+    }
+
+    #[test]
+    fn no_valid_weight_is_treated_as_quorum_not_met() {
+        let candidates = candidates();
+        let params = params(true);
+        let initiation = ProposalState::InitiationVote as i32;
+        assert_eq!(
+            calculate_vote_result(
+                initiation,
+                &proposal("Other", 100),
+                &candidates,
+                results(0, 0, 0),
+                &params
+            ),
+            VoteResult::TotalLessThan3X
+        );
+        assert_eq!(
+            calculate_vote_result(
+                initiation,
+                &proposal("BudgetProposal", 100),
+                &candidates,
+                results(0, 0, 0),
+                &params
+            ),
+            VoteResult::TotalLessThan185000000CKB
+        );
+        assert_eq!(
+            calculate_vote_result(
+                ProposalState::MilestoneVote as i32,
+                &proposal("Other", 100),
+                &candidates,
+                results(0, 0, 0),
+                &params
+            ),
+            VoteResult::Agree
+        );
+    }
+}
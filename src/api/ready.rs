@@ -0,0 +1,88 @@
+use chrono::{DateTime, Local};
+use common_x::restful::axum::{
+    Json,
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use sea_query::{Order, PostgresQueryBuilder};
+use sea_query_sqlx::SqlxBinder;
+use serde_json::json;
+
+use crate::{AppView, error::AppError, lexicon::voter_list::VoterList};
+
+/// 构建一次投票名单允许的耗时，超出调度周期加该时长仍未生成视为停滞
+const VOTER_LIST_BUILD_GRACE_MINUTES: i64 = 30;
+
+#[utoipa::path(
+    get,
+    path = "/ready/voter_list",
+    description = "检查最新投票名单是否过期：下一个名单区块已出块超过一个调度周期仍未生成新名单"
+)]
+pub async fn voter_list(State(state): State<AppView>) -> Result<Response, AppError> {
+    let (sql, values) = sea_query::Query::select()
+        .columns([
+            (VoterList::Table, VoterList::Id),
+            (VoterList::Table, VoterList::BlockNumber),
+            (VoterList::Table, VoterList::Created),
+        ])
+        .from(VoterList::Table)
+        .order_by(VoterList::Created, Order::Desc)
+        .limit(1)
+        .build_sqlx(PostgresQueryBuilder);
+    let latest: Option<(String, i64, DateTime<Local>)> = sqlx::query_as_with(&sql, values)
+        .fetch_optional(&state.db)
+        .await
+        .map_err(|e| AppError::ExecSqlFailed(e.to_string()))?;
+
+    let Some((id, block_number, created)) = latest else {
+        warn!("no voter list built yet");
+        return Ok((
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({ "status": "missing" })),
+        )
+            .into_response());
+    };
+
+    // 链上越过下一个名单区块后，构建任务应在一个调度周期内生成新名单
+    let tip: u64 = state.ckb_client.get_tip_block_number().await?.into();
+    let due_block = block_number as u64 + state.build_voter_list_interval;
+    let overdue = if tip < due_block {
+        chrono::Duration::zero()
+    } else {
+        let due_header = state
+            .ckb_client
+            .get_header_by_number(due_block.into())
+            .await?
+            .ok_or_else(|| AppError::Unknown(format!("header {due_block} not found")))?;
+        let due_since =
+            DateTime::from_timestamp_millis(u64::from(due_header.inner.timestamp) as i64)
+                .unwrap_or_default();
+        chrono::Utc::now() - due_since
+    };
+    let stale = overdue
+        > state.build_voter_list_period + chrono::Duration::minutes(VOTER_LIST_BUILD_GRACE_MINUTES);
+    let age = chrono::Local::now() - created;
+    if stale {
+        warn!(
+            "latest voter list {id} is stale: block {due_block} reached {} minutes ago",
+            overdue.num_minutes()
+        );
+    }
+    let status = if stale {
+        StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        StatusCode::OK
+    };
+    Ok((
+        status,
+        Json(json!({
+            "status": if stale { "stale" } else { "ok" },
+            "id": id,
+            "blockNumber": block_number,
+            "created": created,
+            "ageSeconds": age.num_seconds(),
+        })),
+    )
+        .into_response())
+}
@@ -5,11 +5,25 @@ use common_x::restful::{
     },
     ok,
 };
-use serde::Deserialize;
-use utoipa::IntoParams;
+use sea_query::{Expr, ExprTrait, PostgresQueryBuilder, SelectStatement};
+use sea_query_sqlx::SqlxBinder;
+use serde::{Deserialize, Serialize};
+use sqlx::query_as_with;
+use utoipa::{IntoParams, ToSchema};
 use validator::Validate;
 
-use crate::{AppView, api::build_author, error::AppError};
+use crate::{
+    AppView,
+    api::build_author,
+    error::AppError,
+    lexicon::{
+        like::Like,
+        proposal::Proposal,
+        reply::Reply,
+        task::Task,
+        vote::{Vote, VoteState},
+    },
+};
 
 #[derive(Debug, Default, Validate, Deserialize, IntoParams)]
 #[serde(default)]
@@ -32,3 +46,99 @@ pub async fn profile(
 
     Ok(ok(author))
 }
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivityResponse {
+    pub proposals: i64,
+    pub replies: i64,
+    /// 点出的赞
+    pub likes: i64,
+    /// 参与过的投票数：同一投票多次提交只算一次，未上链的不计
+    pub votes: i64,
+    /// operators 中包含该 DID 的任务，含已完成
+    pub tasks: i64,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/repo/activity",
+    params(RepoQuery),
+    responses((status = 200, body = ActivityResponse))
+)]
+pub async fn activity(
+    State(state): State<AppView>,
+    Query(query): Query<RepoQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    query
+        .validate()
+        .map_err(|e| AppError::ValidateFailed(e.to_string()))?;
+
+    let repo = &query.repo;
+    let (proposals, replies, likes, votes, tasks) = tokio::try_join!(
+        count(
+            &state,
+            sea_query::Query::select()
+                .expr(Expr::col(Proposal::Uri).count())
+                .from(Proposal::Table)
+                .and_where(Expr::col(Proposal::Repo).eq(repo))
+                .take(),
+        ),
+        count(
+            &state,
+            sea_query::Query::select()
+                .expr(Expr::col(Reply::Uri).count())
+                .from(Reply::Table)
+                .and_where(Expr::col(Reply::Repo).eq(repo))
+                .take(),
+        ),
+        count(
+            &state,
+            sea_query::Query::select()
+                .expr(Expr::col(Like::Uri).count())
+                .from(Like::Table)
+                .and_where(Expr::col(Like::Repo).eq(repo))
+                .take(),
+        ),
+        count(
+            &state,
+            sea_query::Query::select()
+                .expr(Expr::col(Vote::VoteMetaId).count_distinct())
+                .from(Vote::Table)
+                .and_where(Expr::col(Vote::Voter).eq(repo))
+                .and_where(
+                    Expr::col(Vote::State)
+                        .is_in([VoteState::Committed as i32, VoteState::Finished as i32]),
+                )
+                .take(),
+        ),
+        count(
+            &state,
+            sea_query::Query::select()
+                .expr(Expr::col(Task::Id).count())
+                .from(Task::Table)
+                .and_where(Expr::cust_with_values(
+                    "$1 = ANY(\"task\".\"operators\")",
+                    [repo],
+                ))
+                .take(),
+        ),
+    )?;
+
+    Ok(ok(ActivityResponse {
+        proposals,
+        replies,
+        likes,
+        votes,
+        tasks,
+    }))
+}
+
+async fn count(state: &AppView, select: SelectStatement) -> Result<i64, AppError> {
+    let (sql, values) = select.build_sqlx(PostgresQueryBuilder);
+    let row: (i64,) = query_as_with(&sql, values)
+        .fetch_one(&state.db)
+        .await
+        .map_err(|e| AppError::ExecSqlFailed(e.to_string()))?;
+    Ok(row.0)
+}
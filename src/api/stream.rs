@@ -0,0 +1,66 @@
+use std::convert::Infallible;
+
+use common_x::restful::axum::{
+    extract::{Path, State},
+    response::{
+        IntoResponse,
+        sse::{Event, KeepAlive, Sse},
+    },
+};
+use serde::Serialize;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::AppView;
+
+/// 调度任务推动提案 / 投票状态变化时推送的事件
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProposalEvent {
+    pub uri: String,
+    pub vote_meta_id: i32,
+    pub vote_meta_state: i32,
+    /// 提案状态有变化时才有
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proposal_state: Option<i32>,
+}
+
+pub type ProposalEvents = broadcast::Sender<ProposalEvent>;
+
+pub fn create_proposal_events() -> ProposalEvents {
+    broadcast::channel(256).0
+}
+
+pub fn publish(events: &ProposalEvents, event: ProposalEvent) {
+    // no subscribers is not an error
+    events.send(event).ok();
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/stream/proposal/{uri}",
+    params(("uri" = String, Path, description = "proposal uri")),
+    description = "SSE：推送提案状态与投票状态变化"
+)]
+pub async fn proposal(State(state): State<AppView>, Path(uri): Path<String>) -> impl IntoResponse {
+    let rx = state.proposal_events.subscribe();
+    let stream = futures::stream::unfold((rx, uri), |(mut rx, uri)| async move {
+        loop {
+            match rx.recv().await {
+                Ok(event) if event.uri == uri => {
+                    let event = Event::default()
+                        .event("proposal")
+                        .json_data(&event)
+                        .unwrap_or_else(|_| Event::default().comment("encode failed"));
+                    return Some((Ok::<_, Infallible>(event), (rx, uri)));
+                }
+                Ok(_) => continue,
+                Err(RecvError::Lagged(n)) => {
+                    warn!("proposal stream {uri} lagged, {n} events dropped");
+                    continue;
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
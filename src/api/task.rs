@@ -1,4 +1,4 @@
-use std::str::FromStr;
+use std::{collections::HashMap, str::FromStr};
 
 use chrono::DateTime;
 use color_eyre::eyre::eyre;
@@ -20,10 +20,14 @@ use validator::Validate;
 
 use crate::{
     AppView,
-    api::{SignedBody, SignedParam, build_author, create_vote_tx},
+    api::{
+        AdminSignedBody, SignedBody, SignedParam, build_authors, create_vote_tx, decode_cursor,
+        encode_cursor,
+    },
+    ckb::TxHash,
     error::AppError,
     lexicon::{
-        administrator::{Administrator, AdministratorRow},
+        administrator::Administrator,
         meeting::{Meeting, MeetingRow, MeetingState},
         proposal::{Proposal, ProposalRow, ProposalSample, ProposalState, has_next_milestone},
         task::{Task, TaskRow, TaskState, TaskType, TaskView},
@@ -36,6 +40,8 @@ use crate::{
 pub struct TaskQuery {
     #[validate(length(min = 1))]
     pub did: String,
+    /// pagination cursor (opaque, returned by the previous page); takes precedence over `page`
+    pub cursor: Option<String>,
     #[validate(range(min = 1))]
     pub page: u64,
     #[validate(range(min = 1))]
@@ -46,6 +52,7 @@ impl Default for TaskQuery {
     fn default() -> Self {
         Self {
             did: String::new(),
+            cursor: None,
             page: 1,
             per_page: 20,
         }
@@ -61,9 +68,8 @@ pub async fn get(
         .validate()
         .map_err(|e| AppError::ValidateFailed(e.to_string()))?;
 
-    let offset = query.per_page * (query.page - 1);
-
-    let (sql, values) = sea_query::Query::select()
+    let mut select = sea_query::Query::select();
+    select
         .columns([
             (Task::Table, Task::Id),
             (Task::Table, Task::TaskType),
@@ -75,6 +81,7 @@ pub async fn get(
             (Task::Table, Task::State),
             (Task::Table, Task::Updated),
             (Task::Table, Task::Created),
+            (Task::Table, Task::Overdue),
         ])
         .from(Task::Table)
         .and_where(Expr::col(Task::State).ne(TaskState::Completed as i32))
@@ -85,9 +92,22 @@ pub async fn get(
                     "$1 = ANY(\"task\".\"operators\")",
                     [&query.did],
                 )),
-        )
-        .order_by(Task::Created, Order::Desc)
-        .offset(offset)
+        );
+    // keyset on (created, id) stays stable while tasks are completed between requests
+    if let Some(cursor) = query.cursor.as_deref().filter(|c| !c.is_empty()) {
+        let (created, id) = decode_cursor(cursor)
+            .and_then(|(created, id)| Some((created, id.parse::<i32>().ok()?)))
+            .ok_or_else(|| AppError::ValidateFailed("invalid cursor".to_string()))?;
+        select.and_where(Expr::cust_with_values(
+            "(\"task\".\"created\", \"task\".\"id\") < ($1::timestamptz, $2)",
+            [sea_query::Value::from(created), sea_query::Value::from(id)],
+        ));
+    } else {
+        select.offset(query.per_page * (query.page - 1));
+    }
+    let (sql, values) = select
+        .order_by((Task::Table, Task::Created), Order::Desc)
+        .order_by((Task::Table, Task::Id), Order::Desc)
         .limit(std::cmp::min(query.per_page, 100))
         .build_sqlx(PostgresQueryBuilder);
 
@@ -95,6 +115,13 @@ pub async fn get(
         .fetch_all(&state.db)
         .await
         .map_err(|e| eyre!("exec sql failed: {e}"))?;
+    let cursor = rows
+        .last()
+        .map(|r| encode_cursor(&r.created, &r.id.to_string()));
+
+    // Batch fetch processors to avoid N+1 queries
+    let processors: Vec<&str> = rows.iter().filter_map(|r| r.processor.as_deref()).collect();
+    let authors = build_authors(&state, &processors).await;
 
     let mut views = vec![];
     for row in rows {
@@ -110,23 +137,7 @@ pub async fn get(
                 AppError::ExecSqlFailed(e.to_string())
             })?;
 
-        let processor = if let Some(processor) = &row.processor {
-            build_author(&state, processor).await
-        } else {
-            serde_json::Value::Null
-        };
-        views.push(TaskView {
-            id: row.id,
-            task_type: row.task_type,
-            message: serde_json::Value::from_str(&row.message).unwrap_or(json!(row.message)),
-            target: json!(proposal),
-            operators: row.operators,
-            processor,
-            deadline: row.deadline,
-            state: row.state,
-            updated: row.updated,
-            created: row.created,
-        });
+        views.push(task_view(row, json!(proposal), &authors));
     }
 
     let (sql, values) = sea_query::Query::select()
@@ -150,18 +161,205 @@ pub async fn get(
 
     Ok(ok(json!({
         "tasks": views,
+        "cursor": cursor,
         "page": query.page,
-        "per_page": query.per_page,
+        "perPage": query.per_page,
         "total":  total.0
     })))
 }
 
+fn task_view(row: TaskRow, target: Value, authors: &HashMap<String, Value>) -> TaskView {
+    let processor = if let Some(processor) = &row.processor {
+        authors
+            .get(processor)
+            .cloned()
+            .unwrap_or_else(|| json!({"did": processor}))
+    } else {
+        serde_json::Value::Null
+    };
+    TaskView {
+        id: row.id,
+        task_type: row.task_type,
+        message: serde_json::Value::from_str(&row.message).unwrap_or(json!(row.message)),
+        target,
+        operators: row.operators,
+        processor,
+        deadline: row.deadline,
+        state: row.state,
+        updated: row.updated,
+        created: row.created,
+        overdue: row.overdue,
+    }
+}
+
+#[derive(Debug, Default, Validate, Deserialize, IntoParams)]
+#[serde(default)]
+pub struct ByProposalQuery {
+    #[validate(length(min = 1))]
+    /// proposal uri
+    pub uri: String,
+}
+
+/// 提案下的全部任务（不限处理人，含已完成），按创建时间升序
+#[utoipa::path(get, path = "/api/task/by_proposal", params(ByProposalQuery))]
+pub async fn by_proposal(
+    State(state): State<AppView>,
+    Query(query): Query<ByProposalQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    query
+        .validate()
+        .map_err(|e| AppError::ValidateFailed(e.to_string()))?;
+
+    let (sql, values) = Proposal::build_select(None)
+        .and_where(Expr::col(Proposal::Uri).eq(&query.uri))
+        .build_sqlx(PostgresQueryBuilder);
+    let proposal: ProposalRow = query_as_with(&sql, values)
+        .fetch_optional(&state.db)
+        .await
+        .map_err(|e| AppError::ExecSqlFailed(e.to_string()))?
+        .ok_or(AppError::NotFound)?;
+
+    let rows = Task::by_target(&state.db, &query.uri)
+        .await
+        .map_err(|e| AppError::ExecSqlFailed(e.to_string()))?;
+    let processors: Vec<&str> = rows.iter().filter_map(|r| r.processor.as_deref()).collect();
+    let authors = build_authors(&state, &processors).await;
+
+    let target = json!(proposal);
+    let tasks: Vec<TaskView> = rows
+        .into_iter()
+        .map(|row| task_view(row, target.clone(), &authors))
+        .collect();
+    Ok(ok(json!({ "tasks": tasks })))
+}
+
+#[derive(Debug, Default, Validate, Deserialize, Serialize, ToSchema)]
+#[serde(default)]
+pub struct ClaimTaskParams {
+    pub task_id: i32,
+    pub timestamp: i64,
+}
+
+impl SignedParam for ClaimTaskParams {
+    fn timestamp(&self) -> i64 {
+        self.timestamp
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/task/claim",
+    description = "认领任务，须在任务的 operators 中且任务未被他人认领",
+    request_body = SignedBody<ClaimTaskParams>
+)]
+pub async fn claim(
+    State(state): State<AppView>,
+    Json(body): Json<SignedBody<ClaimTaskParams>>,
+) -> Result<impl IntoResponse, AppError> {
+    body.validate()
+        .map_err(|e| AppError::ValidateFailed(e.to_string()))?;
+
+    body.verify_signature(&state.indexer_did_url, state.indexer_timeout)
+        .await
+        .map_err(|e| AppError::ValidateFailed(e.to_string()))?;
+
+    let task = Task::get(&state.db, body.params.task_id)
+        .await
+        .map_err(|e| AppError::ExecSqlFailed(e.to_string()))?
+        .ok_or(AppError::NotFound)?;
+    if !task.operators.contains(&body.did) {
+        return Err(AppError::ValidateFailed("not task operator".to_string()));
+    }
+    if task
+        .processor
+        .as_ref()
+        .is_some_and(|processor| processor != &body.did)
+    {
+        return Err(AppError::ValidateFailed("task already claimed".to_string()));
+    }
+    if task.state == TaskState::Completed as i32 {
+        return Err(AppError::ValidateFailed(
+            "task already completed".to_string(),
+        ));
+    }
+
+    if !Task::claim(&state.db, task.id, &body.did)
+        .await
+        .map_err(|e| AppError::ExecSqlFailed(e.to_string()))?
+    {
+        return Err(AppError::ValidateFailed(
+            "task state changed, retry".to_string(),
+        ));
+    }
+
+    Ok(ok_simple())
+}
+
+#[derive(Debug, Default, Validate, Deserialize, Serialize, ToSchema)]
+#[serde(default)]
+pub struct ReassignTaskParams {
+    pub task_id: i32,
+    /// 新的处理人 DID 列表
+    #[validate(length(min = 1))]
+    pub operators: Vec<String>,
+    pub timestamp: i64,
+}
+
+impl SignedParam for ReassignTaskParams {
+    fn timestamp(&self) -> i64 {
+        self.timestamp
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/task/reassign",
+    description = "重新指定任务处理人",
+    request_body = SignedBody<ReassignTaskParams>
+)]
+pub async fn reassign(
+    State(state): State<AppView>,
+    AdminSignedBody(body): AdminSignedBody<ReassignTaskParams>,
+) -> Result<impl IntoResponse, AppError> {
+    let task = Task::get(&state.db, body.params.task_id)
+        .await
+        .map_err(|e| AppError::ExecSqlFailed(e.to_string()))?
+        .ok_or(AppError::NotFound)?;
+    if task.state == TaskState::Completed as i32 {
+        return Err(AppError::ValidateFailed(
+            "task already completed".to_string(),
+        ));
+    }
+
+    if !Task::reassign(&state.db, task.id, &body.params.operators)
+        .await
+        .map_err(|e| AppError::ExecSqlFailed(e.to_string()))?
+    {
+        return Err(AppError::ValidateFailed(
+            "task state changed, retry".to_string(),
+        ));
+    }
+
+    state
+        .notify_task_assigned(
+            task.id,
+            task.task_type,
+            &task.target,
+            &body.params.operators,
+        )
+        .await;
+
+    Ok(ok_simple())
+}
+
 #[derive(Debug, Default, Validate, Deserialize, Serialize, ToSchema)]
 #[serde(default)]
 pub struct CreateMeetingParams {
     pub proposal_uri: String,
     pub title: String,
     pub start_time: String,
+    /// 须晚于 start_time
+    pub end_time: String,
     pub url: String,
     pub description: String,
     pub timestamp: i64,
@@ -173,30 +371,21 @@ impl SignedParam for CreateMeetingParams {
     }
 }
 
-#[utoipa::path(post, path = "/api/task/create_meeting", description = "组织会议")]
+#[utoipa::path(
+    post,
+    path = "/api/task/create_meeting",
+    description = "组织会议",
+    request_body = SignedBody<CreateMeetingParams>
+)]
 pub async fn create_meeting(
     State(state): State<AppView>,
-    Json(body): Json<SignedBody<CreateMeetingParams>>,
+    AdminSignedBody(body): AdminSignedBody<CreateMeetingParams>,
 ) -> Result<impl IntoResponse, AppError> {
-    body.validate()
-        .map_err(|e| AppError::ValidateFailed(e.to_string()))?;
-
-    let (sql, value) = Administrator::build_select()
-        .and_where(Expr::col(Administrator::Did).eq(body.did.clone()))
-        .build_sqlx(PostgresQueryBuilder);
-    let _admin_row: AdministratorRow = query_as_with(&sql, value)
-        .fetch_one(&state.db)
-        .await
-        .map_err(|e| AppError::ValidateFailed(format!("not administrator: {e}")))?;
-
-    body.verify_signature(&state.indexer_did_url)
-        .await
-        .map_err(|e| AppError::ValidateFailed(e.to_string()))?;
-
     let admins = Administrator::fetch_all(&state.db)
         .await
-        .iter()
-        .map(|admin| admin.did.clone())
+        .map_err(|e| AppError::ExecSqlFailed(e.to_string()))?
+        .into_iter()
+        .map(|admin| admin.did)
         .collect::<Vec<_>>();
 
     let (sql, value) = Proposal::build_sample()
@@ -207,13 +396,21 @@ pub async fn create_meeting(
         .await
         .map_err(|e| AppError::ValidateFailed(format!("invalid proposal_uri: {e}")))?;
 
+    let start_time = DateTime::from_str(&body.params.start_time)
+        .map_err(|e| AppError::ValidateFailed(format!("invalid start_time: {e}")))?;
+    let end_time = DateTime::from_str(&body.params.end_time)
+        .map_err(|e| AppError::ValidateFailed(format!("invalid end_time: {e}")))?;
+    if end_time <= start_time {
+        return Err(AppError::ValidateFailed(
+            "end_time must be after start_time".to_string(),
+        ));
+    }
+
     let meeting_row = MeetingRow {
         id: 0,
         title: body.params.title.clone(),
-        start_time: DateTime::from_str(&body.params.start_time)
-            .map_err(|e| AppError::ValidateFailed(format!("invalid start_time: {e}")))?,
-        end_time: DateTime::from_str(&body.params.start_time)
-            .map_err(|e| AppError::ValidateFailed(format!("invalid start_time: {e}")))?,
+        start_time,
+        end_time,
         location: "".to_string(),
         url: body.params.url.clone(),
         description: body.params.description.clone(),
@@ -230,9 +427,8 @@ pub async fn create_meeting(
 
     match ProposalState::from(proposal_row.state) {
         ProposalState::Draft => {
-            Task::insert(
-                &state.db,
-                &TaskRow {
+            state
+                .insert_task(&TaskRow {
                     id: 0,
                     task_type: TaskType::SubmitAMAReport as i32,
                     message: "SubmitAMAReport".to_string(),
@@ -243,9 +439,9 @@ pub async fn create_meeting(
                     state: TaskState::Unread as i32,
                     updated: chrono::Local::now(),
                     created: chrono::Local::now(),
-                },
-            )
-            .await?;
+                    overdue: false,
+                })
+                .await?;
 
             Timeline::insert(
                 &state.db,
@@ -261,9 +457,8 @@ pub async fn create_meeting(
             .await?;
         }
         ProposalState::WaitingReexamine => {
-            Task::insert(
-                &state.db,
-                &TaskRow {
+            state
+                .insert_task(&TaskRow {
                     id: 0,
                     task_type: TaskType::SubmitReexamineReport as i32,
                     message: "SubmitReexamineReport".to_string(),
@@ -274,9 +469,9 @@ pub async fn create_meeting(
                     state: TaskState::Unread as i32,
                     updated: chrono::Local::now(),
                     created: chrono::Local::now(),
-                },
-            )
-            .await?;
+                    overdue: false,
+                })
+                .await?;
 
             Task::complete(
                 &state.db,
@@ -324,27 +519,13 @@ impl SignedParam for SubmitMeetingReportParams {
 #[utoipa::path(
     post,
     path = "/api/task/submit_meeting_report",
-    description = "提交会议报告"
+    description = "提交会议报告",
+    request_body = SignedBody<SubmitMeetingReportParams>
 )]
 pub async fn submit_meeting_report(
     State(state): State<AppView>,
-    Json(body): Json<SignedBody<SubmitMeetingReportParams>>,
+    AdminSignedBody(body): AdminSignedBody<SubmitMeetingReportParams>,
 ) -> Result<impl IntoResponse, AppError> {
-    body.validate()
-        .map_err(|e| AppError::ValidateFailed(e.to_string()))?;
-
-    let (sql, value) = Administrator::build_select()
-        .and_where(Expr::col(Administrator::Did).eq(body.did.clone()))
-        .build_sqlx(PostgresQueryBuilder);
-    let _admin_row: AdministratorRow = query_as_with(&sql, value)
-        .fetch_one(&state.db)
-        .await
-        .map_err(|e| AppError::ValidateFailed(format!("not administrator: {e}")))?;
-
-    body.verify_signature(&state.indexer_did_url)
-        .await
-        .map_err(|e| AppError::ValidateFailed(e.to_string()))?;
-
     let (sql, value) = Meeting::build_select()
         .and_where(Expr::col(Meeting::Id).eq(body.params.meeting_id))
         .build_sqlx(PostgresQueryBuilder);
@@ -389,7 +570,7 @@ pub async fn submit_meeting_report(
                 &state.db,
                 &TimelineRow {
                     id: 0,
-                    timeline_type: TimelineType::SubmitAMAReport as i32,
+                    timeline_type: TimelineType::SubmitReexamineReport as i32,
                     message: body.params.report.clone(),
                     target: body.params.proposal_uri.clone(),
                     operator: body.did.clone(),
@@ -426,39 +607,118 @@ pub async fn submit_meeting_report(
 
 #[derive(Debug, Default, Validate, Deserialize, Serialize, ToSchema)]
 #[serde(default)]
-pub struct SendFundsParams {
-    pub proposal_uri: String,
-    pub amount: String,
-    pub tx_hash: String,
+pub struct CancelMeetingParams {
+    pub meeting_id: i32,
     pub timestamp: i64,
 }
 
-impl SignedParam for SendFundsParams {
+impl SignedParam for CancelMeetingParams {
     fn timestamp(&self) -> i64 {
         self.timestamp
     }
 }
 
-#[utoipa::path(post, path = "/api/task/send_funds", description = "拨款")]
-pub async fn send_funds(
+#[utoipa::path(
+    post,
+    path = "/api/task/cancel_meeting",
+    description = "取消会议",
+    request_body = SignedBody<CancelMeetingParams>
+)]
+pub async fn cancel_meeting(
     State(state): State<AppView>,
-    Json(body): Json<SignedBody<SendFundsParams>>,
+    AdminSignedBody(body): AdminSignedBody<CancelMeetingParams>,
 ) -> Result<impl IntoResponse, AppError> {
-    body.validate()
-        .map_err(|e| AppError::ValidateFailed(e.to_string()))?;
-
-    let (sql, value) = Administrator::build_select()
-        .and_where(Expr::col(Administrator::Did).eq(body.did.clone()))
+    let (sql, value) = Meeting::build_select()
+        .and_where(Expr::col(Meeting::Id).eq(body.params.meeting_id))
         .build_sqlx(PostgresQueryBuilder);
-    let _admin_row: AdministratorRow = query_as_with(&sql, value)
+    let meeting_row: MeetingRow = query_as_with(&sql, value)
         .fetch_one(&state.db)
         .await
-        .map_err(|e| AppError::ValidateFailed(format!("not administrator: {e}")))?;
+        .map_err(|e| AppError::ValidateFailed(format!("not meeting: {e}")))?;
+
+    if meeting_row.state != MeetingState::Scheduled as i32 {
+        return Err(AppError::ValidateFailed(format!(
+            "meeting not scheduled: {}",
+            meeting_row.state
+        )));
+    }
+
+    if !Meeting::cancel(&state.db, meeting_row.id).await? {
+        return Err(AppError::ValidateFailed(
+            "meeting state changed, retry".to_string(),
+        ));
+    }
 
-    body.verify_signature(&state.indexer_did_url)
+    Timeline::insert(
+        &state.db,
+        &TimelineRow {
+            id: 0,
+            timeline_type: TimelineType::CancelMeeting as i32,
+            message: meeting_row.title.clone(),
+            target: meeting_row.proposal_uri.clone(),
+            operator: body.did.clone(),
+            timestamp: chrono::Local::now(),
+        },
+    )
+    .await
+    .map_err(|e| error!("insert timeline failed: {e}"))
+    .ok();
+
+    // 同一提案没有其他待开会议时，报告任务随之作废
+    let (sql, value) = Meeting::build_select()
+        .and_where(Expr::col(Meeting::ProposalUri).eq(&meeting_row.proposal_uri))
+        .and_where(Expr::col(Meeting::State).eq(MeetingState::Scheduled as i32))
+        .limit(1)
+        .build_sqlx(PostgresQueryBuilder);
+    let scheduled: Option<MeetingRow> = query_as_with(&sql, value)
+        .fetch_optional(&state.db)
         .await
-        .map_err(|e| AppError::ValidateFailed(e.to_string()))?;
+        .map_err(|e| AppError::ExecSqlFailed(e.to_string()))?;
+    if scheduled.is_none() {
+        let report_task = match ProposalState::from(meeting_row.proposal_state) {
+            ProposalState::Draft => Some(TaskType::SubmitAMAReport),
+            ProposalState::WaitingReexamine => Some(TaskType::SubmitReexamineReport),
+            _ => None,
+        };
+        if let Some(task_type) = report_task {
+            Task::complete(&state.db, &meeting_row.proposal_uri, task_type, &body.did)
+                .await
+                .map_err(|e| error!("complete task failed: {e}"))
+                .ok();
+        }
+    }
 
+    Ok(ok_simple())
+}
+
+#[derive(Debug, Validate, Deserialize, Serialize, ToSchema)]
+pub struct SendFundsParams {
+    #[serde(default)]
+    pub proposal_uri: String,
+    #[serde(default)]
+    pub amount: String,
+    #[schema(value_type = String)]
+    pub tx_hash: TxHash,
+    #[serde(default)]
+    pub timestamp: i64,
+}
+
+impl SignedParam for SendFundsParams {
+    fn timestamp(&self) -> i64 {
+        self.timestamp
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/task/send_funds",
+    description = "拨款",
+    request_body = SignedBody<SendFundsParams>
+)]
+pub async fn send_funds(
+    State(state): State<AppView>,
+    AdminSignedBody(body): AdminSignedBody<SendFundsParams>,
+) -> Result<impl IntoResponse, AppError> {
     let (sql, value) = Proposal::build_sample()
         .and_where(Expr::col(Proposal::Uri).eq(body.params.proposal_uri.clone()))
         .build_sqlx(PostgresQueryBuilder);
@@ -469,30 +729,36 @@ pub async fn send_funds(
 
     let admins = Administrator::fetch_all(&state.db)
         .await
-        .iter()
-        .map(|admin| admin.did.clone())
+        .map_err(|e| AppError::ExecSqlFailed(e.to_string()))?
+        .into_iter()
+        .map(|admin| admin.did)
         .collect::<Vec<_>>();
 
+    // 状态推进、后续任务、时间线要么全部写入，要么全部回滚；事件在提交后推送
+    let mut created_tasks = vec![];
+    let mut new_state = None;
+    let mut tx = state
+        .db
+        .begin()
+        .await
+        .map_err(|e| AppError::ExecSqlFailed(e.to_string()))?;
     match ProposalState::from(proposal_sample.state) {
         ProposalState::WaitingForStartFund => {
-            let milestone = proposal_sample
-                .record
-                .pointer("/data/milestones")
-                .and_then(|m| m.as_array())
-                .and_then(|m| m.first());
+            let proposal_record = proposal_sample.parse_record()?;
+            let milestone = proposal_record.data.milestones.first();
             if let Some(milestone) = milestone {
                 Proposal::update_state(
-                    &state.db,
+                    &mut *tx,
                     &body.params.proposal_uri,
                     ProposalState::InProgress as i32,
                 )
                 .await?;
+                new_state = Some(ProposalState::InProgress as i32);
 
-                Task::insert(
-                    &state.db,
-                    &TaskRow {
+                for task_type in &state.fund_follow_up_tasks {
+                    let task = TaskRow {
                         id: 0,
-                        task_type: TaskType::SubmitMilestoneReport as i32,
+                        task_type: *task_type as i32,
                         message: milestone.to_string(),
                         target: body.params.proposal_uri.clone(),
                         operators: admins.clone(),
@@ -501,59 +767,39 @@ pub async fn send_funds(
                         state: TaskState::Unread as i32,
                         updated: chrono::Local::now(),
                         created: chrono::Local::now(),
-                    },
-                )
-                .await
-                .map_err(|e| error!("insert task failed: {e}"))
-                .ok();
-                Task::insert(
-                    &state.db,
-                    &TaskRow {
-                        id: 0,
-                        task_type: TaskType::SubmitDelayReport as i32,
-                        message: milestone.to_string(),
-                        target: body.params.proposal_uri.clone(),
-                        operators: admins,
-                        processor: None,
-                        deadline: chrono::Local::now() + chrono::Duration::days(7),
-                        state: TaskState::Unread as i32,
-                        updated: chrono::Local::now(),
-                        created: chrono::Local::now(),
-                    },
-                )
-                .await
-                .map_err(|e| error!("insert task failed: {e}"))
-                .ok();
+                        overdue: false,
+                    };
+                    let id = Task::insert(&mut *tx, &task).await?;
+                    created_tasks.push((id, task));
+                }
             } else {
                 Proposal::update_state(
-                    &state.db,
+                    &mut *tx,
                     &body.params.proposal_uri,
                     ProposalState::WaitingForAcceptanceReport as i32,
                 )
                 .await?;
+                new_state = Some(ProposalState::WaitingForAcceptanceReport as i32);
 
-                Task::insert(
-                    &state.db,
-                    &TaskRow {
-                        id: 0,
-                        task_type: TaskType::SubmitAcceptanceReport as i32,
-                        message: "SubmitAcceptanceReport".to_string(),
-                        target: body.params.proposal_uri.clone(),
-                        operators: admins,
-                        processor: None,
-                        deadline: chrono::Local::now() + chrono::Duration::days(7),
-                        state: TaskState::Unread as i32,
-                        updated: chrono::Local::now(),
-                        created: chrono::Local::now(),
-                    },
-                )
-                .await
-                .map_err(|e| error!("insert task failed: {e}"))
-                .ok();
+                let task = TaskRow {
+                    id: 0,
+                    task_type: TaskType::SubmitAcceptanceReport as i32,
+                    message: "SubmitAcceptanceReport".to_string(),
+                    target: body.params.proposal_uri.clone(),
+                    operators: admins,
+                    processor: None,
+                    deadline: chrono::Local::now() + chrono::Duration::days(7),
+                    state: TaskState::Unread as i32,
+                    updated: chrono::Local::now(),
+                    created: chrono::Local::now(),
+                    overdue: false,
+                };
+                let id = Task::insert(&mut *tx, &task).await?;
+                created_tasks.push((id, task));
             }
 
             Timeline::insert(
-                &state.db,
+                &mut *tx,
                 &TimelineRow {
                     id: 0,
                     timeline_type: TimelineType::SendInitialFund as i32,
@@ -567,33 +813,30 @@ pub async fn send_funds(
                     timestamp: chrono::Local::now(),
                 },
             )
-            .await
-            .map_err(|e| error!("insert timeline failed: {e}"))
-            .ok();
+            .await?;
             Task::complete(
-                &state.db,
+                &mut *tx,
                 &body.params.proposal_uri,
                 TaskType::SendInitialFund,
                 &body.did,
             )
-            .await
-            .ok();
+            .await?;
         }
         ProposalState::WaitingForMilestoneFund => {
             if let Some((index, next_milestone)) = has_next_milestone(&proposal_sample) {
                 Proposal::update_progress(
-                    &state.db,
+                    &mut *tx,
                     &body.params.proposal_uri,
                     ProposalState::InProgress as i32,
                     index as i32,
                 )
                 .await?;
+                new_state = Some(ProposalState::InProgress as i32);
 
-                Task::insert(
-                    &state.db,
-                    &TaskRow {
+                for task_type in &state.fund_follow_up_tasks {
+                    let task = TaskRow {
                         id: 0,
-                        task_type: TaskType::SubmitMilestoneReport as i32,
+                        task_type: *task_type as i32,
                         message: next_milestone.to_string(),
                         target: body.params.proposal_uri.clone(),
                         operators: admins.clone(),
@@ -602,59 +845,39 @@ pub async fn send_funds(
                         state: TaskState::Unread as i32,
                         updated: chrono::Local::now(),
                         created: chrono::Local::now(),
-                    },
-                )
-                .await
-                .map_err(|e| error!("insert task failed: {e}"))
-                .ok();
-                Task::insert(
-                    &state.db,
-                    &TaskRow {
-                        id: 0,
-                        task_type: TaskType::SubmitDelayReport as i32,
-                        message: next_milestone.to_string(),
-                        target: body.params.proposal_uri.clone(),
-                        operators: admins,
-                        processor: None,
-                        deadline: chrono::Local::now() + chrono::Duration::days(7),
-                        state: TaskState::Unread as i32,
-                        updated: chrono::Local::now(),
-                        created: chrono::Local::now(),
-                    },
-                )
-                .await
-                .map_err(|e| error!("insert task failed: {e}"))
-                .ok();
+                        overdue: false,
+                    };
+                    let id = Task::insert(&mut *tx, &task).await?;
+                    created_tasks.push((id, task));
+                }
             } else {
                 Proposal::update_state(
-                    &state.db,
+                    &mut *tx,
                     &body.params.proposal_uri,
                     ProposalState::WaitingForAcceptanceReport as i32,
                 )
                 .await?;
+                new_state = Some(ProposalState::WaitingForAcceptanceReport as i32);
 
-                Task::insert(
-                    &state.db,
-                    &TaskRow {
-                        id: 0,
-                        task_type: TaskType::SubmitAcceptanceReport as i32,
-                        message: "SubmitAcceptanceReport".to_string(),
-                        target: body.params.proposal_uri.clone(),
-                        operators: admins,
-                        processor: None,
-                        deadline: chrono::Local::now() + chrono::Duration::days(7),
-                        state: TaskState::Unread as i32,
-                        updated: chrono::Local::now(),
-                        created: chrono::Local::now(),
-                    },
-                )
-                .await
-                .map_err(|e| error!("insert task failed: {e}"))
-                .ok();
+                let task = TaskRow {
+                    id: 0,
+                    task_type: TaskType::SubmitAcceptanceReport as i32,
+                    message: "SubmitAcceptanceReport".to_string(),
+                    target: body.params.proposal_uri.clone(),
+                    operators: admins,
+                    processor: None,
+                    deadline: chrono::Local::now() + chrono::Duration::days(7),
+                    state: TaskState::Unread as i32,
+                    updated: chrono::Local::now(),
+                    created: chrono::Local::now(),
+                    overdue: false,
+                };
+                let id = Task::insert(&mut *tx, &task).await?;
+                created_tasks.push((id, task));
             }
 
             Timeline::insert(
-                &state.db,
+                &mut *tx,
                 &TimelineRow {
                     id: 0,
                     timeline_type: TimelineType::SendMilestoneFund as i32,
@@ -668,21 +891,28 @@ pub async fn send_funds(
                     timestamp: chrono::Local::now(),
                 },
             )
-            .await
-            .map_err(|e| error!("insert timeline failed: {e}"))
-            .ok();
+            .await?;
             Task::complete(
-                &state.db,
+                &mut *tx,
                 &body.params.proposal_uri,
                 TaskType::SendMilestoneFund,
                 &body.did,
             )
-            .await
-            .ok();
+            .await?;
         }
         _ => {}
     }
 
+    tx.commit()
+        .await
+        .map_err(|e| AppError::ExecSqlFailed(e.to_string()))?;
+    if let Some(new_state) = new_state {
+        state.proposal_state_changed(&body.params.proposal_uri, new_state);
+    }
+    for (id, task) in &created_tasks {
+        state.task_created(*id, task).await;
+    }
+
     Ok(ok_simple())
 }
 
@@ -705,27 +935,13 @@ impl SignedParam for SubmitReportParams {
 #[utoipa::path(
     post,
     path = "/api/task/submit_milestone_report",
-    description = "提交里程碑报告"
+    description = "提交里程碑报告",
+    request_body = SignedBody<SubmitReportParams>
 )]
 pub async fn submit_milestone_report(
     State(state): State<AppView>,
-    Json(body): Json<SignedBody<SubmitReportParams>>,
+    AdminSignedBody(body): AdminSignedBody<SubmitReportParams>,
 ) -> Result<impl IntoResponse, AppError> {
-    body.validate()
-        .map_err(|e| AppError::ValidateFailed(e.to_string()))?;
-
-    let (sql, value) = Administrator::build_select()
-        .and_where(Expr::col(Administrator::Did).eq(body.did.clone()))
-        .build_sqlx(PostgresQueryBuilder);
-    let _admin_row: AdministratorRow = query_as_with(&sql, value)
-        .fetch_one(&state.db)
-        .await
-        .map_err(|e| AppError::ValidateFailed(format!("not administrator: {e}")))?;
-
-    body.verify_signature(&state.indexer_did_url)
-        .await
-        .map_err(|e| AppError::ValidateFailed(e.to_string()))?;
-
     let (sql, value) = Proposal::build_sample()
         .and_where(Expr::col(Proposal::Uri).eq(body.params.proposal_uri.clone()))
         .build_sqlx(PostgresQueryBuilder);
@@ -780,27 +996,13 @@ pub async fn submit_milestone_report(
 #[utoipa::path(
     post,
     path = "/api/task/submit_delay_report",
-    description = "提交延期报告"
+    description = "提交延期报告",
+    request_body = SignedBody<SubmitReportParams>
 )]
 pub async fn submit_delay_report(
     State(state): State<AppView>,
-    Json(body): Json<SignedBody<SubmitReportParams>>,
+    AdminSignedBody(body): AdminSignedBody<SubmitReportParams>,
 ) -> Result<impl IntoResponse, AppError> {
-    body.validate()
-        .map_err(|e| AppError::ValidateFailed(e.to_string()))?;
-
-    let (sql, value) = Administrator::build_select()
-        .and_where(Expr::col(Administrator::Did).eq(body.did.clone()))
-        .build_sqlx(PostgresQueryBuilder);
-    let _admin_row: AdministratorRow = query_as_with(&sql, value)
-        .fetch_one(&state.db)
-        .await
-        .map_err(|e| AppError::ValidateFailed(format!("not administrator: {e}")))?;
-
-    body.verify_signature(&state.indexer_did_url)
-        .await
-        .map_err(|e| AppError::ValidateFailed(e.to_string()))?;
-
     let (sql, value) = Proposal::build_sample()
         .and_where(Expr::col(Proposal::Uri).eq(body.params.proposal_uri.clone()))
         .build_sqlx(PostgresQueryBuilder);
@@ -855,27 +1057,13 @@ pub async fn submit_delay_report(
 #[utoipa::path(
     post,
     path = "/api/task/submit_acceptance_report",
-    description = "提交结项报告"
+    description = "提交结项报告",
+    request_body = SignedBody<SubmitReportParams>
 )]
 pub async fn submit_acceptance_report(
     State(state): State<AppView>,
-    Json(body): Json<SignedBody<SubmitReportParams>>,
+    AdminSignedBody(body): AdminSignedBody<SubmitReportParams>,
 ) -> Result<impl IntoResponse, AppError> {
-    body.validate()
-        .map_err(|e| AppError::ValidateFailed(e.to_string()))?;
-
-    let (sql, value) = Administrator::build_select()
-        .and_where(Expr::col(Administrator::Did).eq(body.did.clone()))
-        .build_sqlx(PostgresQueryBuilder);
-    let _admin_row: AdministratorRow = query_as_with(&sql, value)
-        .fetch_one(&state.db)
-        .await
-        .map_err(|e| AppError::ValidateFailed(format!("not administrator: {e}")))?;
-
-    body.verify_signature(&state.indexer_did_url)
-        .await
-        .map_err(|e| AppError::ValidateFailed(e.to_string()))?;
-
     let (sql, value) = Proposal::build_sample()
         .and_where(Expr::col(Proposal::Uri).eq(body.params.proposal_uri.clone()))
         .build_sqlx(PostgresQueryBuilder);
@@ -890,12 +1078,9 @@ pub async fn submit_acceptance_report(
         ));
     }
 
-    Proposal::update_state(
-        &state.db,
-        &body.params.proposal_uri,
-        ProposalState::Completed as i32,
-    )
-    .await?;
+    state
+        .update_proposal_state(&body.params.proposal_uri, ProposalState::Completed as i32)
+        .await?;
 
     Timeline::insert(
         &state.db,
@@ -933,34 +1118,20 @@ impl SignedParam for RectificationVoteParams {
 #[utoipa::path(
     post,
     path = "/api/task/rectification_vote",
-    description = "发起整改投票"
+    description = "发起整改投票",
+    request_body = SignedBody<RectificationVoteParams>
 )]
 pub async fn rectification_vote(
     State(state): State<AppView>,
-    Json(body): Json<SignedBody<RectificationVoteParams>>,
+    AdminSignedBody(body): AdminSignedBody<RectificationVoteParams>,
 ) -> Result<impl IntoResponse, AppError> {
-    body.validate()
-        .map_err(|e| AppError::ValidateFailed(e.to_string()))?;
-
-    let (sql, value) = Administrator::build_select()
-        .and_where(Expr::col(Administrator::Did).eq(body.did.clone()))
-        .build_sqlx(PostgresQueryBuilder);
-    let _admin_row: AdministratorRow = query_as_with(&sql, value)
-        .fetch_one(&state.db)
-        .await
-        .map_err(|e| AppError::ValidateFailed(format!("not administrator: {e}")))?;
-
-    body.verify_signature(&state.indexer_did_url)
-        .await
-        .map_err(|e| AppError::ValidateFailed(e.to_string()))?;
-
     let SignedBody::<RectificationVoteParams> { params, did, .. } = body;
 
-    let (sql, values) = Proposal::build_select(None)
+    let (sql, values) = Proposal::build_sample()
         .and_where(Expr::col(Proposal::Uri).eq(&params.proposal_uri))
         .build_sqlx(PostgresQueryBuilder);
 
-    let proposal_row: ProposalRow = query_as_with(&sql, values.clone())
+    let proposal_row: ProposalSample = query_as_with(&sql, values.clone())
         .fetch_one(&state.db)
         .await
         .map_err(|e| {
@@ -1026,31 +1197,20 @@ impl SignedParam for RectificationParams {
     }
 }
 
-#[utoipa::path(post, path = "/api/task/rectification")]
+#[utoipa::path(
+    post,
+    path = "/api/task/rectification",
+    request_body = SignedBody<RectificationParams>
+)]
 pub async fn rectification(
     State(state): State<AppView>,
-    Json(body): Json<SignedBody<RectificationParams>>,
+    AdminSignedBody(body): AdminSignedBody<RectificationParams>,
 ) -> Result<impl IntoResponse, AppError> {
-    body.validate()
-        .map_err(|e| AppError::ValidateFailed(e.to_string()))?;
-
-    let (sql, value) = Administrator::build_select()
-        .and_where(Expr::col(Administrator::Did).eq(body.did.clone()))
-        .build_sqlx(PostgresQueryBuilder);
-    let _admin_row: AdministratorRow = query_as_with(&sql, value)
-        .fetch_one(&state.db)
-        .await
-        .map_err(|e| AppError::ValidateFailed(format!("not administrator: {e}")))?;
-
-    body.verify_signature(&state.indexer_did_url)
-        .await
-        .map_err(|e| AppError::ValidateFailed(e.to_string()))?;
-
-    let (sql, values) = Proposal::build_select(None)
+    let (sql, values) = Proposal::build_sample()
         .and_where(Expr::col(Proposal::Uri).eq(&body.params.proposal_uri))
         .build_sqlx(PostgresQueryBuilder);
 
-    let proposal_row: ProposalRow = query_as_with(&sql, values.clone())
+    let proposal_row: ProposalSample = query_as_with(&sql, values.clone())
         .fetch_one(&state.db)
         .await
         .map_err(|e| {
@@ -1100,13 +1260,13 @@ pub async fn rectification(
 
     let admins = Administrator::fetch_all(&state.db)
         .await
-        .iter()
-        .map(|admin| admin.did.clone())
+        .map_err(|e| AppError::ExecSqlFailed(e.to_string()))?
+        .into_iter()
+        .map(|admin| admin.did)
         .collect::<Vec<_>>();
 
-    Task::insert(
-        &state.db,
-        &TaskRow {
+    state
+        .insert_task(&TaskRow {
             id: 0,
             task_type: TaskType::SubmitMilestoneReport as i32,
             message: body.params.progress.to_string(),
@@ -1117,14 +1277,13 @@ pub async fn rectification(
             state: TaskState::Unread as i32,
             updated: chrono::Local::now(),
             created: chrono::Local::now(),
-        },
-    )
-    .await
-    .map_err(|e| error!("insert task failed: {e}"))
-    .ok();
-    Task::insert(
-        &state.db,
-        &TaskRow {
+            overdue: false,
+        })
+        .await
+        .map_err(|e| error!("insert task failed: {e}"))
+        .ok();
+    state
+        .insert_task(&TaskRow {
             id: 0,
             task_type: TaskType::SubmitDelayReport as i32,
             message: body.params.progress.to_string(),
@@ -1135,11 +1294,11 @@ pub async fn rectification(
             state: TaskState::Unread as i32,
             updated: chrono::Local::now(),
             created: chrono::Local::now(),
-        },
-    )
-    .await
-    .map_err(|e| error!("insert task failed: {e}"))
-    .ok();
+            overdue: false,
+        })
+        .await
+        .map_err(|e| error!("insert task failed: {e}"))
+        .ok();
 
     Task::complete(
         &state.db,
@@ -25,15 +25,23 @@ use serde_json::json;
 pub struct TimelineQuery {
     #[validate(length(min = 1))]
     pub uri: String,
-    /// number of items to return
-    pub limit: u64,
+    /// 只返回该类型的记录
+    pub timeline_type: Option<i32>,
+    #[validate(range(min = 1))]
+    pub page: u64,
+    /// number of items per page
+    #[validate(range(min = 1))]
+    #[serde(alias = "limit")]
+    pub per_page: u64,
 }
 
 impl Default for TimelineQuery {
     fn default() -> Self {
         Self {
             uri: String::new(),
-            limit: 50,
+            timeline_type: None,
+            page: 1,
+            per_page: 50,
         }
     }
 }
@@ -46,6 +54,7 @@ pub async fn get(
     query
         .validate()
         .map_err(|e| AppError::ValidateFailed(e.to_string()))?;
+    let offset = query.per_page * (query.page - 1);
 
     let (sql, values) = sea_query::Query::select()
         .columns([
@@ -57,9 +66,15 @@ pub async fn get(
             (Timeline::Table, Timeline::Timestamp),
         ])
         .from(Timeline::Table)
-        .and_where(Expr::col(Timeline::Target).eq(query.uri))
+        .and_where(Expr::col(Timeline::Target).eq(&query.uri))
+        .and_where_option(
+            query
+                .timeline_type
+                .map(|t| Expr::col(Timeline::TimelineType).eq(t)),
+        )
         .order_by(Timeline::Timestamp, Order::Desc)
-        .limit(std::cmp::min(query.limit, 100))
+        .limit(std::cmp::min(query.per_page, 100))
+        .offset(offset)
         .build_sqlx(PostgresQueryBuilder);
 
     let rows: Vec<TimelineRow> = sqlx::query_as_with(&sql, values)
@@ -67,6 +82,21 @@ pub async fn get(
         .await
         .map_err(|e| eyre!("exec sql failed: {e}"))?;
 
+    let (sql, values) = sea_query::Query::select()
+        .expr(Expr::col((Timeline::Table, Timeline::Id)).count())
+        .from(Timeline::Table)
+        .and_where(Expr::col(Timeline::Target).eq(&query.uri))
+        .and_where_option(
+            query
+                .timeline_type
+                .map(|t| Expr::col(Timeline::TimelineType).eq(t)),
+        )
+        .build_sqlx(PostgresQueryBuilder);
+    let total: (i64,) = sqlx::query_as_with(&sql, values)
+        .fetch_one(&state.db)
+        .await
+        .map_err(|e| eyre!("exec sql failed: {e}"))?;
+
     // Batch fetch authors to avoid N+1 queries
     let repos: Vec<&str> = rows.iter().map(|r| r.operator.as_str()).collect();
     let authors = build_authors(&state, &repos).await;
@@ -76,7 +106,7 @@ pub async fn get(
         views.push(TimelineView {
             id: row.id,
             timeline_type: row.timeline_type,
-            message: row.message,
+            message: TimelineView::parse_message(row.message),
             target: row.target,
             operator: authors
                 .get(&row.operator)
@@ -86,5 +116,10 @@ pub async fn get(
         });
     }
 
-    Ok(ok(views))
+    Ok(ok(json!({
+        "rows": views,
+        "page": query.page,
+        "perPage": query.per_page,
+        "total": total.0
+    })))
 }
@@ -1,4 +1,7 @@
-use color_eyre::{Result, eyre::eyre};
+use color_eyre::{
+    Result,
+    eyre::{OptionExt, eyre},
+};
 use common_x::restful::{
     axum::{
         Json,
@@ -12,27 +15,27 @@ use sea_query::{Expr, ExprTrait, Order, PostgresQueryBuilder};
 use sea_query_sqlx::SqlxBinder;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use sparse_merkle_tree::H256;
 use sqlx::query_as_with;
 use utoipa::{IntoParams, ToSchema};
 use validator::Validate;
 
 use crate::{
     AppView,
-    api::{SignedBody, SignedParam},
-    ckb::get_ckb_addr_by_did,
+    api::{AdminSignedBody, SignedBody, SignedParam, proposal},
+    ckb::TxHash,
     error::AppError,
     lexicon::{
         proposal::{Proposal, ProposalSample},
-        vote::{Vote, VoteRow},
-        vote_meta::{VoteMeta, VoteMetaRow, VoteMetaState},
+        vote::{ViewerVote, Vote, VoteRow, VoteState},
+        vote_meta::{VoteMeta, VoteMetaRow, VoteMetaState, VoteResults},
         voter_list::{VoterList, VoterListRow},
+        voter_proof::VoterProof,
     },
     molecules,
     scheduler::check_vote_finished::{
-        build_vote_results, get_vote_end_block_number, get_vote_end_time,
+        build_vote_results, finalize_vote_meta, get_current_epoch, get_vote_end_block_number,
+        get_vote_end_time, is_vote_ended,
     },
-    smt::{Blake2bHasher, CkbSMT, SMT_VALUE},
 };
 
 #[derive(Debug, Default, Validate, Deserialize, IntoParams)]
@@ -58,26 +61,30 @@ pub async fn bind_list(
         .validate()
         .map_err(|e| AppError::ValidateFailed(e.to_string()))?;
 
-    let ckb_addr = crate::ckb::get_ckb_addr_by_did(
-        &state.ckb_client,
-        &state.ckb_net,
-        query
-            .did
-            .strip_prefix("did:web5")
-            .unwrap_or(&query.did)
-            .strip_prefix("did:ckb")
-            .unwrap_or(&query.did)
-            .strip_prefix("did:plc")
-            .unwrap_or(&query.did),
-    )
-    .await?;
+    let ckb_addr = state
+        .ckb_addr_by_did(&query.did)
+        .await
+        .map_err(AppError::from_did_lookup)?;
 
-    let from_list = crate::indexer_bind::query_by_to(&state.indexer_bind_url, &ckb_addr).await?;
+    let from_list =
+        crate::indexer_bind::query_by_to(&state.indexer_bind_url, state.indexer_timeout, &ckb_addr)
+            .await?;
 
     Ok(ok(from_list))
 }
 
-#[utoipa::path(get, path = "/api/vote/weight", params(CkbAddrQuery))]
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WeightResponse {
+    /// 投票权重，单位 shannon
+    pub weight: u64,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/vote/weight",
+    params(CkbAddrQuery),
+    responses((status = 200, body = WeightResponse))
+)]
 pub async fn weight(
     State(state): State<AppView>,
     Query(query): Query<CkbAddrQuery>,
@@ -90,22 +97,88 @@ pub async fn weight(
         state.ckb_net,
         &state.indexer_bind_url,
         &state.indexer_dao_url,
+        state.indexer_timeout,
         &query.ckb_addr,
         None,
     )
     .await?
     .values()
     .sum();
-    Ok(ok(json!({ "weight": weight })))
+    Ok(ok(WeightResponse { weight }))
+}
+
+#[derive(Debug, Validate, Deserialize, IntoParams)]
+#[serde(default)]
+pub struct VoterListQuery {
+    /// whether to return the lock hashes in `list`
+    pub include_list: bool,
+    /// index of the first lock hash to return
+    pub offset: u32,
+    /// number of lock hashes to return, all of them when absent
+    #[validate(range(min = 1))]
+    pub limit: Option<u32>,
+}
+
+impl Default for VoterListQuery {
+    fn default() -> Self {
+        Self {
+            include_list: true,
+            offset: 0,
+            limit: None,
+        }
+    }
+}
+
+#[derive(sqlx::FromRow, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VoterListPageRow {
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub list: Option<Vec<String>>,
+    pub root_hash: String,
+    pub block_number: i64,
+    pub created: chrono::DateTime<chrono::Local>,
+    /// total number of lock hashes in the voter list
+    pub total: i32,
 }
 
-#[utoipa::path(get, path = "/api/vote/voter_list")]
-pub async fn voter_list(State(state): State<AppView>) -> Result<impl IntoResponse, AppError> {
-    let (sql, value) = VoterList::build_select()
+#[utoipa::path(get, path = "/api/vote/voter_list", params(VoterListQuery))]
+pub async fn voter_list(
+    State(state): State<AppView>,
+    Query(query): Query<VoterListQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    query
+        .validate()
+        .map_err(|e| AppError::ValidateFailed(e.to_string()))?;
+
+    // postgres arrays are 1-based and slice bounds are inclusive
+    let start = query.offset as i64 + 1;
+    let list_expr = if !query.include_list {
+        Expr::cust("null::varchar[] as list")
+    } else if let Some(limit) = query.limit {
+        Expr::cust_with_values(
+            "\"voter_list\".\"list\"[$1:$2] as list",
+            [start, start + limit as i64 - 1],
+        )
+    } else {
+        Expr::cust_with_values("\"voter_list\".\"list\"[$1:] as list", [start])
+    };
+    let (sql, value) = sea_query::Query::select()
+        .columns([
+            (VoterList::Table, VoterList::Id),
+            (VoterList::Table, VoterList::RootHash),
+            (VoterList::Table, VoterList::BlockNumber),
+            (VoterList::Table, VoterList::Created),
+        ])
+        .expr(list_expr)
+        .expr(Expr::cust(
+            "coalesce(cardinality(\"voter_list\".\"list\"), 0) as total",
+        ))
+        .from(VoterList::Table)
         .order_by(VoterList::Created, Order::Desc)
         .limit(1)
         .build_sqlx(PostgresQueryBuilder);
-    let row: VoterListRow = sqlx::query_as_with(&sql, value)
+    let row: VoterListPageRow = sqlx::query_as_with(&sql, value)
         .fetch_one(&state.db)
         .await
         .map_err(|e| {
@@ -115,6 +188,69 @@ pub async fn voter_list(State(state): State<AppView>) -> Result<impl IntoRespons
     Ok(ok(row))
 }
 
+#[derive(Debug, Default, Validate, Deserialize, IntoParams)]
+#[serde(default)]
+pub struct InVoterListQuery {
+    #[validate(length(min = 1))]
+    pub ckb_addr: String,
+    /// 缺省为最新的投票名单
+    pub voter_list_id: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/vote/in_voter_list",
+    params(InVoterListQuery),
+    description = "地址是否在投票名单中"
+)]
+pub async fn in_voter_list(
+    State(state): State<AppView>,
+    Query(query): Query<InVoterListQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    query
+        .validate()
+        .map_err(|e| AppError::ValidateFailed(e.to_string()))?;
+
+    let address = crate::AddressParser::default()
+        .set_network(state.ckb_net)
+        .parse(&query.ckb_addr)?;
+    let lock_hash = ckb_types::packed::Script::from(address.payload()).calc_script_hash();
+    let lock_hash = hex::encode(lock_hash.raw_data());
+
+    let (sql, values) = sea_query::Query::select()
+        .columns([
+            (VoterList::Table, VoterList::Id),
+            (VoterList::Table, VoterList::RootHash),
+        ])
+        .expr(Expr::cust_with_values(
+            "$1 = any(\"voter_list\".\"list\")",
+            [&lock_hash],
+        ))
+        .from(VoterList::Table)
+        .and_where_option(
+            query
+                .voter_list_id
+                .as_ref()
+                .map(|id| Expr::col((VoterList::Table, VoterList::Id)).eq(id)),
+        )
+        .order_by(VoterList::Created, Order::Desc)
+        .limit(1)
+        .build_sqlx(PostgresQueryBuilder);
+    let (voter_list_id, root_hash, in_voter_list): (String, String, Option<bool>) =
+        query_as_with(&sql, values)
+            .fetch_optional(&state.db)
+            .await
+            .map_err(|e| AppError::ExecSqlFailed(e.to_string()))?
+            .ok_or(AppError::NotFound)?;
+
+    Ok(ok(json!({
+        "inVoterList": in_voter_list.unwrap_or(false),
+        "voterListId": voter_list_id,
+        "rootHash": root_hash,
+        "lockHash": lock_hash,
+    })))
+}
+
 #[derive(Debug, Default, Validate, Deserialize, IntoParams)]
 #[serde(default)]
 pub struct ProofQuery {
@@ -132,18 +268,80 @@ pub async fn proof(
         .await
         .map(|r| {
             ok(json!({
-                "smt_root_hash": hex::encode(r.0),
-                "smt_proof": hex::encode(r.1),
+                "smtRootHash": hex::encode(r.root_hash),
+                "smtProof": hex::encode(&r.proof),
+                "voteProof": hex::encode(r.vote_proof().as_slice()),
             }))
         })
-        .map_err(|e| AppError::ValidateFailed(e.to_string()))
+        .map_err(AppError::from_validation)
 }
 
-async fn get_proof(
+/// 查看者是否在该投票的名单中，以及是否已投票
+pub async fn viewer_vote(
     state: &AppView,
-    voter_list_id: &str,
-    ckb_addr: &str,
-) -> Result<(Vec<u8>, Vec<u8>)> {
+    vote_meta_row: &VoteMetaRow,
+    viewer: &str,
+) -> Result<ViewerVote> {
+    let ckb_addr = state.ckb_addr_by_did(viewer).await?;
+    let address = crate::AddressParser::default()
+        .set_network(state.ckb_net)
+        .parse(&ckb_addr)?;
+    let lock_hash = ckb_types::packed::Script::from(address.payload()).calc_script_hash();
+    let (sql, values) = sea_query::Query::select()
+        .expr(Expr::cust_with_values(
+            "$1 = any(\"voter_list\".\"list\")",
+            [hex::encode(lock_hash.raw_data())],
+        ))
+        .from(VoterList::Table)
+        .and_where(Expr::col(VoterList::Id).eq(&vote_meta_row.voter_list_id))
+        .build_sqlx(PostgresQueryBuilder);
+    let eligible: Option<(bool,)> = query_as_with(&sql, values)
+        .fetch_optional(&state.db)
+        .await?;
+
+    // rejected / timed out votes never reached the chain
+    let (sql, values) = sea_query::Query::select()
+        .column(Vote::CandidatesIndex)
+        .from(Vote::Table)
+        .and_where(Expr::col(Vote::VoteMetaId).eq(vote_meta_row.id))
+        .and_where(Expr::col(Vote::Voter).eq(viewer))
+        .and_where(Expr::col(Vote::State).is_in([
+            VoteState::Waiting as i32,
+            VoteState::Committed as i32,
+            VoteState::Finished as i32,
+        ]))
+        .order_by(Vote::Created, Order::Desc)
+        .limit(1)
+        .build_sqlx(PostgresQueryBuilder);
+    let voted: Option<(i32,)> = query_as_with(&sql, values)
+        .fetch_optional(&state.db)
+        .await?;
+
+    Ok(ViewerVote {
+        eligible: eligible.is_some_and(|(e,)| e),
+        has_voted: voted.is_some(),
+        candidates_index: voted.map(|(i,)| i),
+    })
+}
+
+/// 投票者在名单中的 SMT 证明
+pub struct SmtProof {
+    pub root_hash: [u8; 32],
+    pub lock_hash: [u8; 32],
+    pub proof: Vec<u8>,
+}
+
+impl SmtProof {
+    /// 链上投票脚本在 witness 中读取的 VoteProof
+    pub fn vote_proof(&self) -> molecules::VoteProof {
+        molecules::VoteProof::new_builder()
+            .lock_script_hash(molecules::Bytes::from(self.lock_hash.to_vec()))
+            .smt_proof(molecules::Bytes::from(self.proof.clone()))
+            .build()
+    }
+}
+
+async fn get_proof(state: &AppView, voter_list_id: &str, ckb_addr: &str) -> Result<SmtProof> {
     let (sql, values) = VoterList::build_select()
         .and_where(Expr::col(VoterList::Id).eq(voter_list_id))
         .build_sqlx(PostgresQueryBuilder);
@@ -153,51 +351,52 @@ async fn get_proof(
         .await
         .map_err(|e| eyre!(e))?;
 
-    let mut smt_tree = CkbSMT::default();
-    for lock_hash in row.list.iter() {
-        if let Ok(lock_hash) = hex::decode(lock_hash)
-            && let Ok(key) = TryInto::<[u8; 32]>::try_into(lock_hash.as_slice())
-        {
-            smt_tree
-                .update(key.into(), crate::smt::SMT_VALUE.into())
-                .ok();
-        }
-    }
-
-    let smt_root_hash: H256 = *smt_tree.root();
+    let smt_root_hash: [u8; 32] = hex::decode(&row.root_hash)?.as_slice().try_into()?;
 
     let address = crate::AddressParser::default()
         .set_network(state.ckb_net)
-        .parse(ckb_addr)
-        .map_err(|e| eyre!(e))?;
+        .parse(ckb_addr)?;
     let lock_script = ckb_types::packed::Script::from(address.payload());
     let lock_hash = lock_script.calc_script_hash();
     let key: [u8; 32] = lock_hash.raw_data().to_vec().as_slice().try_into()?;
-    let proof = smt_tree
-        .merkle_proof(vec![key.into()])
-        .map_err(|e| eyre!(e))?;
-    let compiled_proof = proof
-        .clone()
-        .compile(vec![key.into()])
-        .map_err(|e| eyre!(e))?;
+    let lock_hash = hex::encode(key);
+
+    if let Some(proof) = VoterProof::get(&state.db, voter_list_id, &lock_hash).await? {
+        return Ok(SmtProof {
+            root_hash: smt_root_hash,
+            lock_hash: key,
+            proof: hex::decode(proof)?,
+        });
+    }
+    if !row.list.contains(&lock_hash) {
+        return Err(eyre!("Not in the voter_list"));
+    }
 
-    let proof: Vec<u8> = compiled_proof.0;
-    let compiled_proof = sparse_merkle_tree::CompiledMerkleProof(proof);
-    let ret = compiled_proof
-        .verify::<Blake2bHasher>(&smt_root_hash, vec![(key.into(), SMT_VALUE.into())])
-        .unwrap_or(false);
-    if ret {
-        Ok((smt_root_hash.as_slice().to_vec(), compiled_proof.0))
-    } else {
-        Err(eyre!("Not in the voter_list"))
+    // 早于证明落库的名单：重建一次并补齐
+    let smt_tree = crate::smt::build_tree(&row.list);
+    if smt_tree.root().as_slice() != smt_root_hash {
+        return Err(eyre!("voter_list root hash mismatch"));
     }
+    let proofs = crate::smt::compiled_proofs(&smt_tree, &row.list)?;
+    VoterProof::insert_all(&state.db, voter_list_id, &proofs)
+        .await
+        .map_err(|e| error!("backfill voter proofs failed: {e}"))
+        .ok();
+    let proof = crate::smt::compiled_proof(&smt_tree, key)?;
+    Ok(SmtProof {
+        root_hash: smt_root_hash,
+        lock_hash: key,
+        proof,
+    })
 }
 
-#[derive(Debug, Default, Validate, Deserialize, Serialize, ToSchema)]
-#[serde(default)]
+#[derive(Debug, Validate, Deserialize, Serialize, ToSchema)]
 pub struct UpdateTxParams {
+    #[serde(default)]
     pub id: i32,
-    pub tx_hash: String,
+    #[schema(value_type = String)]
+    pub tx_hash: TxHash,
+    #[serde(default)]
     pub timestamp: i64,
 }
 
@@ -215,7 +414,7 @@ pub async fn update_meta_tx_hash(
     body.validate()
         .map_err(|e| AppError::ValidateFailed(e.to_string()))?;
 
-    body.verify_signature(&state.indexer_did_url)
+    body.verify_signature(&state.indexer_did_url, state.indexer_timeout)
         .await
         .map_err(|e| AppError::ValidateFailed(e.to_string()))?;
 
@@ -234,19 +433,22 @@ pub async fn update_meta_tx_hash(
         return Err(AppError::ValidateFailed("not creator".to_string()));
     }
 
-    VoteMeta::update_tx_hash(&state.db, body.params.id, &body.params.tx_hash)
+    VoteMeta::update_tx_hash(&state.db, body.params.id, body.params.tx_hash.as_str())
         .await
         .map_err(|e| AppError::ValidateFailed(format!("update vote_meta tx_hash failed: {e}")))?;
 
     Ok(ok_simple())
 }
 
-#[derive(Debug, Default, Validate, Deserialize, Serialize, ToSchema)]
-#[serde(default)]
+#[derive(Debug, Validate, Deserialize, Serialize, ToSchema)]
 pub struct UpdateVoteTxParams {
+    #[serde(default)]
     pub id: i32,
-    pub tx_hash: String,
+    #[schema(value_type = String)]
+    pub tx_hash: TxHash,
+    #[serde(default)]
     pub candidates_index: i32,
+    #[serde(default)]
     pub timestamp: i64,
 }
 
@@ -264,14 +466,51 @@ pub async fn update_vote_tx_hash(
     body.validate()
         .map_err(|e| AppError::ValidateFailed(e.to_string()))?;
 
-    body.verify_signature(&state.indexer_did_url)
+    body.verify_signature(&state.indexer_did_url, state.indexer_timeout)
         .await
         .map_err(|e| AppError::ValidateFailed(e.to_string()))?;
 
+    let (sql, value) = VoteMeta::build_select()
+        .and_where(Expr::col(VoteMeta::Id).eq(body.params.id))
+        .build_sqlx(PostgresQueryBuilder);
+    let vote_meta_row: VoteMetaRow = query_as_with(&sql, value)
+        .fetch_one(&state.db)
+        .await
+        .map_err(|e| AppError::ValidateFailed(format!("not vote_meta: {e}")))?;
+
+    match VoteMetaState::from(vote_meta_row.state) {
+        VoteMetaState::Committed => (),
+        VoteMetaState::Finished => {
+            return Err(AppError::ValidateFailed("voting has closed".to_string()));
+        }
+        _ => {
+            return Err(AppError::ValidateFailed(format!(
+                "vote_meta not already: {}",
+                vote_meta_row.state
+            )));
+        }
+    }
+    let Some(block_number) = vote_meta_row.block_number else {
+        return Err(AppError::ValidateFailed(
+            "vote_meta has no block_number".into(),
+        ));
+    };
+    let end_time = get_vote_end_time(&state, &vote_meta_row, block_number as u64).await?;
+    let (current_epoch_number, current_epoch_index, current_epoch_length) =
+        get_current_epoch(&state).await?;
+    if is_vote_ended(
+        end_time,
+        current_epoch_number,
+        current_epoch_index,
+        current_epoch_length,
+    ) {
+        return Err(AppError::ValidateFailed("voting has closed".to_string()));
+    }
+
     let mut vote_row = VoteRow {
         id: -1,
         state: 0,
-        tx_hash: Some(body.params.tx_hash),
+        tx_hash: Some(body.params.tx_hash.into()),
         vote_meta_id: body.params.id,
         candidates_index: body.params.candidates_index,
         voter: body.did.clone(),
@@ -319,22 +558,27 @@ pub async fn prepare(
         .await
         .map_err(|e| AppError::ValidateFailed(format!("not vote_meta: {e}")))?;
 
-    if vote_meta_row.state != (VoteMetaState::Committed as i32) {
+    if VoteMetaState::from(vote_meta_row.state) != VoteMetaState::Committed {
         return Err(AppError::ValidateFailed(format!(
             "vote_meta not already: {}",
             vote_meta_row.state
         )));
     }
 
-    let vote_addr = get_ckb_addr_by_did(&state.ckb_client, &state.ckb_net, &body.did).await?;
+    let vote_addr = state
+        .ckb_addr_by_did(&body.did)
+        .await
+        .map_err(AppError::from_did_lookup)?;
 
     let proof = get_proof(&state, &vote_meta_row.voter_list_id, &vote_addr).await?;
 
     Ok(ok(json!({
-        "vote_meta": vote_meta_row,
+        "voteMeta": vote_meta_row,
         "did": body.did,
-        "vote_addr": vote_addr,
-        "proof": proof.1
+        "voteAddr": vote_addr,
+        "proof": proof.proof,
+        // 可直接放入 witness 的 VoteProof molecule
+        "voteProof": hex::encode(proof.vote_proof().as_slice()),
     })))
 }
 
@@ -374,7 +618,32 @@ pub struct DetailQuery {
     pub id: i32,
 }
 
-#[utoipa::path(get, path = "/api/vote/detail", params(DetailQuery))]
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct VoteDetailResponse {
+    pub vote_meta: VoteMetaRow,
+    /// 链上 vote meta cell 中的候选项，读取失败时为空
+    pub onchain_candidates: Option<Vec<String>>,
+    /// 链上候选项与数据库记录是否一致
+    pub candidates_matched: Option<bool>,
+    pub vote_sum: u64,
+    pub valid_vote_sum: u64,
+    pub valid_weight_sum: u64,
+    pub candidate_ratios: Vec<f64>,
+    pub candidate_votes: Vec<u64>,
+}
+
+/// 链上候选项与数据库记录是否一致；票中记录的是候选项下标，顺序不同也视为不一致
+fn candidates_match(onchain: &[String], stored: &[String]) -> bool {
+    onchain == stored
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/vote/detail",
+    params(DetailQuery),
+    responses((status = 200, body = VoteDetailResponse))
+)]
 pub async fn detail(
     State(state): State<AppView>,
     Query(query): Query<DetailQuery>,
@@ -394,9 +663,10 @@ pub async fn detail(
             AppError::ExecSqlFailed(e.to_string())
         })?;
 
-    if vote_meta_row.state != (VoteMetaState::Committed as i32)
-        && vote_meta_row.state != (VoteMetaState::Finished as i32)
-    {
+    if !matches!(
+        VoteMetaState::from(vote_meta_row.state),
+        VoteMetaState::Committed | VoteMetaState::Finished
+    ) {
         return Err(AppError::ValidateFailed(format!(
             "vote_meta not already: {}",
             vote_meta_row.state
@@ -415,25 +685,178 @@ pub async fn detail(
         return Err(AppError::ValidateFailed("vote_meta has no tx_hash".into()));
     };
 
-    let end_time = get_vote_end_time(&state, vote_meta_row.proposal_state, block_number).await?;
+    let end_time = get_vote_end_time(&state, &vote_meta_row, block_number).await?;
     let end_block_number = get_vote_end_block_number(&state, end_time).await?;
 
+    // the on-chain cell is the source of truth for candidates
+    let onchain_candidates = get_onchain_candidates(&state, &tx_hash)
+        .await
+        .map_err(|e| {
+            error!(
+                "VoteMeta({}) fetch on-chain candidates failed: {e}",
+                query.id
+            )
+        })
+        .ok();
+    let candidates_matched = onchain_candidates
+        .as_deref()
+        .map(|candidates| candidates_match(candidates, &vote_meta_row.candidates));
+    if candidates_matched == Some(false) {
+        error!(
+            "VoteMeta({}) candidates {:?} differ from on-chain {:?}",
+            query.id, vote_meta_row.candidates, onchain_candidates
+        );
+    }
+
     let vote_results = build_vote_results(
         &state,
         Some(tx_hash),
-        &vote_meta_row.candidates,
+        onchain_candidates
+            .as_ref()
+            .unwrap_or(&vote_meta_row.candidates),
         end_time,
         end_block_number,
         false,
     )
     .await?;
 
+    Ok(ok(VoteDetailResponse {
+        vote_meta: vote_meta_row,
+        onchain_candidates,
+        candidates_matched,
+        vote_sum: vote_results.vote_sum,
+        valid_vote_sum: vote_results.valid_vote_sum,
+        valid_weight_sum: vote_results.valid_weight_sum,
+        candidate_ratios: vote_results.candidate_ratios(),
+        candidate_votes: vote_results.candidate_votes,
+    }))
+}
+
+async fn get_onchain_candidates(state: &AppView, tx_hash: &str) -> Result<Vec<String>> {
+    let vote_meta = get_onchain_vote_meta(state, tx_hash).await?;
+    Ok(vote_meta
+        .candidates()
+        .into_iter()
+        .map(|c| String::from_utf8_lossy(&c.raw_data()).to_string())
+        .collect())
+}
+
+/// vote_meta 交易第一个输出的 cell data
+async fn get_onchain_vote_meta(state: &AppView, tx_hash: &str) -> Result<molecules::VoteMeta> {
+    let tx_hash: TxHash = tx_hash.parse()?;
+    let tx = state
+        .ckb_client
+        .get_transaction(tx_hash.h256())
+        .await?
+        .and_then(|t| t.transaction)
+        .and_then(|t| {
+            if let ckb_jsonrpc_types::Either::Left(tx) = t.inner {
+                Some(tx)
+            } else {
+                None
+            }
+        })
+        .ok_or_eyre("vote_meta tx not found")?;
+    let data = tx
+        .inner
+        .outputs_data
+        .first()
+        .ok_or_eyre("vote_meta tx has no outputs data")?;
+    molecules::VoteMeta::from_slice(data.as_bytes())
+        .map_err(|e| eyre!("invalid vote_meta cell data: {e}"))
+}
+
+#[derive(Debug, Default, Validate, Deserialize, Serialize, ToSchema)]
+#[serde(default)]
+pub struct VerifyRootParams {
+    #[validate(length(min = 1))]
+    pub voter_list_id: String,
+    pub timestamp: i64,
+}
+
+impl SignedParam for VerifyRootParams {
+    fn timestamp(&self) -> i64 {
+        self.timestamp
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/vote/verify_root",
+    description = "按名单重建 SMT，与库中 root 及链上 vote_meta 中的 root 逐一比对；会逐个查询链上交易，仅限管理员",
+    request_body = SignedBody<VerifyRootParams>
+)]
+pub async fn verify_root(
+    State(state): State<AppView>,
+    AdminSignedBody(body): AdminSignedBody<VerifyRootParams>,
+) -> Result<impl IntoResponse, AppError> {
+    let query = body.params;
+
+    let (sql, values) = VoterList::build_select()
+        .and_where(Expr::col(VoterList::Id).eq(&query.voter_list_id))
+        .build_sqlx(PostgresQueryBuilder);
+    let row: VoterListRow = query_as_with(&sql, values)
+        .fetch_optional(&state.db)
+        .await
+        .map_err(|e| AppError::ExecSqlFailed(e.to_string()))?
+        .ok_or(AppError::NotFound)?;
+
+    let computed_root = hex::encode(crate::smt::build_tree(&row.list).root().as_slice());
+    if computed_root != row.root_hash {
+        warn!(
+            "voter list {} root mismatch: stored {}, computed {computed_root}",
+            row.id, row.root_hash
+        );
+    }
+
+    let (sql, values) = VoteMeta::build_select()
+        .and_where(Expr::col(VoteMeta::VoterListId).eq(&row.id))
+        .and_where(Expr::col(VoteMeta::TxHash).is_not_null())
+        .order_by(VoteMeta::Created, Order::Asc)
+        .build_sqlx(PostgresQueryBuilder);
+    let vote_meta_rows: Vec<VoteMetaRow> = query_as_with(&sql, values)
+        .fetch_all(&state.db)
+        .await
+        .map_err(|e| AppError::ExecSqlFailed(e.to_string()))?;
+
+    let mut onchain = vec![];
+    for vote_meta_row in vote_meta_rows {
+        let tx_hash = vote_meta_row.tx_hash.unwrap_or_default();
+        let view = match get_onchain_vote_meta(&state, &tx_hash).await {
+            Ok(vote_meta) => {
+                let root = vote_meta
+                    .smt_root_hash()
+                    .to_opt()
+                    .map(|root| hex::encode(root.as_slice()));
+                let matched = root.as_deref() == Some(row.root_hash.as_str());
+                if !matched {
+                    warn!(
+                        "vote_meta {} on-chain root {root:?} differs from voter list {}",
+                        vote_meta_row.id, row.id
+                    );
+                }
+                json!({
+                    "voteMetaId": vote_meta_row.id,
+                    "txHash": tx_hash,
+                    "root": root,
+                    "matched": matched,
+                })
+            }
+            Err(e) => json!({
+                "voteMetaId": vote_meta_row.id,
+                "txHash": tx_hash,
+                "error": e.to_string(),
+            }),
+        };
+        onchain.push(view);
+    }
+
     Ok(ok(json!({
-        "vote_meta": vote_meta_row,
-        "vote_sum": vote_results.vote_sum,
-        "valid_vote_sum": vote_results.valid_vote_sum,
-        "valid_weight_sum": vote_results.valid_weight_sum,
-        "candidate_votes": vote_results.candidate_votes
+        "voterListId": row.id,
+        "storedRoot": row.root_hash,
+        "computedRoot": computed_root,
+        "storedMatchesComputed": computed_root == row.root_hash,
+        "onchain": onchain,
     })))
 }
 
@@ -457,6 +880,140 @@ impl Default for ListSelfQuery {
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/vote/result",
+    params(DetailQuery),
+    description = "投票结果及其依据：适用门槛、计入门槛的总票数与赞成 / 反对比例"
+)]
+pub async fn result(
+    State(state): State<AppView>,
+    Query(query): Query<DetailQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let (sql, value) = VoteMeta::build_select()
+        .and_where(Expr::col(VoteMeta::Id).eq(query.id))
+        .build_sqlx(PostgresQueryBuilder);
+    let vote_meta_row: VoteMetaRow = query_as_with(&sql, value)
+        .fetch_one(&state.db)
+        .await
+        .map_err(|_| AppError::NotFound)?;
+
+    let (sql, value) = Proposal::build_sample()
+        .and_where(Expr::col(Proposal::Uri).eq(&vote_meta_row.proposal_uri))
+        .build_sqlx(PostgresQueryBuilder);
+    let proposal_sample: ProposalSample = query_as_with(&sql, value)
+        .fetch_one(&state.db)
+        .await
+        .map_err(|_| AppError::NotFound)?;
+    let record = proposal_sample
+        .parse_record()
+        .map_err(|e| AppError::ValidateFailed(e.to_string()))?;
+
+    let threshold = proposal::vote_threshold(
+        vote_meta_row.proposal_state,
+        &record.data,
+        &state.governance,
+    );
+    let result = proposal::vote_result(&vote_meta_row, &proposal_sample.record, &state.governance);
+    // 投票结束前 results 为空，只返回门槛
+    let results = vote_meta_row
+        .results
+        .clone()
+        .and_then(|results| serde_json::from_value::<VoteResults>(results).ok());
+    let (valid_weight_sum, quorum_weight, ratio) = match &results {
+        Some(results) => (
+            Some(results.valid_weight_sum),
+            Some(proposal::quorum_weight(
+                &vote_meta_row.candidates,
+                results,
+                &state.governance,
+            )),
+            threshold.as_ref().map(|threshold| {
+                proposal::decision_ratio(&vote_meta_row.candidates, results, threshold.rejection)
+            }),
+        ),
+        None => (None, None, None),
+    };
+
+    Ok(ok(json!({
+        "id": vote_meta_row.id,
+        "proposalUri": vote_meta_row.proposal_uri,
+        "proposalState": vote_meta_row.proposal_state,
+        "voteResult": result as u32,
+        "threshold": threshold,
+        "abstainCountsForQuorum": state.governance.abstain_counts_for_quorum,
+        "validWeightSum": valid_weight_sum,
+        "quorumWeight": quorum_weight,
+        "ratio": ratio,
+    })))
+}
+
+#[derive(Debug, Default, Validate, Deserialize, Serialize, ToSchema)]
+#[serde(default)]
+pub struct FinalizeVoteParams {
+    pub vote_meta_id: i32,
+    pub timestamp: i64,
+}
+
+impl SignedParam for FinalizeVoteParams {
+    fn timestamp(&self) -> i64 {
+        self.timestamp
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/vote/finalize",
+    description = "手动结算已到期但未被定时任务处理的投票，已结算的投票不重复处理",
+    request_body = SignedBody<FinalizeVoteParams>
+)]
+pub async fn finalize(
+    State(state): State<AppView>,
+    AdminSignedBody(body): AdminSignedBody<FinalizeVoteParams>,
+) -> Result<impl IntoResponse, AppError> {
+    let (sql, value) = VoteMeta::build_select()
+        .and_where(Expr::col(VoteMeta::Id).eq(body.params.vote_meta_id))
+        .build_sqlx(PostgresQueryBuilder);
+    let vote_meta_row: VoteMetaRow = query_as_with(&sql, value)
+        .fetch_optional(&state.db)
+        .await
+        .map_err(|e| AppError::ExecSqlFailed(e.to_string()))?
+        .ok_or(AppError::NotFound)?;
+
+    match VoteMetaState::from(vote_meta_row.state) {
+        VoteMetaState::Finished => return Ok(ok(json!({ "finalized": false }))),
+        VoteMetaState::Committed => {}
+        _ => {
+            return Err(AppError::ValidateFailed(format!(
+                "vote_meta not committed: {}",
+                vote_meta_row.state
+            )));
+        }
+    }
+    let block_number = vote_meta_row
+        .block_number
+        .ok_or_else(|| AppError::ValidateFailed("vote_meta has no block_number".into()))?;
+
+    let end_time = get_vote_end_time(&state, &vote_meta_row, block_number as u64).await?;
+    let (current_epoch_number, current_epoch_index, current_epoch_length) =
+        get_current_epoch(&state).await?;
+    if !is_vote_ended(
+        end_time,
+        current_epoch_number,
+        current_epoch_index,
+        current_epoch_length,
+    ) {
+        return Err(AppError::ValidateFailed("vote not ended yet".into()));
+    }
+
+    info!(
+        "vote_meta {} finalized manually by {}",
+        vote_meta_row.id, body.did
+    );
+    let finalized = finalize_vote_meta(&state, vote_meta_row, end_time).await?;
+    Ok(ok(json!({ "finalized": finalized })))
+}
+
 #[utoipa::path(get, path = "/api/vote/list_self", params(ListSelfQuery))]
 pub async fn list_self(
     State(state): State<AppView>,
@@ -527,7 +1084,7 @@ pub async fn list_self(
     for row in &rows {
         let mut view = json!(row);
         if let Some(vote_meta_row) = vote_meta_map.get(&row.vote_meta_id) {
-            view["vote_meta"] = json!(vote_meta_row);
+            view["voteMeta"] = json!(vote_meta_row);
             if let Some(proposal_row) = proposal_map.get(&vote_meta_row.proposal_uri) {
                 view["proposal"] = json!(proposal_row);
             }
@@ -548,7 +1105,7 @@ pub async fn list_self(
     Ok(ok(json!({
         "rows": views,
         "page": query.page,
-        "per_page": query.per_page,
+        "perPage": query.per_page,
         "total":  total.0
     })))
 }
@@ -564,19 +1121,9 @@ pub async fn build_vote_meta(
 
     let voter_list_row: VoterListRow = query_as_with(&sql, values.clone()).fetch_one(db).await?;
 
-    let mut smt_tree = CkbSMT::default();
-    for lock_hash in voter_list_row.list.iter() {
-        if let Ok(lock_hash) = hex::decode(lock_hash)
-            && let Ok(key) = TryInto::<[u8; 32]>::try_into(lock_hash.as_slice())
-        {
-            smt_tree
-                .update(key.into(), crate::smt::SMT_VALUE.into())
-                .ok();
-        }
-    }
-
-    let smt_root = smt_tree.root().as_slice();
-    let smt_root_hash: [u8; 32] = smt_root.try_into()?;
+    let smt_root_hash: [u8; 32] = hex::decode(&voter_list_row.root_hash)?
+        .as_slice()
+        .try_into()?;
 
     Ok(molecules::VoteMeta::new_builder()
         .candidates(molecules::StringVec::from(
@@ -612,3 +1159,27 @@ pub async fn build_vote_meta(
         )
         .build())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn update_vote_tx_params_require_tx_hash() {
+        let params = serde_json::json!({ "id": 1, "candidates_index": 1, "timestamp": 0 });
+        assert!(serde_json::from_value::<UpdateVoteTxParams>(params).is_err());
+    }
+
+    #[test]
+    fn candidates_match_requires_same_order() {
+        let stored: Vec<String> = ["Abstain", "Agree", "Against"].map(String::from).to_vec();
+        assert!(candidates_match(&stored, &stored));
+
+        let reordered: Vec<String> = ["Agree", "Abstain", "Against"].map(String::from).to_vec();
+        assert!(!candidates_match(&reordered, &stored));
+
+        let divergent: Vec<String> = ["Abstain", "Yes", "No"].map(String::from).to_vec();
+        assert!(!candidates_match(&divergent, &stored));
+        assert!(!candidates_match(&stored[..2], &stored));
+    }
+}
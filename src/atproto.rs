@@ -8,21 +8,127 @@ pub const NSID_REPLY: &str = "app.dao.reply";
 pub const NSID_LIKE: &str = "app.dao.like";
 pub const NSID_PROFILE: &str = "app.actor.profile";
 
+/// app view 会索引的集合，其余集合的记录直接忽略
+pub const KNOWN_COLLECTIONS: [&str; 4] = [NSID_PROPOSAL, NSID_REPLY, NSID_LIKE, NSID_PROFILE];
+
+/// PDS 返回非 2xx，保留 atproto 标准错误体 `{error, message}` 或原始响应文本
+#[derive(Debug)]
+pub struct PdsError {
+    pub status: u16,
+    pub error: Option<String>,
+    pub message: String,
+}
+
+impl std::fmt::Display for PdsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.error {
+            Some(error) => write!(f, "pds {} {error}: {}", self.status, self.message),
+            None => write!(f, "pds {}: {}", self.status, self.message),
+        }
+    }
+}
+
+impl std::error::Error for PdsError {}
+
+/// 错误页可能很大，只保留开头
+const PDS_ERROR_BODY_LIMIT: usize = 512;
+
+async fn read_json(response: reqwest::Response) -> Result<Value> {
+    let status = response.status();
+    if status.is_success() {
+        return response
+            .json::<Value>()
+            .await
+            .map_err(|e| eyre!("decode pds response failed: {e}"));
+    }
+    let body = response.text().await.unwrap_or_default();
+    let parsed = serde_json::from_str::<Value>(&body).ok();
+    let error = parsed
+        .as_ref()
+        .and_then(|v| v["error"].as_str())
+        .map(str::to_string);
+    let message = parsed
+        .as_ref()
+        .and_then(|v| v["message"].as_str())
+        .map(str::to_string)
+        .unwrap_or_else(|| body.chars().take(PDS_ERROR_BODY_LIMIT).collect());
+    Err(PdsError {
+        status: status.as_u16(),
+        error,
+        message,
+    }
+    .into())
+}
+
 fn http_client() -> &'static reqwest::Client {
     static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
     CLIENT.get_or_init(reqwest::Client::new)
 }
 
-pub async fn get_record(url: &str, repo: &str, nsid: &str, rkey: &str) -> Result<Value> {
-    http_client()
+pub async fn get_record(
+    url: &str,
+    timeout: Duration,
+    repo: &str,
+    nsid: &str,
+    rkey: &str,
+) -> Result<Value> {
+    let response = http_client()
         .get(format!("{url}/xrpc/com.atproto.repo.getRecord"))
         .query(&[("repo", repo), ("collection", nsid), ("rkey", rkey)])
         .header("Content-Type", "application/json; charset=utf-8")
-        .timeout(Duration::from_secs(5))
+        .timeout(timeout)
         .send()
         .await
-        .map_err(|e| eyre!("call pds failed: {e}"))?
-        .json::<Value>()
-        .await
-        .map_err(|e| eyre!("decode pds response failed: {e}"))
+        .map_err(|e| eyre!("call pds failed: {e}"))?;
+    read_json(response).await
+}
+
+/// 列出仓库某个集合下全部记录的 uri
+pub async fn list_record_uris(
+    url: &str,
+    timeout: Duration,
+    repo: &str,
+    nsid: &str,
+) -> Result<Vec<String>> {
+    Ok(list_records(url, timeout, repo, nsid)
+        .await?
+        .iter()
+        .filter_map(|r| r["uri"].as_str().map(str::to_string))
+        .collect())
+}
+
+/// 通过 com.atproto.repo.listRecords 分页列出 repo 下该集合的全部记录（`{uri, cid, value}`）
+pub async fn list_records(
+    url: &str,
+    timeout: Duration,
+    repo: &str,
+    nsid: &str,
+) -> Result<Vec<Value>> {
+    let mut records = vec![];
+    let mut cursor: Option<String> = None;
+    loop {
+        let mut query = vec![("repo", repo), ("collection", nsid), ("limit", "100")];
+        if let Some(cursor) = &cursor {
+            query.push(("cursor", cursor.as_str()));
+        }
+        let response = http_client()
+            .get(format!("{url}/xrpc/com.atproto.repo.listRecords"))
+            .query(&query)
+            .header("Content-Type", "application/json; charset=utf-8")
+            .timeout(timeout)
+            .send()
+            .await
+            .map_err(|e| eyre!("call pds failed: {e}"))?;
+        let mut page = read_json(response).await?;
+        let Some(Value::Array(page_records)) = page.get_mut("records").map(Value::take) else {
+            return Err(eyre!("invalid listRecords response"));
+        };
+        let done = page_records.is_empty();
+        records.extend(page_records);
+        match page["cursor"].as_str() {
+            Some(next) if !done => cursor = Some(next.to_string()),
+            _ => break,
+        }
+    }
+    Ok(records)
 }
@@ -0,0 +1,178 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, RwLock},
+    time::{Duration, Instant},
+};
+
+/// 带过期时间的内存缓存，过期后由调用方重新解析
+#[derive(Clone)]
+pub struct TtlCache<V> {
+    ttl: Duration,
+    entries: Arc<RwLock<HashMap<String, (Instant, V)>>>,
+}
+
+impl<V: Clone> TtlCache<V> {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<V> {
+        let entries = self.entries.read().ok()?;
+        entries
+            .get(key)
+            .filter(|(cached_at, _)| cached_at.elapsed() < self.ttl)
+            .map(|(_, value)| value.clone())
+    }
+
+    pub fn insert(&self, key: &str, value: V) {
+        self.insert_many([(key.to_string(), value)]);
+    }
+
+    pub fn insert_many(&self, items: impl IntoIterator<Item = (String, V)>) {
+        if self.ttl.is_zero() {
+            return;
+        }
+        if let Ok(mut entries) = self.entries.write() {
+            entries.retain(|_, (cached_at, _)| cached_at.elapsed() < self.ttl);
+            let now = Instant::now();
+            for (key, value) in items {
+                entries.insert(key, (now, value));
+            }
+        }
+    }
+
+    pub fn invalidate(&self, key: &str) {
+        if let Ok(mut entries) = self.entries.write() {
+            entries.remove(key);
+        }
+    }
+
+    pub fn clear(&self) {
+        if let Ok(mut entries) = self.entries.write() {
+            entries.clear();
+        }
+    }
+}
+
+/// 简单熔断：调用失败后在冷却期内直接跳过，避免下游故障时拖慢整体响应
+#[derive(Clone)]
+pub struct Cooldown {
+    period: Duration,
+    tripped_at: Arc<RwLock<Option<Instant>>>,
+}
+
+impl Cooldown {
+    pub fn new(period: Duration) -> Self {
+        Self {
+            period,
+            tripped_at: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.tripped_at
+            .read()
+            .ok()
+            .and_then(|t| *t)
+            .is_some_and(|t| t.elapsed() < self.period)
+    }
+
+    pub fn trip(&self) {
+        if self.period.is_zero() {
+            return;
+        }
+        if let Ok(mut tripped_at) = self.tripped_at.write() {
+            *tripped_at = Some(Instant::now());
+        }
+    }
+}
+
+/// 按 key（如客户端 IP）限流的令牌桶，每分钟补满 `per_minute` 个令牌；为 0 时不限流
+#[derive(Clone)]
+pub struct RateLimiter {
+    per_minute: u32,
+    buckets: Arc<Mutex<Buckets>>,
+}
+
+struct Buckets {
+    entries: HashMap<String, (f64, Instant)>,
+    last_sweep: Instant,
+}
+
+/// 桶数量超过该值时清理已补满的桶
+const RATE_LIMITER_MAX_KEYS: usize = 10_000;
+/// 两次清理的最短间隔；空闲满一分钟的桶必然已补满，更频繁的清理收益有限
+const RATE_LIMITER_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+impl RateLimiter {
+    pub fn new(per_minute: u32) -> Self {
+        Self {
+            per_minute,
+            buckets: Arc::new(Mutex::new(Buckets {
+                entries: HashMap::new(),
+                last_sweep: Instant::now(),
+            })),
+        }
+    }
+
+    /// 取一个令牌；令牌不足时返回需要等待的秒数
+    pub fn check(&self, key: &str) -> Result<(), u64> {
+        if self.per_minute == 0 {
+            return Ok(());
+        }
+        let capacity = self.per_minute as f64;
+        let per_sec = capacity / 60.0;
+        let Ok(mut buckets) = self.buckets.lock() else {
+            return Ok(());
+        };
+        let now = Instant::now();
+        // 全表扫描每个间隔最多一次，避免大量活跃 key 时每个请求都持锁扫描
+        if buckets.entries.len() > RATE_LIMITER_MAX_KEYS
+            && now.duration_since(buckets.last_sweep) >= RATE_LIMITER_SWEEP_INTERVAL
+        {
+            buckets.entries.retain(|_, (tokens, last)| {
+                *tokens + now.duration_since(*last).as_secs_f64() * per_sec < capacity
+            });
+            buckets.last_sweep = now;
+        }
+        let (tokens, last) = buckets
+            .entries
+            .entry(key.to_string())
+            .or_insert((capacity, now));
+        *tokens = (*tokens + now.duration_since(*last).as_secs_f64() * per_sec).min(capacity);
+        *last = now;
+        if *tokens >= 1.0 {
+            *tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(((1.0 - *tokens) / per_sec).ceil() as u64)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rate_limiter_allows_burst_then_rejects() {
+        let limiter = RateLimiter::new(2);
+        assert!(limiter.check("192.0.2.1").is_ok());
+        assert!(limiter.check("192.0.2.1").is_ok());
+        let wait = limiter.check("192.0.2.1").unwrap_err();
+        assert!((1..=30).contains(&wait));
+        // 不同 key 各自计数
+        assert!(limiter.check("192.0.2.2").is_ok());
+    }
+
+    #[test]
+    fn rate_limiter_disabled_when_zero() {
+        let limiter = RateLimiter::new(0);
+        for _ in 0..100 {
+            assert!(limiter.check("192.0.2.1").is_ok());
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt, str::FromStr, time::Duration};
 
 use ckb_sdk::{Address, AddressPayload, CkbRpcAsyncClient, NetworkType};
 use ckb_types::{
@@ -10,8 +10,62 @@ use color_eyre::{
     Result,
     eyre::{OptionExt, eyre},
 };
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
 use serde_json::json;
 
+/// 调度任务并发查询交易的上限
+const TX_QUERY_CONCURRENCY: usize = 8;
+
+/// 判断确认数所需的链上高度；未要求确认数时返回 `None`，不必查询
+pub async fn confirmation_tip(
+    ckb_client: &CkbRpcAsyncClient,
+    confirmations: u64,
+) -> Result<Option<u64>> {
+    if confirmations == 0 {
+        return Ok(None);
+    }
+    Ok(Some(ckb_client.get_tip_block_number().await?.into()))
+}
+
+/// 已上链交易是否达到确认数，tip 为 `None` 时视为已确认
+pub const fn is_confirmed(block_number: u64, tip: Option<u64>, confirmations: u64) -> bool {
+    let Some(tip) = tip else {
+        return true;
+    };
+    tip.saturating_sub(block_number) >= confirmations
+}
+
+/// 交易查询结果；`NotFound` 表示节点还没见到该交易（如刚广播），应继续轮询而非报错
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxStatusResult {
+    NotFound,
+    Pending,
+    Proposed,
+    /// 所在区块高度
+    Committed(u64),
+    Rejected,
+    Unknown,
+}
+
+impl TxStatusResult {
+    pub fn from_tx_status(tx_status: Option<&ckb_jsonrpc_types::TxStatus>) -> Self {
+        let Some(tx_status) = tx_status else {
+            return Self::NotFound;
+        };
+        match tx_status.status {
+            ckb_jsonrpc_types::Status::Pending => Self::Pending,
+            ckb_jsonrpc_types::Status::Proposed => Self::Proposed,
+            ckb_jsonrpc_types::Status::Committed => match tx_status.block_number {
+                Some(block_number) => Self::Committed(block_number.into()),
+                None => Self::Unknown,
+            },
+            ckb_jsonrpc_types::Status::Rejected => Self::Rejected,
+            ckb_jsonrpc_types::Status::Unknown => Self::Unknown,
+        }
+    }
+}
+
 // CKB contract code hashes
 const OMNI_LOCK_MAINNET_CODE_HASH: &str =
     "9b819793a64463aed77c615d6cb226eea5487ccfc0783043a587254cda2b6f26";
@@ -78,6 +132,7 @@ pub async fn get_vote_result(
     ckb_net: NetworkType,
     indexer_bind_url: &str,
     indexer_dao_url: &str,
+    indexer_timeout: Duration,
     vote_meta_tx_hash: &str,
 ) -> Result<HashMap<String, (usize, u64)>> {
     use ckb_types::prelude::Entity;
@@ -138,6 +193,7 @@ pub async fn get_vote_result(
                     ckb_net,
                     indexer_bind_url,
                     indexer_dao_url,
+                    indexer_timeout,
                     &address,
                     None,
                 )
@@ -151,14 +207,33 @@ pub async fn get_vote_result(
     Ok(result)
 }
 
+/// 按区块倒序只取第一页，页内即为最新的 cell
+const DID_CELL_PAGE_SIZE: u32 = 10;
+
+/// did 没有对应的 did cell（区别于 RPC 调用失败）
+#[derive(Debug)]
+pub struct DidCellNotFound(pub String);
+
+impl std::fmt::Display for DidCellNotFound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "did cell not found: {}", self.0)
+    }
+}
+
+impl std::error::Error for DidCellNotFound {}
+
+pub fn strip_did_prefix(did: &str) -> &str {
+    let did = did.trim_start_matches("did:web5:");
+    let did = did.trim_start_matches("did:ckb:");
+    did.trim_start_matches("did:plc:")
+}
+
 pub async fn get_ckb_addr_by_did(
     ckb_client: &CkbRpcAsyncClient,
     ckb_net: &NetworkType,
     did: &str,
 ) -> Result<String> {
-    let did = did.trim_start_matches("did:web5:");
-    let did = did.trim_start_matches("did:ckb:");
-    let did = did.trim_start_matches("did:plc:");
+    let did = strip_did_prefix(did);
     let code_hash = match ckb_net {
         NetworkType::Mainnet => DID_MAINNET_CODE_HASH,
         NetworkType::Testnet | NetworkType::Dev | NetworkType::Staging | NetworkType::Preview => {
@@ -182,26 +257,224 @@ pub async fn get_ckb_addr_by_did(
                 with_data: None,
                 group_by_transaction: None,
             },
-            ckb_sdk::rpc::ckb_indexer::Order::Asc,
-            1.into(),
+            ckb_sdk::rpc::ckb_indexer::Order::Desc,
+            DID_CELL_PAGE_SIZE.into(),
             None,
         )
         .await?;
-    let output: &ckb_jsonrpc_types::CellOutput = &r.objects.first().ok_or_eyre("Not Found")?.output;
+    if r.objects.len() > 1 {
+        warn!(
+            "did {did} matches {} cells, using the latest",
+            r.objects.len()
+        );
+    }
+    // 多个 cell 时取区块最高的（倒序查询，第一页必含最新的），同一区块内按 out_point 排序，保证结果稳定
+    let cell = r
+        .objects
+        .iter()
+        .max_by(|a, b| {
+            let block_a: u64 = a.block_number.into();
+            let block_b: u64 = b.block_number.into();
+            block_a.cmp(&block_b).then_with(|| {
+                let index_a: u32 = a.out_point.index.into();
+                let index_b: u32 = b.out_point.index.into();
+                (&b.out_point.tx_hash, index_b).cmp(&(&a.out_point.tx_hash, index_a))
+            })
+        })
+        .ok_or_else(|| DidCellNotFound(did.to_string()))?;
+    let output: &ckb_jsonrpc_types::CellOutput = &cell.output;
     let script: ckb_types::packed::Script = output.lock.clone().into();
     let ckb_addr = ckb_sdk::Address::new(*ckb_net, script.into(), true);
     Ok(ckb_addr.to_string())
 }
 
+/// 32 字节的十六进制交易哈希，可带 `0x` 前缀
+///
+/// 序列化时保留原始字符串，签名校验依赖参数原样回写。
+/// 不实现 `Default`，请求参数缺少 tx_hash 时反序列化失败而不是得到空哈希
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct TxHash {
+    raw: String,
+    hash: [u8; 32],
+}
+
+impl TxHash {
+    pub const fn h256(&self) -> ckb_types::H256 {
+        ckb_types::H256(self.hash)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.raw
+    }
+}
+
+impl FromStr for TxHash {
+    type Err = color_eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let hex_str = s.strip_prefix("0x").unwrap_or(s);
+        if hex_str.len() != 64 {
+            return Err(eyre!(
+                "invalid tx_hash: expected 64 hex chars, got {}",
+                hex_str.len()
+            ));
+        }
+        let hash: [u8; 32] = hex::decode(hex_str)
+            .map_err(|e| eyre!("invalid tx_hash: {e}"))?
+            .try_into()
+            .map_err(|_| eyre!("invalid tx_hash length"))?;
+        Ok(Self {
+            raw: s.to_string(),
+            hash,
+        })
+    }
+}
+
+impl TryFrom<String> for TxHash {
+    type Error = color_eyre::Report;
+
+    fn try_from(value: String) -> Result<Self> {
+        value.parse()
+    }
+}
+
+impl From<TxHash> for String {
+    fn from(value: TxHash) -> Self {
+        value.raw
+    }
+}
+
+impl fmt::Display for TxHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.raw)
+    }
+}
+
+/// 只有 RPC 调用失败才返回错误
 pub async fn get_tx_status(
     ckb_client: &CkbRpcAsyncClient,
     tx_hash: &str,
-) -> Result<ckb_jsonrpc_types::Status> {
-    let tx_hash: [u8; 32] = hex::decode(tx_hash.strip_prefix("0x").unwrap_or(tx_hash))?
-        .try_into()
-        .map_err(|_| eyre!("invalid tx_hash format"))?;
-    let tx_status = ckb_client.get_transaction(ckb_types::H256(tx_hash)).await?;
-    tx_status
-        .ok_or_eyre("get tx error")
-        .map(|t| t.tx_status.status)
+) -> Result<TxStatusResult> {
+    let tx_hash: TxHash = tx_hash.parse()?;
+    let tx = ckb_client.get_transaction(tx_hash.h256()).await?;
+    Ok(TxStatusResult::from_tx_status(
+        tx.as_ref().map(|t| &t.tx_status),
+    ))
+}
+
+/// 并发查询交易，返回顺序与输入无关
+pub async fn get_transactions<K>(
+    ckb_client: &CkbRpcAsyncClient,
+    txs: impl IntoIterator<Item = (K, TxHash)>,
+) -> Vec<(
+    K,
+    Result<Option<ckb_jsonrpc_types::TransactionWithStatusResponse>>,
+)> {
+    futures::stream::iter(txs)
+        .map(|(key, tx_hash)| async move {
+            let tx = ckb_client
+                .get_transaction(tx_hash.h256())
+                .await
+                .map_err(|e| eyre!("get transaction {tx_hash} failed: {e}"));
+            (key, tx)
+        })
+        .buffer_unordered(TX_QUERY_CONCURRENCY)
+        .collect()
+        .await
+}
+
+/// 并发查询交易状态，返回顺序与输入无关；只有 RPC 调用失败才返回错误
+pub async fn get_tx_statuses<K>(
+    ckb_client: &CkbRpcAsyncClient,
+    txs: impl IntoIterator<Item = (K, TxHash)>,
+) -> Vec<(K, Result<TxStatusResult>)> {
+    get_transactions(ckb_client, txs)
+        .await
+        .into_iter()
+        .map(|(key, tx)| {
+            let status =
+                tx.map(|t| TxStatusResult::from_tx_status(t.as_ref().map(|t| &t.tx_status)));
+            (key, status)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HASH: &str = "8f8c79eb6671709633fe6a46de93c0fedc9c1b8a6527a18d3983879542635c9f";
+
+    #[test]
+    fn tx_hash_accepts_32_bytes_with_or_without_prefix() {
+        let plain: TxHash = HASH.parse().unwrap();
+        let prefixed: TxHash = format!("0x{HASH}").parse().unwrap();
+        assert_eq!(plain.h256(), prefixed.h256());
+        assert_eq!(prefixed.as_str(), format!("0x{HASH}"));
+    }
+
+    #[test]
+    fn tx_hash_rejects_short_long_and_non_hex() {
+        assert!(HASH[..62].parse::<TxHash>().is_err());
+        assert!(format!("{HASH}00").parse::<TxHash>().is_err());
+        assert!(format!("0x{}zz", &HASH[..62]).parse::<TxHash>().is_err());
+        assert!("".parse::<TxHash>().is_err());
+    }
+
+    #[test]
+    fn confirmed_after_enough_blocks() {
+        assert!(!is_confirmed(100, Some(100), 3));
+        assert!(!is_confirmed(100, Some(102), 3));
+        assert!(is_confirmed(100, Some(103), 3));
+        assert!(is_confirmed(100, Some(200), 3));
+        // tip 落后于交易所在区块（如节点切换）时不算确认
+        assert!(!is_confirmed(100, Some(90), 3));
+    }
+
+    #[test]
+    fn confirmed_without_tip_or_requirement() {
+        assert!(is_confirmed(100, None, 3));
+        assert!(is_confirmed(100, Some(100), 0));
+    }
+
+    #[test]
+    fn tx_status_result_maps_status() {
+        use ckb_jsonrpc_types::{Status, TxStatus};
+
+        assert_eq!(
+            TxStatusResult::from_tx_status(None),
+            TxStatusResult::NotFound
+        );
+        let status = |status, block_number: Option<u64>| TxStatus {
+            status,
+            block_number: block_number.map(Into::into),
+            ..TxStatus::pending()
+        };
+        let cases = [
+            (status(Status::Pending, None), TxStatusResult::Pending),
+            (status(Status::Proposed, None), TxStatusResult::Proposed),
+            (
+                status(Status::Committed, Some(100)),
+                TxStatusResult::Committed(100),
+            ),
+            (status(Status::Committed, None), TxStatusResult::Unknown),
+            (status(Status::Rejected, None), TxStatusResult::Rejected),
+            (status(Status::Unknown, None), TxStatusResult::Unknown),
+        ];
+        for (tx_status, expected) in cases {
+            assert_eq!(TxStatusResult::from_tx_status(Some(&tx_status)), expected);
+        }
+    }
+
+    #[test]
+    fn tx_hash_deserializes_from_string_and_keeps_raw() {
+        let raw = format!("0x{HASH}");
+        let tx_hash: TxHash = serde_json::from_value(serde_json::json!(raw)).unwrap();
+        assert_eq!(
+            serde_json::to_value(&tx_hash).unwrap(),
+            serde_json::json!(raw)
+        );
+        assert!(serde_json::from_value::<TxHash>(serde_json::json!("0x1234")).is_err());
+    }
 }
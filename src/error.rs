@@ -1,22 +1,41 @@
 use color_eyre::eyre::Error;
 use common_x::restful::axum::{
     Json,
-    http::StatusCode,
+    http::{HeaderValue, StatusCode, header::RETRY_AFTER},
     response::{IntoResponse, Response},
 };
 use serde_json::json;
 
+use crate::AddressParseError;
+
 #[derive(Debug)]
 pub enum AppError {
     ValidateFailed(String),
     NotFound,
     ExecSqlFailed(String),
     CallPdsFailed(String),
+    InvalidAddress(AddressParseError),
+    /// 超出限流，参数为建议的重试等待秒数
+    TooManyRequests(u64),
     Unknown(String),
 }
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
+        // 在请求 span 内记录，日志与响应头中的 request id 对应
+        if !matches!(
+            self,
+            AppError::ValidateFailed(_)
+                | AppError::NotFound
+                | AppError::InvalidAddress(_)
+                | AppError::TooManyRequests(_)
+        ) {
+            error!("{self:?}");
+        }
+        let retry_after = match &self {
+            AppError::TooManyRequests(secs) => Some(*secs),
+            _ => None,
+        };
         let (status, error, error_message) = match self {
             AppError::ValidateFailed(msg) => (StatusCode::BAD_REQUEST, "ValidateFailed", msg),
             AppError::NotFound => (StatusCode::NOT_FOUND, "NotFound", "NOT_FOUND".to_owned()),
@@ -30,6 +49,12 @@ impl IntoResponse for AppError {
                 "CallPdsFailed",
                 json!({"pds": msg}).to_string(),
             ),
+            AppError::InvalidAddress(e) => (StatusCode::BAD_REQUEST, e.code(), e.to_string()),
+            AppError::TooManyRequests(secs) => (
+                StatusCode::TOO_MANY_REQUESTS,
+                "TooManyRequests",
+                format!("retry after {secs}s"),
+            ),
             AppError::Unknown(msg) => (StatusCode::INTERNAL_SERVER_ERROR, "ServerError", msg),
         };
         let body = Json(json!({
@@ -37,7 +62,32 @@ impl IntoResponse for AppError {
             "error": error,
             "message": error_message,
         }));
-        (status, body).into_response()
+        let mut response = (status, body).into_response();
+        if let Some(secs) = retry_after {
+            response
+                .headers_mut()
+                .insert(RETRY_AFTER, HeaderValue::from(secs));
+        }
+        response
+    }
+}
+
+impl AppError {
+    /// 地址解析错误保留具体原因，其余视为参数校验失败
+    pub fn from_validation(err: Error) -> Self {
+        match err.downcast::<AddressParseError>() {
+            Ok(e) => Self::InvalidAddress(e),
+            Err(e) => Self::ValidateFailed(e.to_string()),
+        }
+    }
+
+    /// did 没有 did cell 时返回 NotFound，其余视为调用失败
+    pub fn from_did_lookup(err: Error) -> Self {
+        if err.downcast_ref::<crate::ckb::DidCellNotFound>().is_some() {
+            Self::NotFound
+        } else {
+            Self::Unknown(err.to_string())
+        }
     }
 }
 
@@ -46,6 +96,9 @@ where
     E: Into<Error>,
 {
     fn from(err: E) -> Self {
-        Self::Unknown(err.into().to_string())
+        match err.into().downcast::<AddressParseError>() {
+            Ok(e) => Self::InvalidAddress(e),
+            Err(e) => Self::Unknown(e.to_string()),
+        }
     }
 }
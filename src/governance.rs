@@ -0,0 +1,153 @@
+use std::{collections::HashMap, path::Path};
+
+use color_eyre::{Result, eyre::eyre};
+use serde::{Deserialize, Serialize};
+
+use crate::lexicon::proposal::ProposalState;
+
+/// 未单独配置的投票阶段使用的投票时长（天）
+pub const DEFAULT_VOTE_DURATION_DAYS: u64 = 7;
+/// 投票时长上限（天）
+pub const MAX_VOTE_DURATION_DAYS: u64 = 30;
+
+/// 计票门槛与通过比例，权重单位为 shannon
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct GovernanceParams {
+    /// 弃权票是否计入投票门槛（总票数）
+    pub abstain_counts_for_quorum: bool,
+    /// 预算提案立项 / 复审 / 整改投票的最低总票数
+    pub budget_initiation_quorum: u64,
+    /// 预算提案立项 / 复审 / 整改投票的赞成比例
+    pub budget_initiation_approval: f64,
+    /// 预算提案里程碑 / 延期投票的最低总票数
+    pub budget_milestone_quorum: u64,
+    /// 预算提案里程碑 / 延期投票的否决比例（超过即否决）
+    pub budget_milestone_rejection: f64,
+    /// 其他提案立项 / 复审 / 整改投票的最低总票数 = 预算（CKB）× 该倍数
+    pub initiation_quorum_multiplier: u64,
+    /// 其他提案立项 / 复审 / 整改投票的赞成比例
+    pub initiation_approval: f64,
+    /// 其他提案里程碑 / 延期投票的最低总票数 = 预算（CKB）× 该倍数
+    pub milestone_quorum_multiplier: u64,
+    /// 其他提案里程碑 / 延期投票的否决比例（超过即否决）
+    pub milestone_rejection: f64,
+    /// 各投票阶段的投票时长（天），键为提案状态名，如 `MilestoneVote`；未列出的阶段保留默认值
+    #[serde(deserialize_with = "merge_vote_durations")]
+    pub vote_durations: HashMap<ProposalState, u64>,
+}
+
+impl Default for GovernanceParams {
+    fn default() -> Self {
+        Self {
+            abstain_counts_for_quorum: true,
+            budget_initiation_quorum: 1_8500_0000_0000_0000,
+            budget_initiation_approval: 0.67,
+            budget_milestone_quorum: 6200_0000_0000_0000,
+            budget_milestone_rejection: 0.67,
+            initiation_quorum_multiplier: 3_0000_0000,
+            initiation_approval: 0.51,
+            milestone_quorum_multiplier: 1_0000_0000,
+            milestone_rejection: 0.51,
+            vote_durations: HashMap::from([
+                (ProposalState::MilestoneVote, 3),
+                (ProposalState::DelayVote, 3),
+            ]),
+        }
+    }
+}
+
+fn merge_vote_durations<'de, D>(
+    deserializer: D,
+) -> std::result::Result<HashMap<ProposalState, u64>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let mut vote_durations = GovernanceParams::default().vote_durations;
+    vote_durations.extend(HashMap::<ProposalState, u64>::deserialize(deserializer)?);
+    Ok(vote_durations)
+}
+
+impl GovernanceParams {
+    /// 从 JSON 文件读取，缺省字段使用默认值
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| eyre!("read governance config {}: {e}", path.display()))?;
+        let params: Self = serde_json::from_str(&content)
+            .map_err(|e| eyre!("parse governance config {}: {e}", path.display()))?;
+        params.validate()?;
+        Ok(params)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        for (state, days) in &self.vote_durations {
+            if *days == 0 || *days > MAX_VOTE_DURATION_DAYS {
+                return Err(eyre!(
+                    "vote duration of {state:?} must be within 1..={MAX_VOTE_DURATION_DAYS} days, got {days}"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// 该提案状态下发起的投票持续的天数
+    pub fn vote_duration_days(&self, proposal_state: i32) -> u64 {
+        self.vote_durations
+            .get(&ProposalState::from(proposal_state))
+            .copied()
+            .unwrap_or(DEFAULT_VOTE_DURATION_DAYS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_params_are_valid() {
+        assert!(GovernanceParams::default().validate().is_ok());
+    }
+
+    #[test]
+    fn vote_duration_out_of_range_is_rejected() {
+        for days in [0, MAX_VOTE_DURATION_DAYS + 1] {
+            let params = GovernanceParams {
+                vote_durations: HashMap::from([(ProposalState::InitiationVote, days)]),
+                ..Default::default()
+            };
+            assert!(params.validate().is_err());
+        }
+        let params = GovernanceParams {
+            vote_durations: HashMap::from([(
+                ProposalState::InitiationVote,
+                MAX_VOTE_DURATION_DAYS,
+            )]),
+            ..Default::default()
+        };
+        assert!(params.validate().is_ok());
+    }
+
+    #[test]
+    fn partial_vote_durations_keep_defaults() {
+        let params: GovernanceParams = serde_json::from_value(
+            serde_json::json!({ "voteDurations": { "InitiationVote": 10 } }),
+        )
+        .unwrap();
+        assert_eq!(
+            params.vote_duration_days(ProposalState::InitiationVote as i32),
+            10
+        );
+        assert_eq!(
+            params.vote_duration_days(ProposalState::MilestoneVote as i32),
+            3
+        );
+        assert_eq!(
+            params.vote_duration_days(ProposalState::DelayVote as i32),
+            3
+        );
+        assert_eq!(
+            params.vote_duration_days(ProposalState::ReexamineVote as i32),
+            DEFAULT_VOTE_DURATION_DAYS
+        );
+    }
+}
@@ -15,11 +15,11 @@ fn http_client() -> &'static reqwest::Client {
     CLIENT.get_or_init(reqwest::Client::new)
 }
 
-pub async fn query_by_to(url: &str, to: &str) -> Result<Value> {
+pub async fn query_by_to(url: &str, timeout: Duration, to: &str) -> Result<Value> {
     http_client()
         .get(format!("{url}/by_to/{to}"))
         .header("Content-Type", "application/json; charset=utf-8")
-        .timeout(Duration::from_secs(5))
+        .timeout(timeout)
         .send()
         .await
         .map_err(|e| eyre!("call indexer failed: {e}"))?
@@ -32,11 +32,16 @@ pub async fn query_by_to(url: &str, to: &str) -> Result<Value> {
         })?
 }
 
-pub async fn query_by_to_at_height(url: &str, to: &str, height: u64) -> Result<Value> {
+pub async fn query_by_to_at_height(
+    url: &str,
+    timeout: Duration,
+    to: &str,
+    height: u64,
+) -> Result<Value> {
     http_client()
         .get(format!("{url}/by_to_at_height/{to}/{height}"))
         .header("Content-Type", "application/json; charset=utf-8")
-        .timeout(Duration::from_secs(5))
+        .timeout(timeout)
         .send()
         .await
         .map_err(|e| eyre!("call indexer failed: {e}"))?
@@ -49,11 +54,11 @@ pub async fn query_by_to_at_height(url: &str, to: &str, height: u64) -> Result<V
         })?
 }
 
-pub async fn query_by_from(url: &str, from: &str) -> Result<Value> {
+pub async fn query_by_from(url: &str, timeout: Duration, from: &str) -> Result<Value> {
     http_client()
         .get(format!("{url}/by_from/{from}"))
         .header("Content-Type", "application/json; charset=utf-8")
-        .timeout(Duration::from_secs(5))
+        .timeout(timeout)
         .send()
         .await
         .map_err(|e| eyre!("call indexer failed: {e}"))?
@@ -70,13 +75,14 @@ pub async fn get_weight(
     ckb_net: ckb_sdk::NetworkType,
     indexer_bind_url: &str,
     indexer_dao_url: &str,
+    timeout: Duration,
     ckb_addr: &str,
     until_block_number: Option<u64>,
 ) -> Result<HashMap<String, u64>> {
     let from_list = if let Some(until_block_number) = until_block_number {
-        query_by_to_at_height(indexer_bind_url, ckb_addr, until_block_number).await?
+        query_by_to_at_height(indexer_bind_url, timeout, ckb_addr, until_block_number).await?
     } else {
-        query_by_to(indexer_bind_url, ckb_addr).await?
+        query_by_to(indexer_bind_url, timeout, ckb_addr).await?
     };
     let mut ckb_addrs: HashSet<String> = from_list
         .as_array()
@@ -100,6 +106,7 @@ pub async fn get_weight(
         for ckb_addr_batch in ckb_addr_vec.chunks(20) {
             let batch_weight_map = crate::indexer_dao::query_dao_stake_until_height(
                 indexer_dao_url,
+                timeout,
                 until_block_number,
                 ckb_addr_batch,
             )
@@ -111,6 +118,7 @@ pub async fn get_weight(
     } else {
         crate::indexer_dao::query_dao_stake_until_height(
             indexer_dao_url,
+            timeout,
             until_block_number,
             &ckb_addrs.into_iter().collect::<Vec<_>>(),
         )
@@ -10,6 +10,7 @@ fn http_client() -> &'static reqwest::Client {
 
 pub async fn query_dao_stake_until_height(
     url: &str,
+    timeout: Duration,
     until_height: Option<u64>,
     ckb_addrs: &[String],
 ) -> Result<HashMap<String, u64>> {
@@ -23,7 +24,7 @@ pub async fn query_dao_stake_until_height(
             .to_string(),
         )
         .header("Content-Type", "application/json; charset=utf-8")
-        .timeout(Duration::from_secs(5))
+        .timeout(timeout)
         .send()
         .await
         .map_err(|e| eyre!("call indexer failed: {e}"))?
@@ -8,11 +8,15 @@ fn http_client() -> &'static reqwest::Client {
     CLIENT.get_or_init(reqwest::Client::new)
 }
 
-pub async fn did_set(url: &str, until_height: u64) -> Result<HashMap<String, String>> {
+pub async fn did_set(
+    url: &str,
+    timeout: Duration,
+    until_height: u64,
+) -> Result<HashMap<String, String>> {
     http_client()
         .get(format!("{url}/did-set?until_height={until_height}"))
         .header("Content-Type", "application/json; charset=utf-8")
-        .timeout(Duration::from_secs(5))
+        .timeout(timeout)
         .send()
         .await
         .map_err(|e| eyre!("call indexer failed: {e}"))?
@@ -21,11 +25,11 @@ pub async fn did_set(url: &str, until_height: u64) -> Result<HashMap<String, Str
         .map_err(|e| eyre!("decode indexer response failed: {e}"))
 }
 
-pub async fn did_document(url: &str, did: &str) -> Result<Value> {
+pub async fn did_document(url: &str, timeout: Duration, did: &str) -> Result<Value> {
     http_client()
         .get(format!("{url}/{did}"))
         .header("Content-Type", "application/json; charset=utf-8")
-        .timeout(Duration::from_secs(5))
+        .timeout(timeout)
         .send()
         .await
         .map_err(|e| eyre!("call indexer failed: {e}"))?
@@ -34,11 +38,11 @@ pub async fn did_document(url: &str, did: &str) -> Result<Value> {
         .map_err(|e| eyre!("decode indexer response failed: {e}"))
 }
 
-pub async fn ckb_did(url: &str, ckb_addr: &str) -> Result<Vec<String>> {
+pub async fn ckb_did(url: &str, timeout: Duration, ckb_addr: &str) -> Result<Vec<String>> {
     http_client()
         .get(format!("{url}/resolve-ckb-addr/{ckb_addr}"))
         .header("Content-Type", "application/json; charset=utf-8")
-        .timeout(Duration::from_secs(5))
+        .timeout(timeout)
         .send()
         .await
         .map_err(|e| eyre!("call indexer failed: {e}"))?
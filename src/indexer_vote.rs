@@ -10,6 +10,7 @@ fn http_client() -> &'static reqwest::Client {
 
 pub async fn all_votes(
     url: &str,
+    timeout: Duration,
     args: &str,
     epoch_number: i64,
     epoch_index: i64,
@@ -24,7 +25,7 @@ pub async fn all_votes(
             ("epoch_length", &epoch_length.to_string()),
         ])
         .header("Content-Type", "application/json; charset=utf-8")
-        .timeout(Duration::from_secs(5))
+        .timeout(timeout)
         .send()
         .await
         .map_err(|e| eyre!("call indexer failed: {e}"))?;
@@ -38,6 +39,7 @@ pub async fn all_votes(
 
 pub async fn address_vote(
     url: &str,
+    timeout: Duration,
     args: &str,
     ckb_addr: &str,
     epoch_number: i64,
@@ -54,7 +56,7 @@ pub async fn address_vote(
             ("epoch_length", &epoch_length.to_string()),
         ])
         .header("Content-Type", "application/json; charset=utf-8")
-        .timeout(Duration::from_secs(5))
+        .timeout(timeout)
         .send()
         .await
         .map_err(|e| eyre!("call indexer failed: {e}"))?
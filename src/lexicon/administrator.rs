@@ -1,8 +1,9 @@
 use color_eyre::Result;
-use sea_query::{ColumnDef, Iden, OnConflict, PostgresQueryBuilder};
+use sea_query::{ColumnDef, Expr, ExprTrait, Iden, OnConflict, PostgresQueryBuilder};
 use sea_query_sqlx::SqlxBinder;
 use serde::Serialize;
 use sqlx::{Executor, Pool, Postgres, query, query_with};
+use utoipa::ToSchema;
 
 #[derive(Iden, Debug, Clone, Copy)]
 pub enum Administrator {
@@ -11,6 +12,21 @@ pub enum Administrator {
     Permission,
 }
 
+/// `permission` 字段按位解释
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ToSchema)]
+pub enum AdminPermission {
+    /// 1 处理提案流程（会议、报告、拨款等）
+    Process = 1,
+    /// 2 管理管理员
+    Manage = 1 << 1,
+}
+
+impl AdminPermission {
+    pub const fn granted(self, permission: i32) -> bool {
+        permission & (self as i32) != 0
+    }
+}
+
 impl Administrator {
     pub async fn init(db: &Pool<Postgres>) -> Result<()> {
         let sql = sea_query::Table::create()
@@ -40,6 +56,16 @@ impl Administrator {
         Ok(())
     }
 
+    pub async fn remove(db: &Pool<Postgres>, did: &str) -> Result<u64> {
+        let (sql, values) = sea_query::Query::delete()
+            .from_table(Self::Table)
+            .and_where(Expr::col(Self::Did).eq(did))
+            .build_sqlx(PostgresQueryBuilder);
+
+        let lines = db.execute(query_with(&sql, values)).await?.rows_affected();
+        Ok(lines)
+    }
+
     pub fn build_select() -> sea_query::SelectStatement {
         sea_query::Query::select()
             .columns([
@@ -50,16 +76,24 @@ impl Administrator {
             .take()
     }
 
-    pub async fn fetch_all(db: &Pool<Postgres>) -> Vec<AdministratorRow> {
+    pub async fn get(db: &Pool<Postgres>, did: &str) -> Result<Option<AdministratorRow>> {
+        let (sql, values) = Self::build_select()
+            .and_where(Expr::col(Self::Did).eq(did))
+            .build_sqlx(PostgresQueryBuilder);
+        let row: Option<AdministratorRow> =
+            sqlx::query_as_with(&sql, values).fetch_optional(db).await?;
+        Ok(row)
+    }
+
+    pub async fn fetch_all(db: &Pool<Postgres>) -> Result<Vec<AdministratorRow>> {
         let (sql, values) = Self::build_select().build_sqlx(PostgresQueryBuilder);
-        sqlx::query_as_with(&sql, values)
-            .fetch_all(db)
-            .await
-            .unwrap_or_default()
+        let rows = sqlx::query_as_with(&sql, values).fetch_all(db).await?;
+        Ok(rows)
     }
 }
 
 #[derive(sqlx::FromRow, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct AdministratorRow {
     pub did: String,
     pub permission: i32,
@@ -15,6 +15,7 @@ pub enum CursorState {
 }
 
 #[derive(Debug, Clone, FromRow, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct CursorStateRow {
     pub id: i32,
     pub name: String,
@@ -1,6 +1,6 @@
 use chrono::{DateTime, Local};
 use color_eyre::{Result, eyre::OptionExt};
-use sea_query::{ColumnDef, Expr, Iden, OnConflict, PostgresQueryBuilder};
+use sea_query::{ColumnDef, Expr, Iden, InsertStatement, OnConflict, PostgresQueryBuilder};
 use sea_query_sqlx::SqlxBinder;
 use serde::Serialize;
 use serde_json::Value;
@@ -47,6 +47,11 @@ impl Like {
             r#"CREATE INDEX IF NOT EXISTS idx_like_repo ON "like"(repo)"#,
         ))
         .await?;
+        // viewer 是否点赞：按 (repo, to) 定位
+        db.execute(query(
+            r#"CREATE INDEX IF NOT EXISTS idx_like_repo_to ON "like"(repo, "to")"#,
+        ))
+        .await?;
         Ok(())
     }
 
@@ -57,6 +62,33 @@ impl Like {
         uri: &str,
         cid: &str,
     ) -> Result<()> {
+        let (sql, values) = Self::build_insert(repo, like, uri, cid)?
+            .on_conflict(
+                OnConflict::column(Self::Uri)
+                    .update_columns([Self::Cid, Self::Repo, Self::To, Self::Updated])
+                    .to_owned(),
+            )
+            .build_sqlx(PostgresQueryBuilder);
+        db.execute(query_with(&sql, values)).await?;
+        Ok(())
+    }
+
+    /// 仅在行不存在时写入，已有行保持不变，返回是否写入
+    pub async fn restore(
+        db: &Pool<Postgres>,
+        repo: &str,
+        like: &Value,
+        uri: &str,
+        cid: &str,
+    ) -> Result<bool> {
+        let (sql, values) = Self::build_insert(repo, like, uri, cid)?
+            .on_conflict(OnConflict::column(Self::Uri).do_nothing().to_owned())
+            .build_sqlx(PostgresQueryBuilder);
+        let result = db.execute(query_with(&sql, values)).await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    fn build_insert(repo: &str, like: &Value, uri: &str, cid: &str) -> Result<InsertStatement> {
         let to = like["to"]
             .as_str()
             .map(|s| s.trim_matches('\"'))
@@ -65,7 +97,7 @@ impl Like {
             .as_str()
             .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
             .ok_or_eyre("error in created")?;
-        let (sql, values) = sea_query::Query::insert()
+        Ok(sea_query::Query::insert()
             .into_table(Self::Table)
             .columns([
                 Self::Uri,
@@ -84,18 +116,12 @@ impl Like {
                 created.into(),
             ])?
             .returning_col(Self::Uri)
-            .on_conflict(
-                OnConflict::column(Self::Uri)
-                    .update_columns([Self::Cid, Self::Repo, Self::To, Self::Updated])
-                    .to_owned(),
-            )
-            .build_sqlx(PostgresQueryBuilder);
-        db.execute(query_with(&sql, values)).await?;
-        Ok(())
+            .to_owned())
     }
 }
 
 #[derive(sqlx::FromRow, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
 #[allow(dead_code)]
 pub struct LikeRow {
     pub uri: String,
@@ -107,6 +133,7 @@ pub struct LikeRow {
 }
 
 #[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
 #[allow(dead_code)]
 pub struct LikeView {
     pub uri: String,
@@ -3,6 +3,7 @@ use color_eyre::Result;
 use sea_query::{ColumnDef, Expr, ExprTrait, Iden, PostgresQueryBuilder};
 use sea_query_sqlx::SqlxBinder;
 use serde::Serialize;
+use serde_json::Value;
 use sqlx::{Executor, Pool, Postgres, Row, query};
 use utoipa::ToSchema;
 
@@ -183,9 +184,26 @@ impl Meeting {
         db.execute(sqlx::query_with(&sql, values)).await?;
         Ok(())
     }
+
+    /// 仅取消未结束的会议，返回是否有更新
+    pub async fn cancel(db: &Pool<Postgres>, id: i32) -> Result<bool> {
+        let (sql, values) = sea_query::Query::update()
+            .table(Self::Table)
+            .values([
+                (Self::State, (MeetingState::Canceled as i32).into()),
+                (Self::Updated, Expr::current_timestamp()),
+            ])
+            .and_where(Expr::col(Self::Id).eq(id))
+            .and_where(Expr::col(Self::State).eq(MeetingState::Scheduled as i32))
+            .build_sqlx(PostgresQueryBuilder);
+
+        let result = db.execute(sqlx::query_with(&sql, values)).await?;
+        Ok(result.rows_affected() > 0)
+    }
 }
 
 #[derive(sqlx::FromRow, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct MeetingRow {
     pub id: i32,
     pub title: String,
@@ -202,3 +220,43 @@ pub struct MeetingRow {
     pub updated: DateTime<Local>,
     pub created: DateTime<Local>,
 }
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MeetingView {
+    pub id: i32,
+    pub title: String,
+    pub start_time: DateTime<Local>,
+    pub end_time: DateTime<Local>,
+    pub location: String,
+    pub url: String,
+    pub description: String,
+    pub proposal_uri: String,
+    pub proposal_state: i32,
+    pub state: i32,
+    pub report: Option<String>,
+    pub creator: Value,
+    pub updated: DateTime<Local>,
+    pub created: DateTime<Local>,
+}
+
+impl MeetingView {
+    pub fn build(row: MeetingRow, creator: Value) -> Self {
+        Self {
+            id: row.id,
+            title: row.title,
+            start_time: row.start_time,
+            end_time: row.end_time,
+            location: row.location,
+            url: row.url,
+            description: row.description,
+            proposal_uri: row.proposal_uri,
+            proposal_state: row.proposal_state,
+            state: row.state,
+            report: row.report,
+            creator,
+            updated: row.updated,
+            created: row.created,
+        }
+    }
+}
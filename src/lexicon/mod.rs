@@ -2,11 +2,14 @@ pub mod administrator;
 pub mod cursor_state;
 pub mod like;
 pub mod meeting;
+pub mod notification;
 pub mod profile;
 pub mod proposal;
 pub mod reply;
+pub mod schema;
 pub mod task;
 pub mod timeline;
 pub mod vote;
 pub mod vote_meta;
 pub mod voter_list;
+pub mod voter_proof;
@@ -0,0 +1,125 @@
+use chrono::{DateTime, Local};
+use color_eyre::Result;
+use sea_query::{ColumnDef, Expr, ExprTrait, Iden, PostgresQueryBuilder};
+use sea_query_sqlx::SqlxBinder;
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::{Executor, Pool, Postgres, Row, query};
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, Default, ToSchema)]
+pub enum NotificationType {
+    #[default]
+    Default = 0,
+    /// 1 自己的提案投票已计票
+    VoteFinished = 1,
+    /// 2 被指派了任务
+    TaskAssigned = 2,
+}
+
+#[derive(Iden, Debug, Clone, Copy)]
+pub enum Notification {
+    Table,
+    Id,
+    Recipient,
+    NotificationType,
+    Payload,
+    Read,
+    Created,
+}
+
+impl Notification {
+    pub async fn init(db: &Pool<Postgres>) -> Result<()> {
+        let sql = sea_query::Table::create()
+            .table(Self::Table)
+            .if_not_exists()
+            .col(
+                ColumnDef::new(Self::Id)
+                    .integer()
+                    .not_null()
+                    .auto_increment()
+                    .primary_key(),
+            )
+            .col(ColumnDef::new(Self::Recipient).string().not_null())
+            .col(
+                ColumnDef::new(Self::NotificationType)
+                    .integer()
+                    .not_null()
+                    .default(NotificationType::default() as i32),
+            )
+            .col(ColumnDef::new(Self::Payload).json_binary().default("{}"))
+            .col(
+                ColumnDef::new(Self::Read)
+                    .boolean()
+                    .not_null()
+                    .default(false),
+            )
+            .col(
+                ColumnDef::new(Self::Created)
+                    .timestamp_with_time_zone()
+                    .not_null()
+                    .default(Expr::current_timestamp()),
+            )
+            .build(PostgresQueryBuilder);
+        db.execute(query(&sql)).await?;
+
+        db.execute(query(
+            "CREATE INDEX IF NOT EXISTS idx_notification_recipient ON notification(recipient, read)",
+        ))
+        .await?;
+        Ok(())
+    }
+
+    pub async fn insert<'c>(
+        db: impl Executor<'c, Database = Postgres>,
+        recipient: &str,
+        notification_type: NotificationType,
+        payload: Value,
+    ) -> Result<i32> {
+        let (sql, values) = sea_query::Query::insert()
+            .into_table(Self::Table)
+            .columns([Self::Recipient, Self::NotificationType, Self::Payload])
+            .values([
+                recipient.into(),
+                (notification_type as i32).into(),
+                payload.into(),
+            ])?
+            .returning_col(Self::Id)
+            .build_sqlx(PostgresQueryBuilder);
+        sqlx::query_with(&sql, values)
+            .fetch_one(db)
+            .await
+            .and_then(|r| r.try_get(0))
+            .map_err(|e| color_eyre::eyre::eyre!(e))
+    }
+
+    /// 将 recipient 的通知标记为已读，ids 为空时标记全部，返回更新条数
+    pub async fn mark_read<'c>(
+        db: impl Executor<'c, Database = Postgres>,
+        recipient: &str,
+        ids: &[i32],
+    ) -> Result<u64> {
+        let (sql, values) = sea_query::Query::update()
+            .table(Self::Table)
+            .value(Self::Read, true)
+            .and_where(Expr::col(Self::Recipient).eq(recipient))
+            .and_where(Expr::col(Self::Read).eq(false))
+            .and_where_option(
+                (!ids.is_empty()).then(|| Expr::col(Self::Id).is_in(ids.iter().copied())),
+            )
+            .build_sqlx(PostgresQueryBuilder);
+        let result = sqlx::query_with(&sql, values).execute(db).await?;
+        Ok(result.rows_affected())
+    }
+}
+
+#[derive(sqlx::FromRow, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationRow {
+    pub id: i32,
+    pub recipient: String,
+    pub notification_type: i32,
+    pub payload: Value,
+    pub read: bool,
+    pub created: DateTime<Local>,
+}
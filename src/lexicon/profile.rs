@@ -1,15 +1,39 @@
 use color_eyre::Result;
-use sea_query::{ColumnDef, Iden, OnConflict, PostgresQueryBuilder};
+use sea_query::{ColumnDef, Expr, Iden, OnConflict, PostgresQueryBuilder};
 use sea_query_sqlx::SqlxBinder;
 use serde::Serialize;
 use serde_json::Value;
 use sqlx::{Executor, Pool, Postgres, query, query_with};
 
+const PROFILE_MAX_BYTES: usize = 16 * 1024;
+const DISPLAY_NAME_MAX_CHARS: usize = 64;
+const DESCRIPTION_MAX_CHARS: usize = 256;
+const IMAGE_MAX_SIZE: u64 = 1_000_000;
+
+/// blob 引用：`{"$type": "blob", "ref": {"$link": cid}, "mimeType": "image/png|jpeg", "size": n}`
+fn validate_image_blob(blob: &Value) -> std::result::Result<(), String> {
+    if blob["$type"].as_str() != Some("blob") {
+        return Err("$type must be blob".to_string());
+    }
+    if !blob["ref"]["$link"].as_str().is_some_and(|l| !l.is_empty()) {
+        return Err("ref.$link is required".to_string());
+    }
+    if !matches!(blob["mimeType"].as_str(), Some("image/png" | "image/jpeg")) {
+        return Err("mimeType must be image/png or image/jpeg".to_string());
+    }
+    match blob["size"].as_u64() {
+        Some(size) if size <= IMAGE_MAX_SIZE => Ok(()),
+        Some(_) => Err(format!("size larger than {IMAGE_MAX_SIZE} bytes")),
+        None => Err("size is required".to_string()),
+    }
+}
+
 #[derive(Iden, Debug, Clone, Copy)]
 pub enum Profile {
     Table,
     Did,
     Profile,
+    Updated,
 }
 
 impl Profile {
@@ -19,20 +43,32 @@ impl Profile {
             .if_not_exists()
             .col(ColumnDef::new(Self::Did).string().not_null().primary_key())
             .col(ColumnDef::new(Self::Profile).json_binary().default("{}"))
+            .col(
+                ColumnDef::new(Self::Updated)
+                    .timestamp_with_time_zone()
+                    .not_null()
+                    .default(Expr::current_timestamp()),
+            )
             .build(PostgresQueryBuilder);
         db.execute(query(&sql)).await?;
+
+        // tables created before `updated` was added
+        db.execute(query(
+            "ALTER TABLE profile ADD COLUMN IF NOT EXISTS updated timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP",
+        ))
+        .await?;
         Ok(())
     }
 
     pub async fn insert(db: &Pool<Postgres>, did: &str, profile: Value) -> Result<()> {
         let (sql, values) = sea_query::Query::insert()
             .into_table(Self::Table)
-            .columns([Self::Did, Self::Profile])
-            .values([did.into(), profile.into()])?
+            .columns([Self::Did, Self::Profile, Self::Updated])
+            .values([did.into(), profile.into(), Expr::current_timestamp()])?
             .returning_col(Self::Did)
             .on_conflict(
                 OnConflict::column(Self::Did)
-                    .update_columns([Self::Profile])
+                    .update_columns([Self::Profile, Self::Updated])
                     .to_owned(),
             )
             .build_sqlx(PostgresQueryBuilder);
@@ -41,6 +77,42 @@ impl Profile {
         Ok(())
     }
 
+    /// 按 app.actor.profile 校验，返回逐字段的错误
+    pub fn validate(profile: &Value) -> std::result::Result<(), Vec<String>> {
+        let mut errors = vec![];
+        let Some(fields) = profile.as_object() else {
+            return Err(vec!["profile: must be an object".to_string()]);
+        };
+        if profile.to_string().len() > PROFILE_MAX_BYTES {
+            errors.push(format!("profile: larger than {PROFILE_MAX_BYTES} bytes"));
+        }
+        for (field, max_chars) in [
+            ("displayName", DISPLAY_NAME_MAX_CHARS),
+            ("description", DESCRIPTION_MAX_CHARS),
+        ] {
+            match fields.get(field) {
+                None => {}
+                Some(Value::String(text)) if text.chars().count() <= max_chars => {}
+                Some(Value::String(_)) => {
+                    errors.push(format!("{field}: longer than {max_chars} characters"))
+                }
+                Some(_) => errors.push(format!("{field}: must be a string")),
+            }
+        }
+        for field in ["avatar", "banner"] {
+            if let Some(blob) = fields.get(field)
+                && let Err(e) = validate_image_blob(blob)
+            {
+                errors.push(format!("{field}: {e}"));
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
     pub fn build_select() -> sea_query::SelectStatement {
         sea_query::Query::select()
             .columns([
@@ -53,6 +125,7 @@ impl Profile {
 }
 
 #[derive(sqlx::FromRow, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct ProfileRow {
     pub did: String,
     pub profile: Value,
@@ -1,15 +1,18 @@
 use chrono::{DateTime, Local};
 use color_eyre::{Result, eyre::eyre};
-use sea_query::{ColumnDef, Expr, ExprTrait, Iden, OnConflict, PostgresQueryBuilder};
+use sea_query::{
+    Alias, ColumnDef, Expr, ExprTrait, Iden, InsertStatement, JoinType, OnConflict,
+    PostgresQueryBuilder,
+};
 use sea_query_sqlx::SqlxBinder;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use sqlx::{Executor, Pool, Postgres, query, query_as_with, query_with};
 use utoipa::ToSchema;
 
-use crate::lexicon::vote_meta::VoteMetaRow;
+use crate::lexicon::{like::Like, vote::ViewerVote, vote_meta::VoteMetaRow};
 
-#[derive(Debug, Clone, Copy, Default, ToSchema)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
 pub enum ProposalState {
     End = 0,
 
@@ -74,6 +77,22 @@ impl ProposalState {
             _ => ProposalState::Draft,
         }
     }
+
+    pub const fn is_known(value: i32) -> bool {
+        value >= ProposalState::End as i32 && value <= ProposalState::WaitingRectification as i32
+    }
+
+    /// 是否处于投票阶段
+    pub const fn is_vote(self) -> bool {
+        matches!(
+            self,
+            ProposalState::InitiationVote
+                | ProposalState::MilestoneVote
+                | ProposalState::DelayVote
+                | ProposalState::ReexamineVote
+                | ProposalState::RectificationVote
+        )
+    }
 }
 
 #[derive(Iden, Debug, Clone, Copy)]
@@ -132,6 +151,26 @@ impl Proposal {
             "CREATE INDEX IF NOT EXISTS idx_proposal_updated ON proposal(updated)",
         ))
         .await?;
+
+        // 早期建表时没有里程碑进度与金库地址列
+        db.execute(query(
+            "ALTER TABLE proposal ADD COLUMN IF NOT EXISTS progress integer NOT NULL DEFAULT 0",
+        ))
+        .await?;
+        db.execute(query(
+            "ALTER TABLE proposal ADD COLUMN IF NOT EXISTS receiver_addr varchar",
+        ))
+        .await?;
+
+        // full-text search over title, goals and team
+        db.execute(query(
+            "ALTER TABLE proposal ADD COLUMN IF NOT EXISTS search tsvector GENERATED ALWAYS AS (to_tsvector('simple', coalesce(record #>> '{data,title}', '') || ' ' || coalesce(record #>> '{data,goals}', '') || ' ' || coalesce(record #>> '{data,team}', ''))) STORED",
+        ))
+        .await?;
+        db.execute(query(
+            "CREATE INDEX IF NOT EXISTS idx_proposal_search ON proposal USING GIN(search)",
+        ))
+        .await?;
         Ok(())
     }
 
@@ -142,7 +181,34 @@ impl Proposal {
         uri: &str,
         cid: &str,
     ) -> Result<()> {
-        let (sql, values) = sea_query::Query::insert()
+        let (sql, values) = Self::build_insert(repo, &record, uri, cid)?
+            .on_conflict(
+                OnConflict::column(Self::Uri)
+                    .update_columns([Self::Cid, Self::Repo, Self::Record, Self::Updated])
+                    .to_owned(),
+            )
+            .build_sqlx(PostgresQueryBuilder);
+        db.execute(query_with(&sql, values)).await?;
+        Ok(())
+    }
+
+    /// 仅在行不存在时写入，已有行保持不变，返回是否写入
+    pub async fn restore(
+        db: &Pool<Postgres>,
+        repo: &str,
+        record: &Value,
+        uri: &str,
+        cid: &str,
+    ) -> Result<bool> {
+        let (sql, values) = Self::build_insert(repo, record, uri, cid)?
+            .on_conflict(OnConflict::column(Self::Uri).do_nothing().to_owned())
+            .build_sqlx(PostgresQueryBuilder);
+        let result = db.execute(query_with(&sql, values)).await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    fn build_insert(repo: &str, record: &Value, uri: &str, cid: &str) -> Result<InsertStatement> {
+        Ok(sea_query::Query::insert()
             .into_table(Self::Table)
             .columns([
                 Self::Uri,
@@ -155,27 +221,19 @@ impl Proposal {
                 uri.into(),
                 cid.into(),
                 repo.into(),
-                record.into(),
+                record.clone().into(),
                 Expr::current_timestamp(),
             ])?
             .returning_col(Self::Uri)
-            .on_conflict(
-                OnConflict::column(Self::Uri)
-                    .update_columns([Self::Cid, Self::Repo, Self::Record, Self::Updated])
-                    .to_owned(),
-            )
-            .build_sqlx(PostgresQueryBuilder);
-
-        db.execute(query_with(&sql, values)).await?;
-        Ok(())
+            .to_owned())
     }
 
     pub async fn update(db: &Pool<Postgres>, record: Value, uri: &str, cid: &str) -> Result<()> {
-        let (sql, values) = Proposal::build_select(None)
+        let (sql, values) = Proposal::build_sample()
             .and_where(Expr::col(Proposal::Uri).eq(uri))
             .build_sqlx(PostgresQueryBuilder);
 
-        let proposal_row: ProposalRow = query_as_with(&sql, values.clone()).fetch_one(db).await?;
+        let proposal_row: ProposalSample = query_as_with(&sql, values).fetch_one(db).await?;
 
         // check proposal state
         if proposal_row.state != (ProposalState::Draft as i32)
@@ -203,25 +261,43 @@ impl Proposal {
     }
 
     pub fn build_select(viewer: Option<String>) -> sea_query::SelectStatement {
-        sea_query::Query::select()
-        .columns([
-            (Proposal::Table, Proposal::Uri),
-            (Proposal::Table, Proposal::Cid),
-            (Proposal::Table, Proposal::Repo),
-            (Proposal::Table, Proposal::Record),
-            (Proposal::Table, Proposal::Progress),
-            (Proposal::Table, Proposal::State),
-            (Proposal::Table, Proposal::Updated),
-            (Proposal::Table, Proposal::ReceiverAddr),
-        ])
-        .expr(Expr::cust("(select count(\"like\".\"uri\") from \"like\" where \"like\".\"to\" = \"proposal\".\"uri\") as like_count"))
-        .expr(if let Some(viewer) = viewer {
-            Expr::cust_with_values("((select count(\"like\".\"uri\") from \"like\" where \"like\".\"repo\" = $1 and \"like\".\"to\" = \"proposal\".\"uri\" ) > 0) as liked", [viewer])
+        // 每个提案只按 "to" 索引扫描一次 like，同时得到点赞数与 viewer 是否点赞
+        let liked = if let Some(viewer) = viewer {
+            Expr::cust_with_values(
+                "coalesce(bool_or(\"like\".\"repo\" = $1), false) as liked",
+                [viewer],
+            )
         } else {
-            Expr::cust("false as liked".to_string())
-        })
-        .from(Proposal::Table)
-        .take()
+            Expr::cust("false as liked")
+        };
+        let like_stats = sea_query::Query::select()
+            .expr(Expr::cust("count(\"like\".\"uri\") as like_count"))
+            .expr(liked)
+            .from(Like::Table)
+            .and_where(Expr::cust("\"like\".\"to\" = \"proposal\".\"uri\""))
+            .take();
+
+        sea_query::Query::select()
+            .columns([
+                (Proposal::Table, Proposal::Uri),
+                (Proposal::Table, Proposal::Cid),
+                (Proposal::Table, Proposal::Repo),
+                (Proposal::Table, Proposal::Record),
+                (Proposal::Table, Proposal::Progress),
+                (Proposal::Table, Proposal::State),
+                (Proposal::Table, Proposal::Updated),
+                (Proposal::Table, Proposal::ReceiverAddr),
+            ])
+            .expr(Expr::cust("\"like_stats\".\"like_count\""))
+            .expr(Expr::cust("\"like_stats\".\"liked\""))
+            .from(Proposal::Table)
+            .join_lateral(
+                JoinType::LeftJoin,
+                like_stats,
+                Alias::new("like_stats"),
+                Expr::cust("true"),
+            )
+            .take()
     }
 
     pub fn build_sample() -> sea_query::SelectStatement {
@@ -240,7 +316,11 @@ impl Proposal {
             .take()
     }
 
-    pub async fn update_state(db: &Pool<Postgres>, uri: &str, state: i32) -> Result<u64> {
+    pub async fn update_state<'c>(
+        db: impl Executor<'c, Database = Postgres>,
+        uri: &str,
+        state: i32,
+    ) -> Result<u64> {
         let (sql, values) = sea_query::Query::update()
             .table(Self::Table)
             .values([
@@ -254,8 +334,8 @@ impl Proposal {
         Ok(lines)
     }
 
-    pub async fn update_progress(
-        db: &Pool<Postgres>,
+    pub async fn update_progress<'c>(
+        db: impl Executor<'c, Database = Postgres>,
         uri: &str,
         state: i32,
         progress: i32,
@@ -298,6 +378,7 @@ impl Proposal {
 }
 
 #[derive(sqlx::FromRow, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct ProposalSample {
     pub uri: String,
     pub cid: String,
@@ -310,6 +391,7 @@ pub struct ProposalSample {
 }
 
 #[derive(sqlx::FromRow, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct ProposalRow {
     pub uri: String,
     pub cid: String,
@@ -323,19 +405,29 @@ pub struct ProposalRow {
     pub liked: bool,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
 pub struct ProposalView {
     pub uri: String,
     pub cid: String,
+    #[schema(value_type = Object)]
     pub author: Value,
+    #[schema(value_type = Object)]
     pub record: Value,
     pub progress: i32,
     pub state: i32,
+    #[schema(value_type = String, format = DateTime)]
     pub updated: DateTime<Local>,
     pub receiver_addr: Option<String>,
     pub like_count: String,
     pub liked: bool,
     pub vote_meta: Option<VoteMetaRow>,
+    /// 当前投票的计票结果，仅详情接口返回
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vote_result: Option<u32>,
+    /// 仅详情接口在传入 viewer 且投票进行中时返回
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub viewer_vote: Option<ViewerVote>,
 }
 
 impl ProposalView {
@@ -352,18 +444,90 @@ impl ProposalView {
             like_count: row.like_count.to_string(),
             liked: row.liked,
             vote_meta,
+            vote_result: None,
+            viewer_vote: None,
         }
     }
 }
 
+/// 提案记录中计票、拨款依赖的字段
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProposalRecord {
+    pub data: ProposalData,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProposalData {
+    pub proposal_type: String,
+    /// 记录中为数字字符串，单位 CKB；与 `schema::validate_proposal` 一致为必填
+    #[serde(deserialize_with = "deserialize_budget")]
+    pub budget: u64,
+    #[serde(default)]
+    pub milestones: Vec<Value>,
+}
+
+impl ProposalData {
+    pub fn is_budget_proposal(&self) -> bool {
+        self.proposal_type == "BudgetProposal"
+    }
+}
+
+fn deserialize_budget<'de, D>(deserializer: D) -> std::result::Result<u64, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let budget = String::deserialize(deserializer)?;
+    budget
+        .parse::<u64>()
+        .map_err(|e| serde::de::Error::custom(format!("invalid budget {budget:?}: {e}")))
+}
+
+impl ProposalRecord {
+    pub fn parse(uri: &str, record: &Value) -> Result<Self> {
+        serde_json::from_value(record.clone())
+            .map_err(|e| eyre!("invalid proposal record {uri}: {e}"))
+    }
+}
+
+impl ProposalSample {
+    pub fn parse_record(&self) -> Result<ProposalRecord> {
+        ProposalRecord::parse(&self.uri, &self.record)
+    }
+}
+
 pub fn has_next_milestone(proposal_sample: &ProposalSample) -> Option<(usize, Value)> {
-    if let Some(milestones) = proposal_sample
-        .record
-        .pointer("/data/milestones")
-        .and_then(|m| m.as_array())
-    {
-        let next_index = proposal_sample.progress as usize + 1;
-        return milestones.get(next_index).map(|m| (next_index, m.clone()));
+    let record = proposal_sample
+        .parse_record()
+        .map_err(|e| error!("{e}"))
+        .ok()?;
+    let next_index = proposal_sample.progress as usize + 1;
+    record
+        .data
+        .milestones
+        .get(next_index)
+        .map(|m| (next_index, m.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn budget_is_required_and_numeric() {
+        let record = json!({ "data": { "proposalType": "BudgetProposal", "budget": "100" } });
+        assert_eq!(
+            ProposalRecord::parse("at://p", &record)
+                .unwrap()
+                .data
+                .budget,
+            100
+        );
+        let record = json!({ "data": { "proposalType": "BudgetProposal" } });
+        assert!(ProposalRecord::parse("at://p", &record).is_err());
+        let record = json!({ "data": { "proposalType": "BudgetProposal", "budget": "-1" } });
+        assert!(ProposalRecord::parse("at://p", &record).is_err());
     }
-    None
 }
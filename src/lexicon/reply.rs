@@ -1,6 +1,6 @@
 use chrono::{DateTime, Local};
 use color_eyre::{Result, eyre::OptionExt};
-use sea_query::{ColumnDef, Expr, Iden, OnConflict, PostgresQueryBuilder};
+use sea_query::{ColumnDef, Expr, Iden, InsertStatement, OnConflict, PostgresQueryBuilder};
 use sea_query_sqlx::SqlxBinder;
 use serde::Serialize;
 use serde_json::Value;
@@ -68,6 +68,40 @@ impl Reply {
         uri: &str,
         cid: &str,
     ) -> Result<()> {
+        let (sql, values) = Self::build_insert(repo, reply, uri, cid)?
+            .on_conflict(
+                OnConflict::column(Self::Uri)
+                    .update_columns([
+                        Self::Cid,
+                        Self::Repo,
+                        Self::Proposal,
+                        Self::To,
+                        Self::Text,
+                        Self::Updated,
+                    ])
+                    .to_owned(),
+            )
+            .build_sqlx(PostgresQueryBuilder);
+        db.execute(query_with(&sql, values)).await?;
+        Ok(())
+    }
+
+    /// 仅在行不存在时写入，已有行保持不变，返回是否写入
+    pub async fn restore(
+        db: &Pool<Postgres>,
+        repo: &str,
+        reply: &Value,
+        uri: &str,
+        cid: &str,
+    ) -> Result<bool> {
+        let (sql, values) = Self::build_insert(repo, reply, uri, cid)?
+            .on_conflict(OnConflict::column(Self::Uri).do_nothing().to_owned())
+            .build_sqlx(PostgresQueryBuilder);
+        let result = db.execute(query_with(&sql, values)).await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    fn build_insert(repo: &str, reply: &Value, uri: &str, cid: &str) -> Result<InsertStatement> {
         let proposal = reply["proposal"]
             .as_str()
             .map(|s| s.trim_matches('\"'))
@@ -84,7 +118,7 @@ impl Reply {
             .as_str()
             .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
             .ok_or_eyre("error in created")?;
-        let (sql, values) = sea_query::Query::insert()
+        Ok(sea_query::Query::insert()
             .into_table(Self::Table)
             .columns([
                 Self::Uri,
@@ -107,25 +141,12 @@ impl Reply {
                 created.into(),
             ])?
             .returning_col(Self::Uri)
-            .on_conflict(
-                OnConflict::column(Self::Uri)
-                    .update_columns([
-                        Self::Cid,
-                        Self::Repo,
-                        Self::Proposal,
-                        Self::To,
-                        Self::Text,
-                        Self::Updated,
-                    ])
-                    .to_owned(),
-            )
-            .build_sqlx(PostgresQueryBuilder);
-        db.execute(query_with(&sql, values)).await?;
-        Ok(())
+            .to_owned())
     }
 }
 
 #[derive(sqlx::FromRow, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct ReplySampleRow {
     pub uri: String,
     pub cid: String,
@@ -138,6 +159,7 @@ pub struct ReplySampleRow {
 }
 
 #[derive(sqlx::FromRow, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct ReplyRow {
     pub uri: String,
     pub cid: String,
@@ -152,6 +174,7 @@ pub struct ReplyRow {
 }
 
 #[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct ReplyView {
     pub uri: String,
     pub cid: String,
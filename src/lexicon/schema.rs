@@ -0,0 +1,106 @@
+use serde_json::Value;
+
+use crate::{
+    atproto::{NSID_LIKE, NSID_PROFILE, NSID_PROPOSAL, NSID_REPLY},
+    error::AppError,
+    lexicon::profile::Profile,
+};
+
+/// 入库前按集合校验记录结构，错误信息带出问题字段的路径
+pub fn validate_record(nsid: &str, record: &Value) -> Result<(), AppError> {
+    let result = match nsid {
+        NSID_PROPOSAL => validate_proposal(record),
+        NSID_REPLY => validate_reply(record),
+        NSID_LIKE => validate_like(record),
+        NSID_PROFILE => Profile::validate(record).map_err(|errors| errors.join("; ")),
+        _ => Err(format!("unknown collection {nsid}")),
+    };
+    result.map_err(|e| AppError::ValidateFailed(format!("{nsid}: {e}")))
+}
+
+fn validate_proposal(record: &Value) -> Result<(), String> {
+    if !record.pointer("/data").is_some_and(Value::is_object) {
+        return Err("/data: required object".to_string());
+    }
+    require_str(record, "/data/proposalType")?;
+    // 计票门槛按预算计算，必须是整数
+    let budget = require_str(record, "/data/budget")?;
+    if budget.parse::<u64>().is_err() {
+        return Err(format!("/data/budget: not an unsigned integer: {budget}"));
+    }
+    if let Some(milestones) = record.pointer("/data/milestones")
+        && !milestones.is_array()
+    {
+        return Err("/data/milestones: must be an array".to_string());
+    }
+    Ok(())
+}
+
+fn validate_reply(record: &Value) -> Result<(), String> {
+    require_str(record, "/proposal")?;
+    // 空文本是合法回复，只校验类型
+    match record.pointer("/text") {
+        Some(Value::String(_)) => {}
+        Some(_) => return Err("/text: must be a string".to_string()),
+        None => return Err("/text: required".to_string()),
+    }
+    require_datetime(record, "/created")?;
+    if let Some(to) = record.pointer("/to")
+        && !to.is_string()
+    {
+        return Err("/to: must be a string".to_string());
+    }
+    Ok(())
+}
+
+fn validate_like(record: &Value) -> Result<(), String> {
+    require_str(record, "/to")?;
+    require_datetime(record, "/created")?;
+    Ok(())
+}
+
+fn require_str<'a>(record: &'a Value, path: &str) -> Result<&'a str, String> {
+    match record.pointer(path) {
+        Some(Value::String(s)) if !s.is_empty() => Ok(s),
+        Some(Value::String(_)) => Err(format!("{path}: must not be empty")),
+        Some(_) => Err(format!("{path}: must be a string")),
+        None => Err(format!("{path}: required")),
+    }
+}
+
+fn require_datetime(record: &Value, path: &str) -> Result<(), String> {
+    let value = require_str(record, path)?;
+    chrono::DateTime::parse_from_rfc3339(value)
+        .map(|_| ())
+        .map_err(|e| format!("{path}: invalid rfc3339 datetime: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn proposal_requires_type_and_budget_only() {
+        let record = json!({ "data": { "proposalType": "budget", "budget": "100" } });
+        assert!(validate_record(NSID_PROPOSAL, &record).is_ok());
+        let record = json!({ "data": { "budget": "100" } });
+        assert!(validate_record(NSID_PROPOSAL, &record).is_err());
+        let record = json!({ "data": { "proposalType": "budget", "budget": "1.5" } });
+        assert!(validate_record(NSID_PROPOSAL, &record).is_err());
+    }
+
+    #[test]
+    fn reply_allows_empty_text() {
+        let record = json!({
+            "proposal": "at://did:plc:a/app.dao.proposal/1",
+            "text": "",
+            "created": "2026-01-01T00:00:00Z",
+        });
+        assert!(validate_record(NSID_REPLY, &record).is_ok());
+        let mut record = record;
+        record["text"] = json!(1);
+        assert!(validate_record(NSID_REPLY, &record).is_err());
+    }
+}
@@ -1,13 +1,13 @@
 use chrono::{DateTime, Local};
 use color_eyre::Result;
-use sea_query::{ColumnDef, ColumnType, Expr, ExprTrait, Iden, PostgresQueryBuilder};
+use sea_query::{ColumnDef, ColumnType, Expr, ExprTrait, Iden, Order, PostgresQueryBuilder};
 use sea_query_sqlx::SqlxBinder;
 use serde::Serialize;
 use serde_json::Value;
 use sqlx::{Executor, Pool, Postgres, Row, query};
 use utoipa::ToSchema;
 
-#[derive(Debug, Clone, Copy, Default, ToSchema)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ToSchema, clap::ValueEnum)]
 pub enum TaskType {
     #[default]
     Default = 0,
@@ -55,6 +55,22 @@ pub enum TaskType {
     SubmitReexamineReport,
 }
 
+impl TaskType {
+    /// 拨款后可作为里程碑后续任务的类型
+    pub const fn is_fund_follow_up(self) -> bool {
+        matches!(self, Self::SubmitMilestoneReport | Self::SubmitDelayReport)
+    }
+
+    /// 与里程碑拨款、报告相关的任务类型
+    pub const MILESTONE: [TaskType; 5] = [
+        Self::SendInitialFund,
+        Self::SubmitMilestoneReport,
+        Self::SubmitDelayReport,
+        Self::SendMilestoneFund,
+        Self::SubmitAcceptanceReport,
+    ];
+}
+
 #[derive(Debug, Clone, Copy, Default, ToSchema)]
 pub enum TaskState {
     /// 0 未读
@@ -79,6 +95,7 @@ pub enum Task {
     State,
     Updated,
     Created,
+    Overdue,
 }
 
 impl Task {
@@ -142,10 +159,19 @@ impl Task {
             "CREATE INDEX IF NOT EXISTS idx_task_state ON task(state)",
         ))
         .await?;
+
+        // 早期建表时没有逾期标记列
+        db.execute(query(
+            "ALTER TABLE task ADD COLUMN IF NOT EXISTS overdue boolean NOT NULL DEFAULT false",
+        ))
+        .await?;
         Ok(())
     }
 
-    pub async fn insert(db: &Pool<Postgres>, row: &TaskRow) -> Result<i32> {
+    pub async fn insert<'c>(
+        db: impl Executor<'c, Database = Postgres>,
+        row: &TaskRow,
+    ) -> Result<i32> {
         let (sql, values) = sea_query::Query::insert()
             .into_table(Self::Table)
             .columns([
@@ -172,19 +198,21 @@ impl Task {
             ])?
             .returning_col(Self::Id)
             .build_sqlx(PostgresQueryBuilder);
-        sqlx::query_with(&sql, values)
+        let id: i32 = sqlx::query_with(&sql, values)
             .fetch_one(db)
             .await
             .and_then(|r| r.try_get(0))
-            .map_err(|e| color_eyre::eyre::eyre!(e))
+            .map_err(|e| color_eyre::eyre::eyre!(e))?;
+        Ok(id)
     }
 
-    pub async fn complete(
-        db: &Pool<Postgres>,
+    /// 没有对应任务时返回 `None`
+    pub async fn complete<'c>(
+        db: impl Executor<'c, Database = Postgres>,
         target: &str,
         t: TaskType,
         processor: &str,
-    ) -> Result<i32> {
+    ) -> Result<Option<i32>> {
         let (sql, values) = sea_query::Query::update()
             .table(Self::Table)
             .values([
@@ -197,9 +225,9 @@ impl Task {
             .returning_col(Self::Id)
             .build_sqlx(PostgresQueryBuilder);
         sqlx::query_with(&sql, values)
-            .fetch_one(db)
+            .fetch_optional(db)
             .await
-            .and_then(|r| r.try_get(0))
+            .and_then(|r| r.map(|r| r.try_get(0)).transpose())
             .map_err(|e| color_eyre::eyre::eyre!(e))
     }
 
@@ -220,9 +248,173 @@ impl Task {
             .and_then(|r| r.try_get(0))
             .map_err(|e| color_eyre::eyre::eyre!(e))
     }
+
+    /// 目标下指定类型中尚未完成的任务，按创建时间升序
+    pub async fn pending<'c>(
+        db: impl Executor<'c, Database = Postgres>,
+        target: &str,
+        types: &[TaskType],
+    ) -> Result<Vec<TaskRow>> {
+        let (sql, values) = sea_query::Query::select()
+            .columns([
+                Self::Id,
+                Self::TaskType,
+                Self::Message,
+                Self::Target,
+                Self::Operators,
+                Self::Processor,
+                Self::Deadline,
+                Self::State,
+                Self::Updated,
+                Self::Created,
+                Self::Overdue,
+            ])
+            .from(Self::Table)
+            .and_where(Expr::col(Self::Target).eq(target))
+            .and_where(Expr::col(Self::TaskType).is_in(types.iter().map(|t| *t as i32)))
+            .and_where(Expr::col(Self::State).ne(TaskState::Completed as i32))
+            .order_by(Self::Created, Order::Asc)
+            .build_sqlx(PostgresQueryBuilder);
+        let rows = sqlx::query_as_with(&sql, values).fetch_all(db).await?;
+        Ok(rows)
+    }
+
+    pub async fn get<'c>(
+        db: impl Executor<'c, Database = Postgres>,
+        id: i32,
+    ) -> Result<Option<TaskRow>> {
+        let (sql, values) = sea_query::Query::select()
+            .columns([
+                Self::Id,
+                Self::TaskType,
+                Self::Message,
+                Self::Target,
+                Self::Operators,
+                Self::Processor,
+                Self::Deadline,
+                Self::State,
+                Self::Updated,
+                Self::Created,
+                Self::Overdue,
+            ])
+            .from(Self::Table)
+            .and_where(Expr::col(Self::Id).eq(id))
+            .build_sqlx(PostgresQueryBuilder);
+        let row = sqlx::query_as_with(&sql, values).fetch_optional(db).await?;
+        Ok(row)
+    }
+
+    /// 将已过截止时间且未完成的任务标记为逾期，返回本次新标记的任务
+    pub async fn mark_overdue<'c>(
+        db: impl Executor<'c, Database = Postgres>,
+    ) -> Result<Vec<TaskRow>> {
+        let (sql, values) = sea_query::Query::update()
+            .table(Self::Table)
+            .values([
+                (Self::Overdue, true.into()),
+                (Self::Updated, Expr::current_timestamp()),
+            ])
+            .and_where(Expr::col(Self::State).ne(TaskState::Completed as i32))
+            .and_where(Expr::col(Self::Overdue).eq(false))
+            .and_where(Expr::col(Self::Deadline).lt(Expr::current_timestamp()))
+            .returning(sea_query::Query::returning().columns([
+                Self::Id,
+                Self::TaskType,
+                Self::Message,
+                Self::Target,
+                Self::Operators,
+                Self::Processor,
+                Self::Deadline,
+                Self::State,
+                Self::Updated,
+                Self::Created,
+                Self::Overdue,
+            ]))
+            .build_sqlx(PostgresQueryBuilder);
+        let rows = sqlx::query_as_with(&sql, values).fetch_all(db).await?;
+        Ok(rows)
+    }
+
+    /// 认领任务：记录处理人并置为已读；任务已完成、不在 operators 中或已被他人认领时返回 false
+    pub async fn claim<'c>(
+        db: impl Executor<'c, Database = Postgres>,
+        id: i32,
+        processor: &str,
+    ) -> Result<bool> {
+        let (sql, values) = sea_query::Query::update()
+            .table(Self::Table)
+            .values([
+                (Self::State, (TaskState::Read as i32).into()),
+                (Self::Updated, Expr::current_timestamp()),
+                (Self::Processor, processor.into()),
+            ])
+            .and_where(Expr::col(Self::Id).eq(id))
+            .and_where(Expr::col(Self::State).ne(TaskState::Completed as i32))
+            // 仅 operators 中的人可认领，且不能抢走他人已认领的任务
+            .and_where(Expr::cust_with_values(
+                "$1 = ANY(\"task\".\"operators\")",
+                [processor],
+            ))
+            .and_where(
+                Expr::col(Self::Processor)
+                    .is_null()
+                    .or(Expr::col(Self::Processor).eq(processor)),
+            )
+            .build_sqlx(PostgresQueryBuilder);
+        let result = sqlx::query_with(&sql, values).execute(db).await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// 重新指定处理人范围；任务已完成时返回 false
+    pub async fn reassign<'c>(
+        db: impl Executor<'c, Database = Postgres>,
+        id: i32,
+        operators: &[String],
+    ) -> Result<bool> {
+        let (sql, values) = sea_query::Query::update()
+            .table(Self::Table)
+            .values([
+                (Self::Operators, operators.to_vec().into()),
+                (Self::Updated, Expr::current_timestamp()),
+            ])
+            .and_where(Expr::col(Self::Id).eq(id))
+            .and_where(Expr::col(Self::State).ne(TaskState::Completed as i32))
+            .build_sqlx(PostgresQueryBuilder);
+        let result = sqlx::query_with(&sql, values).execute(db).await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// 目标下的全部任务（含已完成），按创建时间升序
+    pub async fn by_target<'c>(
+        db: impl Executor<'c, Database = Postgres>,
+        target: &str,
+    ) -> Result<Vec<TaskRow>> {
+        let (sql, values) = sea_query::Query::select()
+            .columns([
+                Self::Id,
+                Self::TaskType,
+                Self::Message,
+                Self::Target,
+                Self::Operators,
+                Self::Processor,
+                Self::Deadline,
+                Self::State,
+                Self::Updated,
+                Self::Created,
+                Self::Overdue,
+            ])
+            .from(Self::Table)
+            .and_where(Expr::col(Self::Target).eq(target))
+            .order_by(Self::Created, Order::Asc)
+            .order_by(Self::Id, Order::Asc)
+            .build_sqlx(PostgresQueryBuilder);
+        let rows = sqlx::query_as_with(&sql, values).fetch_all(db).await?;
+        Ok(rows)
+    }
 }
 
 #[derive(sqlx::FromRow, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
 #[allow(dead_code)]
 pub struct TaskRow {
     pub id: i32,
@@ -235,9 +427,12 @@ pub struct TaskRow {
     pub state: i32,
     pub updated: DateTime<Local>,
     pub created: DateTime<Local>,
+    /// 已过截止时间仍未完成，由定时任务标记
+    pub overdue: bool,
 }
 
 #[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
 #[allow(dead_code)]
 pub struct TaskView {
     pub id: i32,
@@ -250,4 +445,5 @@ pub struct TaskView {
     pub state: i32,
     pub updated: DateTime<Local>,
     pub created: DateTime<Local>,
+    pub overdue: bool,
 }
@@ -12,49 +12,51 @@ pub enum TimelineType {
     #[default]
     Default = 0,
     /// 1 创建提案
-    ProposalCreated,
+    ProposalCreated = 1,
     /// 2 编辑提案
-    ProposalEdited,
+    ProposalEdited = 2,
     /// 3 发起立项投票
-    InitiationVote,
+    InitiationVote = 3,
     /// 4 维护项目金库地址
-    UpdateReceiverAddr,
+    UpdateReceiverAddr = 4,
     /// 5 计票完成
-    VoteFinished,
+    VoteFinished = 5,
     /// 6 发送初始资金
-    SendInitialFund,
+    SendInitialFund = 6,
     /// 7 提交里程碑报告
-    SubmitMilestoneReport,
+    SubmitMilestoneReport = 7,
     /// 8 提交延期报告
-    SubmitDelayReport,
+    SubmitDelayReport = 8,
     /// 9 发起里程碑投票
-    MilestoneVote,
+    MilestoneVote = 9,
     /// 10 发起延期投票
-    DelayVote,
+    DelayVote = 10,
     /// 11 发送里程碑资金
-    SendMilestoneFund,
+    SendMilestoneFund = 11,
     /// 12 发起复核投票
-    ReexamineVote,
+    ReexamineVote = 12,
     /// 13 发起验收投票
-    AcceptanceVote,
+    AcceptanceVote = 13,
     /// 14 发起整改投票
-    RectificationVote,
+    RectificationVote = 14,
     /// 15 提交验收报告
-    SubmitAcceptanceReport,
+    SubmitAcceptanceReport = 15,
     /// 16 组织AMA
-    CreateAMA,
+    CreateAMA = 16,
     /// 17 提交AMA报告
-    SubmitAMAReport,
+    SubmitAMAReport = 17,
     /// 18 组织复核会议
-    CreateReexamineMeeting,
+    CreateReexamineMeeting = 18,
     /// 19 提交复核会议报告
-    SubmitReexamineReport,
+    SubmitReexamineReport = 19,
     /// 20 项目整改
-    Rectification,
+    Rectification = 20,
     /// 21 创建投票交易失败
-    VoteMetaTxChanged,
+    VoteMetaTxChanged = 21,
     /// 22 创建投票交易超时
-    VoteMetaTxTimeout,
+    VoteMetaTxTimeout = 22,
+    /// 23 取消会议
+    CancelMeeting = 23,
 }
 
 #[derive(Iden, Debug, Clone, Copy)]
@@ -105,7 +107,10 @@ impl Timeline {
         Ok(())
     }
 
-    pub async fn insert(db: &Pool<Postgres>, row: &TimelineRow) -> Result<i32> {
+    pub async fn insert<'c>(
+        db: impl Executor<'c, Database = Postgres>,
+        row: &TimelineRow,
+    ) -> Result<i32> {
         let (sql, values) = sea_query::Query::insert()
             .into_table(Self::Table)
             .columns([
@@ -133,6 +138,7 @@ impl Timeline {
 }
 
 #[derive(sqlx::FromRow, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
 #[allow(dead_code)]
 pub struct TimelineRow {
     pub id: i32,
@@ -144,12 +150,23 @@ pub struct TimelineRow {
 }
 
 #[derive(sqlx::FromRow, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
 #[allow(dead_code)]
 pub struct TimelineView {
     pub id: i32,
     pub timeline_type: i32,
-    pub message: String,
+    /// JSON 对象 / 数组按结构返回，其余为原字符串
+    pub message: Value,
     pub target: String,
     pub operator: Value,
     pub timestamp: DateTime<Local>,
 }
+
+impl TimelineView {
+    pub fn parse_message(message: String) -> Value {
+        match serde_json::from_str::<Value>(&message) {
+            Ok(value) if value.is_object() || value.is_array() => value,
+            _ => Value::String(message),
+        }
+    }
+}
@@ -31,6 +31,21 @@ pub enum VoteState {
     Rejected = 3,
     /// 4 投票已结束
     Finished = 4,
+    /// 无法识别的旧值，调度任务不做处理
+    Unknown = -1,
+}
+
+impl From<i32> for VoteState {
+    fn from(value: i32) -> Self {
+        match value {
+            0 => VoteState::Waiting,
+            1 => VoteState::Committed,
+            2 => VoteState::Timeout,
+            3 => VoteState::Rejected,
+            4 => VoteState::Finished,
+            _ => VoteState::Unknown,
+        }
+    }
 }
 
 impl Vote {
@@ -125,7 +140,18 @@ impl Vote {
     }
 }
 
+/// 当前查看者在进行中投票里的资格与投票情况
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ViewerVote {
+    pub eligible: bool,
+    pub has_voted: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub candidates_index: Option<i32>,
+}
+
 #[derive(sqlx::FromRow, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct VoteRow {
     pub id: i32,
     pub state: i32,
@@ -135,3 +161,28 @@ pub struct VoteRow {
     pub voter: String,
     pub created: DateTime<Local>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_states_round_trip() {
+        for state in [
+            VoteState::Waiting,
+            VoteState::Committed,
+            VoteState::Timeout,
+            VoteState::Rejected,
+            VoteState::Finished,
+        ] {
+            assert_eq!(VoteState::from(state as i32), state);
+        }
+    }
+
+    #[test]
+    fn out_of_range_states_are_unknown() {
+        for value in [-1, 5, 100, i32::MIN, i32::MAX] {
+            assert_eq!(VoteState::from(value), VoteState::Unknown);
+        }
+    }
+}
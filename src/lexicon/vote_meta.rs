@@ -7,7 +7,7 @@ use serde_json::Value;
 use sqlx::{Executor, Pool, Postgres, Row, query, query_with};
 use utoipa::ToSchema;
 
-use crate::lexicon::proposal::ProposalState;
+use crate::{governance::GovernanceParams, lexicon::proposal::ProposalState};
 
 #[derive(Iden, Debug, Clone, Copy)]
 pub enum VoteMeta {
@@ -25,8 +25,14 @@ pub enum VoteMeta {
     Creator,
     Results,
     Created,
+    DurationDays,
 }
 
+/// 投票选项，由服务端固定下发，不接受客户端传入
+///
+/// 下标即链上 candidates_index，计票按 0 弃权 / 1 赞成 / 2 反对 处理
+pub const VOTE_CANDIDATES: [&str; 3] = ["Abstain", "Agree", "Against"];
+
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ToSchema)]
 pub enum VoteMetaState {
     /// 0 等待发送交易
@@ -42,6 +48,22 @@ pub enum VoteMetaState {
     Finished = 4,
     /// 5 投票已改变
     Changed = 5,
+    /// 无法识别的旧值，调度任务不做处理
+    Unknown = -1,
+}
+
+impl From<i32> for VoteMetaState {
+    fn from(value: i32) -> Self {
+        match value {
+            0 => VoteMetaState::Waiting,
+            1 => VoteMetaState::Committed,
+            2 => VoteMetaState::Timeout,
+            3 => VoteMetaState::Rejected,
+            4 => VoteMetaState::Finished,
+            5 => VoteMetaState::Changed,
+            _ => VoteMetaState::Unknown,
+        }
+    }
 }
 
 impl VoteMeta {
@@ -83,9 +105,16 @@ impl VoteMeta {
                     .not_null()
                     .default(Expr::current_timestamp()),
             )
+            .col(ColumnDef::new(Self::DurationDays).integer())
             .build(PostgresQueryBuilder);
         db.execute(query(&sql)).await?;
 
+        // 早期建表时没有投票时长列，旧记录为 NULL
+        db.execute(query(
+            "ALTER TABLE vote_meta ADD COLUMN IF NOT EXISTS duration_days integer",
+        ))
+        .await?;
+
         db.execute(query(
             "CREATE INDEX IF NOT EXISTS idx_vote_meta_proposal_uri ON vote_meta(proposal_uri)",
         ))
@@ -113,6 +142,7 @@ impl VoteMeta {
                 Self::Creator,
                 Self::Results,
                 Self::Created,
+                Self::DurationDays,
             ])
             .values([
                 row.proposal_state.into(),
@@ -127,6 +157,7 @@ impl VoteMeta {
                 row.creator.clone().into(),
                 row.results.clone().into(),
                 Expr::current_timestamp(),
+                row.duration_days.into(),
             ])?
             .returning_col(Self::Id)
             .build_sqlx(PostgresQueryBuilder);
@@ -148,7 +179,12 @@ impl VoteMeta {
         Ok(())
     }
 
-    pub async fn update_results(db: &Pool<Postgres>, id: i32, results: Value) -> Result<()> {
+    /// 仅 Committed 的投票可以结束，已结束时返回 `false`，保证只结算一次
+    pub async fn update_results<'c>(
+        db: impl Executor<'c, Database = Postgres>,
+        id: i32,
+        results: Value,
+    ) -> Result<bool> {
         let (sql, values) = sea_query::Query::update()
             .table(Self::Table)
             .values([
@@ -156,10 +192,11 @@ impl VoteMeta {
                 (Self::State, (VoteMetaState::Finished as i32).into()),
             ])
             .and_where(Expr::col(Self::Id).eq(id))
+            .and_where(Expr::col(Self::State).eq(VoteMetaState::Committed as i32))
             .build_sqlx(PostgresQueryBuilder);
 
-        db.execute(query_with(&sql, values)).await?;
-        Ok(())
+        let lines = db.execute(query_with(&sql, values)).await?.rows_affected();
+        Ok(lines > 0)
     }
 
     pub fn build_select() -> sea_query::SelectStatement {
@@ -178,13 +215,15 @@ impl VoteMeta {
                 (Self::Table, Self::Creator),
                 (Self::Table, Self::Results),
                 (Self::Table, Self::Created),
+                (Self::Table, Self::DurationDays),
             ])
             .from(Self::Table)
             .take()
     }
 }
 
-#[derive(sqlx::FromRow, Debug, Serialize)]
+#[derive(sqlx::FromRow, Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
 pub struct VoteMetaRow {
     pub id: i32,
     pub proposal_state: i32,
@@ -197,25 +236,61 @@ pub struct VoteMetaRow {
     pub start_time: i64,
     pub end_time: i64,
     pub creator: String,
+    #[schema(value_type = Option<Object>)]
     pub results: Option<Value>,
+    #[schema(value_type = String, format = DateTime)]
     pub created: DateTime<Local>,
+    /// 创建时按治理配置确定的投票时长（天）
+    pub duration_days: Option<i32>,
+}
+
+impl VoteMetaRow {
+    /// 投票时长（天），之后修改配置不影响已创建的投票；该列加入前的旧记录按当前配置计算
+    pub fn vote_duration_days(&self, governance: &GovernanceParams) -> u64 {
+        self.duration_days
+            .and_then(|days| u64::try_from(days).ok())
+            .unwrap_or_else(|| governance.vote_duration_days(self.proposal_state))
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
 pub struct VoteResults {
+    // alias: results written before the camelCase switch are stored in snake_case
+    #[serde(alias = "vote_sum")]
     pub vote_sum: u64,
+    #[serde(alias = "valid_vote_sum")]
     pub valid_vote_sum: u64,
+    #[serde(alias = "valid_weight_sum")]
     pub valid_weight_sum: u64,
+    #[serde(alias = "valid_votes")]
     pub valid_votes: Vec<Vec<VoteView>>,
+    #[serde(alias = "candidate_votes")]
     pub candidate_votes: Vec<u64>,
     pub result: Option<u32>,
 }
 
+impl VoteResults {
+    /// 各候选项占有效票权重的比例，无有效票时全为 0
+    pub fn candidate_ratios(&self) -> Vec<f64> {
+        if self.valid_weight_sum == 0 {
+            return vec![0.0; self.candidate_votes.len()];
+        }
+        self.candidate_votes
+            .iter()
+            .map(|w| *w as f64 / self.valid_weight_sum as f64)
+            .collect()
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
 pub struct VoteView {
     pub author: Value,
+    #[serde(alias = "ckb_addr")]
     pub ckb_addr: String,
     pub weight: u64,
+    #[serde(alias = "vote_index")]
     pub vote_index: usize,
 }
 
@@ -231,3 +306,57 @@ pub enum VoteResult {
     AgainstMoreThan67PCT,
     Failed,
 }
+
+impl TryFrom<u32> for VoteResult {
+    type Error = u32;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0 => VoteResult::Voting,
+            1 => VoteResult::Agree,
+            2 => VoteResult::AgreeLessThan51PCT,
+            3 => VoteResult::AgreeLessThan67PCT,
+            4 => VoteResult::TotalLessThan185000000CKB,
+            5 => VoteResult::TotalLessThan3X,
+            6 => VoteResult::AgainstMoreThan51PCT,
+            7 => VoteResult::AgainstMoreThan67PCT,
+            8 => VoteResult::Failed,
+            _ => return Err(value),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vote_result_round_trips() {
+        for value in 0..=8 {
+            let result = VoteResult::try_from(value).unwrap();
+            assert_eq!(result as u32, value);
+        }
+        assert_eq!(VoteResult::try_from(9), Err(9));
+    }
+
+    #[test]
+    fn known_states_round_trip() {
+        for state in [
+            VoteMetaState::Waiting,
+            VoteMetaState::Committed,
+            VoteMetaState::Timeout,
+            VoteMetaState::Rejected,
+            VoteMetaState::Finished,
+            VoteMetaState::Changed,
+        ] {
+            assert_eq!(VoteMetaState::from(state as i32), state);
+        }
+    }
+
+    #[test]
+    fn out_of_range_states_are_unknown() {
+        for value in [-1, 6, 100, i32::MIN, i32::MAX] {
+            assert_eq!(VoteMetaState::from(value), VoteMetaState::Unknown);
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use chrono::{DateTime, Local};
-use color_eyre::Result;
+use color_eyre::{Result, eyre::eyre};
 use sea_query::{ColumnDef, ColumnType, Expr, Iden, OnConflict, PostgresQueryBuilder};
 use sea_query_sqlx::SqlxBinder;
 use serde::Serialize;
@@ -35,6 +35,7 @@ impl VoterList {
         Ok(())
     }
 
+    /// 名单为不可变快照，同一 id 只能写入一次
     pub async fn insert(
         db: &Pool<Postgres>,
         id: &str,
@@ -58,15 +59,14 @@ impl VoterList {
                 block_number.into(),
                 Expr::current_timestamp(),
             ])?
-            .returning_col(Self::Id)
-            .on_conflict(
-                OnConflict::column(Self::Id)
-                    .update_columns([Self::List, Self::RootHash, Self::BlockNumber, Self::Created])
-                    .to_owned(),
-            )
+            .on_conflict(OnConflict::column(Self::Id).do_nothing().to_owned())
             .build_sqlx(PostgresQueryBuilder);
 
-        db.execute(query_with(&sql, values)).await?;
+        // 投票引用名单 id 后名单与证明不可再变，已存在时拒绝覆盖
+        let lines = db.execute(query_with(&sql, values)).await?.rows_affected();
+        if lines == 0 {
+            return Err(eyre!("voter list {id} already exists"));
+        }
         Ok(())
     }
 
@@ -85,6 +85,7 @@ impl VoterList {
 }
 
 #[derive(sqlx::FromRow, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct VoterListRow {
     pub id: String,
     pub list: Vec<String>,
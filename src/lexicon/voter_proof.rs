@@ -0,0 +1,71 @@
+use color_eyre::Result;
+use sea_query::{ColumnDef, Expr, ExprTrait, Iden, Index, OnConflict, PostgresQueryBuilder};
+use sea_query_sqlx::SqlxBinder;
+use sqlx::{Executor, Pool, Postgres, query, query_as_with, query_with};
+
+/// 构建投票名单时预先算好的 SMT 证明，重启后无需重建整棵树
+#[derive(Iden, Debug, Clone, Copy)]
+pub enum VoterProof {
+    Table,
+    VoterListId,
+    LockHash,
+    Proof,
+}
+
+/// 单条 insert 的行数上限，避免超出参数个数限制
+const INSERT_CHUNK_SIZE: usize = 1000;
+
+impl VoterProof {
+    pub async fn init(db: &Pool<Postgres>) -> Result<()> {
+        let sql = sea_query::Table::create()
+            .table(Self::Table)
+            .if_not_exists()
+            .col(ColumnDef::new(Self::VoterListId).string().not_null())
+            .col(ColumnDef::new(Self::LockHash).string().not_null())
+            .col(ColumnDef::new(Self::Proof).string().not_null())
+            .primary_key(Index::create().col(Self::VoterListId).col(Self::LockHash))
+            .build(PostgresQueryBuilder);
+        db.execute(query(&sql)).await?;
+        Ok(())
+    }
+
+    /// proofs: (lock_hash, compiled proof)，均为 hex
+    pub async fn insert_all(
+        db: &Pool<Postgres>,
+        voter_list_id: &str,
+        proofs: &[(String, String)],
+    ) -> Result<()> {
+        for chunk in proofs.chunks(INSERT_CHUNK_SIZE) {
+            let mut insert = sea_query::Query::insert();
+            insert
+                .into_table(Self::Table)
+                .columns([Self::VoterListId, Self::LockHash, Self::Proof])
+                .on_conflict(
+                    OnConflict::columns([Self::VoterListId, Self::LockHash])
+                        .do_nothing()
+                        .to_owned(),
+                );
+            for (lock_hash, proof) in chunk {
+                insert.values([voter_list_id.into(), lock_hash.into(), proof.into()])?;
+            }
+            let (sql, values) = insert.build_sqlx(PostgresQueryBuilder);
+            db.execute(query_with(&sql, values)).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn get<'c>(
+        db: impl Executor<'c, Database = Postgres>,
+        voter_list_id: &str,
+        lock_hash: &str,
+    ) -> Result<Option<String>> {
+        let (sql, values) = sea_query::Query::select()
+            .column(Self::Proof)
+            .from(Self::Table)
+            .and_where(Expr::col(Self::VoterListId).eq(voter_list_id))
+            .and_where(Expr::col(Self::LockHash).eq(lock_hash))
+            .build_sqlx(PostgresQueryBuilder);
+        let row: Option<(String,)> = query_as_with(&sql, values).fetch_optional(db).await?;
+        Ok(row.map(|(proof,)| proof))
+    }
+}
@@ -1,4 +1,4 @@
-use std::str::FromStr;
+use std::{str::FromStr, time::Duration};
 
 use ckb_sdk::{Address, AddressPayload, AddressType, CodeHashIndex, NetworkType, OldAddress};
 use ckb_types::{H256, prelude::Unpack};
@@ -6,8 +6,10 @@ use color_eyre::{Result, eyre::eyre};
 
 pub mod api;
 pub mod atproto;
+pub mod cache;
 pub mod ckb;
 pub mod error;
+pub mod governance;
 pub mod indexer_bind;
 pub mod indexer_dao;
 pub mod indexer_did;
@@ -18,11 +20,22 @@ pub mod relayer;
 pub mod scheduler;
 pub mod smt;
 pub mod tid;
+pub mod webhook;
 
 #[macro_use]
 extern crate tracing as logger;
 
-use crate::relayer::subscription::LastSeq;
+use crate::{
+    api::stream::ProposalEvents,
+    cache::{Cooldown, TtlCache},
+    lexicon::{
+        notification::{Notification, NotificationType},
+        proposal::Proposal,
+        task::{Task, TaskRow, TaskType},
+    },
+    relayer::subscription::LastSeq,
+    webhook::{Webhook, WebhookEvent},
+};
 
 #[derive(Clone)]
 pub struct AppView {
@@ -32,16 +45,121 @@ pub struct AppView {
     pub indexer_did_url: String,
     pub indexer_vote_url: String,
     pub indexer_dao_url: String,
+    /// PDS 请求超时，由 `--pds-timeout` 设置
+    pub pds_timeout: Duration,
+    /// indexer 请求超时，由 `--indexer-timeout` 设置
+    pub indexer_timeout: Duration,
     pub ckb_client: ckb_sdk::CkbRpcAsyncClient,
     pub ckb_net: ckb_sdk::NetworkType,
+    /// 交易上链后还需等待的区块数，0 表示上链即确认
+    pub tx_confirmations: u64,
+    /// 投票元数据交易状态一直为 Unknown 时，超过该时长标记为 Timeout
+    pub vote_meta_tx_timeout: chrono::Duration,
+    /// 投票交易状态一直为 Unknown 时，超过该时长标记为 Timeout
+    pub vote_tx_timeout: chrono::Duration,
     pub build_voter_list_interval: u64,
+    pub build_voter_list_concurrency: usize,
+    /// 构建投票名单任务的调度周期，由 `--build-voter-list-cron` 推算
+    pub build_voter_list_period: chrono::Duration,
     pub last_seq: LastSeq,
+    /// did → 作者信息（profile + ckb 地址）
+    pub author_cache: TtlCache<serde_json::Value>,
+    /// did → ckb 地址
+    pub ckb_addr_cache: TtlCache<String>,
+    /// 查询 ckb 地址失败后，作者信息在冷却期内不再补充 ckbAddr
+    pub ckb_addr_cooldown: Cooldown,
+    /// 拨款后为每个里程碑创建的后续任务
+    pub fund_follow_up_tasks: Vec<TaskType>,
+    pub proposal_events: ProposalEvents,
+    /// 计票门槛与通过比例
+    pub governance: governance::GovernanceParams,
+    /// 未配置 `--webhook-url` 时为 `None`
+    pub webhook: Option<Webhook>,
 }
 
 impl AppView {
     pub fn get_last_seq(&self) -> i64 {
         self.last_seq.load(std::sync::atomic::Ordering::SeqCst)
     }
+
+    /// 推送 webhook 事件；写入在事务中时须在提交后调用
+    pub fn notify_webhook(&self, event: WebhookEvent) {
+        if let Some(webhook) = &self.webhook {
+            webhook.notify(event);
+        }
+    }
+
+    /// 任务已写入（所在事务已提交）后调用
+    pub async fn task_created(&self, id: i32, task: &TaskRow) {
+        self.notify_webhook(WebhookEvent::TaskCreated {
+            id,
+            task_type: task.task_type,
+            target: task.target.clone(),
+            operators: task.operators.clone(),
+        });
+        self.notify_task_assigned(id, task.task_type, &task.target, &task.operators)
+            .await;
+    }
+
+    /// 为每个 operator 写入被指派任务的通知，失败只记录日志
+    pub async fn notify_task_assigned(
+        &self,
+        id: i32,
+        task_type: i32,
+        target: &str,
+        operators: &[String],
+    ) {
+        for operator in operators {
+            Notification::insert(
+                &self.db,
+                operator,
+                NotificationType::TaskAssigned,
+                serde_json::json!({
+                    "taskId": id,
+                    "taskType": task_type,
+                    "target": target,
+                }),
+            )
+            .await
+            .map_err(|e| error!("insert notification failed: {e}"))
+            .ok();
+        }
+    }
+
+    /// 提案状态已更新（所在事务已提交）后调用
+    pub fn proposal_state_changed(&self, uri: &str, state: i32) {
+        self.notify_webhook(WebhookEvent::ProposalStateChanged {
+            uri: uri.to_string(),
+            state,
+        });
+    }
+
+    /// 不在事务中写入任务，成功后推送事件
+    pub async fn insert_task(&self, task: &TaskRow) -> Result<i32> {
+        let id = Task::insert(&self.db, task).await?;
+        self.task_created(id, task).await;
+        Ok(id)
+    }
+
+    /// 不在事务中更新提案状态，有更新时推送事件
+    pub async fn update_proposal_state(&self, uri: &str, state: i32) -> Result<u64> {
+        let lines = Proposal::update_state(&self.db, uri, state).await?;
+        if lines > 0 {
+            self.proposal_state_changed(uri, state);
+        }
+        Ok(lines)
+    }
+
+    /// 按 did 查询 ckb 地址，结果按 TTL 缓存
+    pub async fn ckb_addr_by_did(&self, did: &str) -> Result<String> {
+        let did = ckb::strip_did_prefix(did);
+        if let Some(ckb_addr) = self.ckb_addr_cache.get(did) {
+            return Ok(ckb_addr);
+        }
+        let ckb_addr = ckb::get_ckb_addr_by_did(&self.ckb_client, &self.ckb_net, did).await?;
+        self.ckb_addr_cache.insert(did, ckb_addr.clone());
+        Ok(ckb_addr)
+    }
 }
 
 pub enum AddressPayloadOption {
@@ -117,34 +235,123 @@ impl AddressParser {
     }
 }
 
+/// 地址解析失败的原因，调用方可据此区分网络不符与格式错误
+#[derive(Debug)]
+pub enum AddressParseError {
+    InvalidNetwork {
+        found: NetworkType,
+        expected: NetworkType,
+    },
+    InvalidCodeHashIndex {
+        found: CodeHashIndex,
+        expected: CodeHashIndex,
+    },
+    UnexpectedType {
+        found: AddressType,
+        expected: Option<AddressType>,
+    },
+    InvalidCodeHash {
+        found: H256,
+        expected: H256,
+    },
+    InvalidPrefix(String),
+    OldAddressFailed(String),
+}
+
+impl AddressParseError {
+    /// 返回给前端的稳定错误码
+    pub const fn code(&self) -> &'static str {
+        match self {
+            Self::InvalidNetwork { .. } => "InvalidNetwork",
+            Self::InvalidCodeHashIndex { .. } => "InvalidCodeHashIndex",
+            Self::UnexpectedType { .. } => "UnexpectedType",
+            Self::InvalidCodeHash { .. } => "InvalidCodeHash",
+            Self::InvalidPrefix(_) => "InvalidPrefix",
+            Self::OldAddressFailed(_) => "OldAddressFailed",
+        }
+    }
+}
+
+impl std::fmt::Display for AddressParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidNetwork { found, expected } => write!(
+                f,
+                "Invalid network: {}, expected: {}",
+                found.to_prefix(),
+                expected.to_prefix()
+            ),
+            Self::InvalidCodeHashIndex { found, expected } => write!(
+                f,
+                "Invalid address code hash index: {found:?}, expected: {expected:?}"
+            ),
+            Self::UnexpectedType {
+                found,
+                expected: Some(expected),
+            } => write!(
+                f,
+                "Unexpected address type: {found:?}, expected: {expected:?}"
+            ),
+            Self::UnexpectedType {
+                found,
+                expected: None,
+            } => write!(f, "Unexpected address type: {found:?}"),
+            Self::InvalidCodeHash { found, expected } => {
+                write!(f, "Invalid code hash: {found:#x}, expected: {expected:#x}")
+            }
+            Self::InvalidPrefix(prefix) => write!(f, "Invalid address prefix: {prefix}"),
+            Self::OldAddressFailed(e) => write!(f, "Invalid old format address: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for AddressParseError {}
+
 impl AddressParser {
-    fn parse(&self, input: &str) -> Result<Address, String> {
+    fn parse(&self, input: &str) -> Result<Address, AddressParseError> {
         fn check_code_hash(
             payload: &AddressPayload,
             code_hash_opt: Option<&H256>,
-        ) -> Result<(), String> {
+        ) -> Result<(), AddressParseError> {
             if let Some(code_hash) = code_hash_opt {
                 let payload_code_hash: H256 = payload.code_hash(None).unpack();
                 if code_hash != &payload_code_hash {
-                    return Err(format!(
-                        "Invalid code hash: {:#x}, expected: {:#x}",
-                        payload_code_hash, code_hash
-                    ));
+                    return Err(AddressParseError::InvalidCodeHash {
+                        found: payload_code_hash,
+                        expected: code_hash.clone(),
+                    });
                 }
             }
             Ok(())
         }
 
-        if let Ok(address) = Address::from_str(input) {
-            if let Some(network) = self.network
-                && address.network().to_prefix() != network.to_prefix()
+        fn check_type(
+            payload: &AddressPayload,
+            expected: AddressType,
+        ) -> Result<(), AddressParseError> {
+            if payload.ty(true) != expected {
+                return Err(AddressParseError::UnexpectedType {
+                    found: payload.ty(true),
+                    expected: Some(expected),
+                });
+            }
+            Ok(())
+        }
+
+        fn check_network(
+            found: NetworkType,
+            expected: Option<NetworkType>,
+        ) -> Result<(), AddressParseError> {
+            if let Some(expected) = expected
+                && found.to_prefix() != expected.to_prefix()
             {
-                return Err(format!(
-                    "Invalid network: {}, expected: {}",
-                    address.network().to_prefix(),
-                    network.to_prefix(),
-                ));
+                return Err(AddressParseError::InvalidNetwork { found, expected });
             }
+            Ok(())
+        }
+
+        if let Ok(address) = Address::from_str(input) {
+            check_network(address.network(), self.network)?;
             if let Some(payload_option) = self.payload.as_ref() {
                 let payload = address.payload();
                 match payload_option {
@@ -153,47 +360,29 @@ impl AddressParser {
                             if let Some(expected_index) = index_opt
                                 && index != expected_index
                             {
-                                return Err(format!(
-                                    "Invalid address code hash index: {:?}, expected: {:?}",
-                                    index, expected_index,
-                                ));
+                                return Err(AddressParseError::InvalidCodeHashIndex {
+                                    found: *index,
+                                    expected: *expected_index,
+                                });
                             }
                         }
-                        _ => {
-                            return Err(format!(
-                                "Invalid address type: {:?}, expected: {:?}",
-                                payload.ty(true),
-                                AddressType::Short,
-                            ));
-                        }
+                        _ => check_type(payload, AddressType::Short)?,
                     },
                     AddressPayloadOption::Full(code_hash_opt) => {
                         if payload.ty(true) == AddressType::Short {
-                            return Err(format!(
-                                "Unexpected address type: {:?}",
-                                AddressType::Short
-                            ));
+                            return Err(AddressParseError::UnexpectedType {
+                                found: AddressType::Short,
+                                expected: None,
+                            });
                         }
                         check_code_hash(payload, code_hash_opt.as_ref())?;
                     }
                     AddressPayloadOption::FullData(code_hash_opt) => {
-                        if payload.ty(true) != AddressType::FullData {
-                            return Err(format!(
-                                "Unexpected address type: {:?}, expected: {:?}",
-                                payload.ty(true),
-                                AddressType::FullData
-                            ));
-                        }
+                        check_type(payload, AddressType::FullData)?;
                         check_code_hash(payload, code_hash_opt.as_ref())?;
                     }
                     AddressPayloadOption::FullType(code_hash_opt) => {
-                        if payload.ty(true) != AddressType::FullType {
-                            return Err(format!(
-                                "Unexpected address type: {:?}, expected: {:?}",
-                                payload.ty(true),
-                                AddressType::FullType
-                            ));
-                        }
+                        check_type(payload, AddressType::FullType)?;
                         check_code_hash(payload, code_hash_opt.as_ref())?;
                     }
                 }
@@ -201,17 +390,15 @@ impl AddressParser {
             return Ok(address);
         }
 
-        // Fallback to old format address (TODO: move this logic to upper level)
+        // Fallback to old format address, keeping the network from its prefix
         let prefix = input.chars().take(3).collect::<String>();
         let network = NetworkType::from_prefix(prefix.as_str())
-            .ok_or_else(|| format!("Invalid address prefix: {}", prefix))?;
-        let old_address = OldAddress::from_input(network, input)?;
+            .ok_or(AddressParseError::InvalidPrefix(prefix))?;
+        check_network(network, self.network)?;
+        let old_address =
+            OldAddress::from_input(network, input).map_err(AddressParseError::OldAddressFailed)?;
         let payload = AddressPayload::from_pubkey_hash(old_address.hash().clone());
-        Ok(Address::new(
-            self.network.ok_or("Missing network")?,
-            payload,
-            true,
-        ))
+        Ok(Address::new(network, payload, true))
     }
 }
 
@@ -220,3 +407,48 @@ pub async fn get_network_type(rpc_client: &ckb_sdk::CkbRpcAsyncClient) -> Result
     NetworkType::from_raw_str(chain_info.chain.as_str())
         .ok_or_else(|| eyre!("Unsupported network type: {}", chain_info.chain))
 }
+
+#[cfg(test)]
+mod tests {
+    use ckb_types::H160;
+
+    use super::*;
+
+    fn old_address(network: NetworkType, hash: &H160) -> String {
+        OldAddress::new_default(hash.clone()).display_with_prefix(network)
+    }
+
+    #[test]
+    fn old_mainnet_address_round_trips_to_mainnet() {
+        let hash = H160([7u8; 20]);
+        let input = old_address(NetworkType::Mainnet, &hash);
+        assert!(input.starts_with("ckb1"));
+        let address = AddressParser::new_sighash().parse(&input).unwrap();
+        assert_eq!(address.network(), NetworkType::Mainnet);
+        assert_eq!(
+            address.to_string(),
+            Address::new(
+                NetworkType::Mainnet,
+                AddressPayload::from_pubkey_hash(hash),
+                true
+            )
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn old_address_of_other_network_is_rejected() {
+        let input = old_address(NetworkType::Mainnet, &H160([7u8; 20]));
+        let err = AddressParser::new_sighash()
+            .set_network(NetworkType::Testnet)
+            .parse(&input)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            AddressParseError::InvalidNetwork {
+                found: NetworkType::Mainnet,
+                expected: NetworkType::Testnet,
+            }
+        ));
+    }
+}
@@ -1,27 +1,39 @@
 #[macro_use]
 extern crate tracing as logger;
 
+use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::time::Duration;
 
 use ckb_sdk::CkbRpcAsyncClient;
 use clap::Parser;
 use color_eyre::{Result, eyre::eyre};
+use common_x::restful::axum::http::{
+    HeaderValue, Method,
+    header::{AUTHORIZATION, CONTENT_TYPE},
+};
 use common_x::restful::axum::routing::get;
-use common_x::restful::axum::{Router, routing::post};
+use common_x::restful::axum::{self, Router, middleware, routing::post};
 use dao::api::ApiDoc;
+use dao::cache::{Cooldown, TtlCache};
+use dao::governance::GovernanceParams;
 use dao::lexicon::administrator::Administrator;
 use dao::lexicon::cursor_state::CursorState;
 use dao::lexicon::meeting::Meeting;
+use dao::lexicon::notification::Notification;
 use dao::lexicon::profile::Profile;
-use dao::lexicon::task::Task;
+use dao::lexicon::task::{Task, TaskType};
 use dao::lexicon::timeline::Timeline;
 use dao::lexicon::vote::Vote;
 use dao::lexicon::vote_meta::VoteMeta;
 use dao::lexicon::voter_list::VoterList;
+use dao::lexicon::voter_proof::VoterProof;
 use dao::relayer::subscription::{create_last_seq, run_with_reconnect};
+use dao::scheduler::{JobName, SchedulerConfig};
+use dao::webhook::Webhook;
 use dao::{AppView, api, scheduler};
 use sqlx::postgres::PgPoolOptions;
-use tower_http::cors::CorsLayer;
+use tower_http::cors::{AllowOrigin, CorsLayer};
 use tower_http::limit::RequestBodyLimitLayer;
 use tower_http::timeout::TimeoutLayer;
 
@@ -31,6 +43,9 @@ use dao::lexicon::reply::Reply;
 use utoipa::OpenApi;
 use utoipa_scalar::{Scalar, Servable};
 
+/// 收到退出信号后等待在途请求的最长时间
+const SHUTDOWN_DRAIN_TIMEOUT: Duration = Duration::from_secs(20);
+
 #[derive(Parser, Debug, Clone)]
 #[command(author, version)]
 pub struct Args {
@@ -60,16 +75,123 @@ pub struct Args {
     ckb_net: String,
     #[clap(long, default_value = "10000")]
     build_voter_list_interval: u64,
+    /// 构建投票名单时同时查询权重的地址数
+    #[clap(long, default_value = "8")]
+    build_voter_list_concurrency: usize,
+    /// 过渡期兼容旧客户端：响应字段使用 snake_case
+    #[clap(long, default_value = "false")]
+    api_snake_case: bool,
+    /// 作者信息缓存时间（秒），0 表示不缓存
+    #[clap(long, default_value = "300")]
+    author_cache_ttl: u64,
+    /// did 对应 ckb 地址的缓存时间（秒），0 表示不缓存
+    #[clap(long, default_value = "300")]
+    ckb_addr_cache_ttl: u64,
+    /// 查询 ckb 地址失败后暂停作者 ckbAddr 补充的时间（秒），0 表示不暂停
+    #[clap(long, default_value = "30")]
+    ckb_addr_cooldown: u64,
+    /// 拨款后为每个里程碑创建的后续任务，逗号分隔
+    #[clap(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_value = "submit-milestone-report,submit-delay-report"
+    )]
+    fund_follow_up_tasks: Vec<TaskType>,
+    /// 计票门槛与通过比例配置文件（JSON），未配置时使用默认值
+    #[clap(long)]
+    governance_config: Option<PathBuf>,
+    /// 弃权票是否计入投票门槛（总票数），覆盖配置文件
+    #[clap(long, action = clap::ArgAction::Set)]
+    abstain_counts_for_quorum: Option<bool>,
+    /// 任务创建、任务逾期、提案状态变化时推送的 webhook 地址
+    #[clap(long)]
+    webhook_url: Option<String>,
+    /// webhook 请求体的 HMAC-SHA256 签名密钥
+    #[clap(long)]
+    webhook_secret: Option<String>,
+    #[clap(long, default_value = "0 * * * * *")]
+    build_voter_list_cron: String,
+    #[clap(long, default_value = "1/10 * * * * *")]
+    vote_meta_tx_cron: String,
+    #[clap(long, default_value = "1/15 * * * * *")]
+    vote_tx_cron: String,
+    #[clap(long, default_value = "0 * * * * *")]
+    vote_finished_cron: String,
+    #[clap(long, default_value = "0 30 3 * * *")]
+    prune_orphans_cron: String,
+    #[clap(long, default_value = "0 */5 * * * *")]
+    task_overdue_cron: String,
+    /// 关闭指定定时任务，可重复
+    #[clap(long, value_enum)]
+    disable_job: Vec<JobName>,
+    /// 请求超时（秒）
+    #[clap(long, default_value = "10")]
+    request_timeout: u64,
+    /// 轻量只读接口（/api/repo/profile、/api/repo/activity）的请求超时（秒）
+    #[clap(long, default_value = "5")]
+    fast_request_timeout: u64,
+    /// 需要多次调用 CKB / indexer / PDS 的接口（/api/vote/weight、/api/admin/reindex）的请求超时（秒）
+    #[clap(long, default_value = "30")]
+    slow_request_timeout: u64,
+    /// 允许跨域访问的来源，可重复或逗号分隔；未配置时拒绝跨域请求
+    #[clap(long, value_delimiter = ',')]
+    cors_origin: Vec<String>,
+    /// PDS 请求超时（秒）
+    #[clap(long, default_value = "5")]
+    pds_timeout: u64,
+    /// indexer 请求超时（秒）
+    #[clap(long, default_value = "5")]
+    indexer_timeout: u64,
+    /// 投票交易上链后需等待的确认区块数，0 表示上链即确认
+    #[clap(long, default_value = "0")]
+    tx_confirmations: u64,
+    /// 投票元数据交易状态为 Unknown / 未找到时，超过该分钟数标记为超时
+    #[clap(long, default_value = "3")]
+    vote_meta_tx_timeout: u32,
+    /// 投票交易状态为 Unknown / 未找到时，超过该分钟数标记为超时
+    #[clap(long, default_value = "3")]
+    vote_tx_timeout: u32,
+    /// 请求体大小上限（字节），超出返回 413
+    #[clap(long, default_value = "1048576")]
+    max_body_size: usize,
+    /// 发起 / 提交投票接口每个客户端 IP 每分钟的请求上限，0 表示不限
+    #[clap(long, default_value = "10")]
+    vote_rate_limit: u32,
+    /// 其他签名写接口每个客户端 IP 每分钟的请求上限，0 表示不限
+    #[clap(long, default_value = "30")]
+    write_rate_limit: u32,
+    /// 部署在反向代理之后时开启，限流按 X-Forwarded-For 最右一项识别客户端 IP；
+    /// 直接对外暴露时不要开启，否则客户端可伪造该头绕过限流
+    #[clap(long, default_value = "false")]
+    trusted_proxy: bool,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let args = Args::parse();
+    if args.fund_follow_up_tasks.is_empty()
+        || !args
+            .fund_follow_up_tasks
+            .iter()
+            .all(|t| t.is_fund_follow_up())
+    {
+        return Err(eyre!(
+            "fund_follow_up_tasks must be a non-empty subset of submit-milestone-report, submit-delay-report"
+        ));
+    }
+    // 重复配置的任务类型只创建一次
+    let mut fund_follow_up_tasks: Vec<TaskType> = vec![];
+    for task_type in &args.fund_follow_up_tasks {
+        if !fund_follow_up_tasks.contains(task_type) {
+            fund_follow_up_tasks.push(*task_type);
+        }
+    }
 
     common_x::log::init_log_filter(&args.log_filter);
     info!(
-        "args: port={}, ckb_net={}, apidoc={}, build_voter_list_interval={}",
-        args.port, args.ckb_net, args.apidoc, args.build_voter_list_interval
+        "args: port={}, ckb_net={}, apidoc={}, build_voter_list_interval={}, api_snake_case={}",
+        args.port, args.ckb_net, args.apidoc, args.build_voter_list_interval, args.api_snake_case
     );
     let db = PgPoolOptions::new()
         .max_connections(5)
@@ -82,18 +204,55 @@ async fn main() -> Result<()> {
     Like::init(&db).await?;
     Profile::init(&db).await?;
     VoterList::init(&db).await?;
+    VoterProof::init(&db).await?;
     Administrator::init(&db).await?;
     VoteMeta::init(&db).await?;
     Vote::init(&db).await?;
     Timeline::init(&db).await?;
     Task::init(&db).await?;
     Meeting::init(&db).await?;
+    Notification::init(&db).await?;
     CursorState::init(&db).await?;
 
+    let webhook = args.webhook_url.clone().map(|url| {
+        info!("webhook enabled: {url}");
+        Webhook::new(url, args.webhook_secret.clone())
+    });
+
     let initial_seq = CursorState::get_seq(&db, "relayer").await.unwrap_or(0);
     info!("Resume relayer from seq: {}", initial_seq);
 
     let ckb_client = CkbRpcAsyncClient::new(&args.ckb_url);
+    let ckb_net = match args.ckb_net.to_lowercase().as_str() {
+        "mainnet" => ckb_sdk::NetworkType::Mainnet,
+        "testnet" => ckb_sdk::NetworkType::Testnet,
+        "dev" => ckb_sdk::NetworkType::Dev,
+        _ => {
+            return Err(eyre!(
+                "unsupported --ckb-net {}, expected Mainnet, Testnet or Dev",
+                args.ckb_net
+            ));
+        }
+    };
+    // 合约 code hash 按 ckb_net 选择，节点网络不一致时所有链上查询都会落空
+    let node_net = dao::get_network_type(&ckb_client)
+        .await
+        .map_err(|e| eyre!("query network of ckb node {} failed: {e}", args.ckb_url))?;
+    if node_net != ckb_net {
+        return Err(eyre!(
+            "ckb node {} is on {node_net:?}, but --ckb-net is {ckb_net:?}",
+            args.ckb_url,
+        ));
+    }
+
+    let mut governance = match &args.governance_config {
+        Some(path) => GovernanceParams::load(path)?,
+        None => GovernanceParams::default(),
+    };
+    if let Some(abstain_counts_for_quorum) = args.abstain_counts_for_quorum {
+        governance.abstain_counts_for_quorum = abstain_counts_for_quorum;
+    }
+    info!("governance params: {:?}", governance);
 
     let app = AppView {
         db,
@@ -102,18 +261,31 @@ async fn main() -> Result<()> {
         indexer_did_url: args.indexer_did_url.clone(),
         indexer_vote_url: args.indexer_vote_url.clone(),
         indexer_dao_url: args.indexer_dao_url.clone(),
+        pds_timeout: Duration::from_secs(args.pds_timeout),
+        indexer_timeout: Duration::from_secs(args.indexer_timeout),
         ckb_client,
-        ckb_net: match args.ckb_net.to_lowercase().as_str() {
-            "mainnet" => ckb_sdk::NetworkType::Mainnet,
-            "testnet" => ckb_sdk::NetworkType::Testnet,
-            "dev" => ckb_sdk::NetworkType::Dev,
-            _ => {
-                error!("Invalid ckb_net, default to Testnet");
-                ckb_sdk::NetworkType::Testnet
-            }
-        },
+        ckb_net,
+        tx_confirmations: args.tx_confirmations,
+        vote_meta_tx_timeout: chrono::Duration::minutes(args.vote_meta_tx_timeout.into()),
+        vote_tx_timeout: chrono::Duration::minutes(args.vote_tx_timeout.into()),
         build_voter_list_interval: args.build_voter_list_interval,
+        build_voter_list_concurrency: args.build_voter_list_concurrency.max(1),
+        build_voter_list_period: scheduler::cron_period(&args.build_voter_list_cron).map_err(
+            |e| {
+                eyre!(
+                    "invalid cron \"{}\" for build voter list: {e}",
+                    args.build_voter_list_cron
+                )
+            },
+        )?,
         last_seq: create_last_seq(initial_seq),
+        author_cache: TtlCache::new(Duration::from_secs(args.author_cache_ttl)),
+        ckb_addr_cache: TtlCache::new(Duration::from_secs(args.ckb_addr_cache_ttl)),
+        ckb_addr_cooldown: Cooldown::new(Duration::from_secs(args.ckb_addr_cooldown)),
+        fund_follow_up_tasks,
+        proposal_events: api::stream::create_proposal_events(),
+        governance,
+        webhook,
     };
 
     let app_ = app.clone();
@@ -122,87 +294,242 @@ async fn main() -> Result<()> {
         run_with_reconnect(relayer, app_).await;
     });
 
-    scheduler::init_task_scheduler(&app).await?;
+    let mut scheduler = scheduler::init_task_scheduler(
+        &app,
+        &SchedulerConfig {
+            build_voter_list_cron: args.build_voter_list_cron.clone(),
+            vote_meta_tx_cron: args.vote_meta_tx_cron.clone(),
+            vote_tx_cron: args.vote_tx_cron.clone(),
+            vote_finished_cron: args.vote_finished_cron.clone(),
+            prune_orphans_cron: args.prune_orphans_cron.clone(),
+            task_overdue_cron: args.task_overdue_cron.clone(),
+            disabled_jobs: args.disable_job.clone(),
+        },
+    )
+    .await?;
 
     let router = if args.apidoc {
         Router::new()
             // openapi docs
             .merge(Scalar::with_url("/apidoc", ApiDoc::openapi()))
+            // dev only: lets clients compare their signing encoding
+            .route(
+                "/api/debug/unsigned_bytes",
+                post(api::debug::unsigned_bytes),
+            )
     } else {
         Router::new()
     };
     let router = router
         // api routes
-        .route("/api/repo/profile", get(api::repo::profile))
         .route("/api/proposal/list", post(api::proposal::list))
+        .route("/api/proposal/batch", post(api::proposal::batch))
         .route("/api/proposal/detail", get(api::proposal::detail))
-        .route(
-            "/api/proposal/initiation_vote",
-            post(api::proposal::initiation_vote),
-        )
-        .route(
-            "/api/proposal/update_receiver_addr",
-            post(api::proposal::update_receiver_addr),
-        )
+        .route("/api/proposal/reports", get(api::proposal::reports))
+        .route("/api/proposal/votes", get(api::proposal::votes))
+        .route("/api/proposal/progress", get(api::proposal::progress))
         .route(
             "/api/proposal/receiver_addr",
             get(api::proposal::receiver_addr),
         )
         .route("/api/proposal/status", get(api::proposal::status))
+        .route("/api/proposal/stats", get(api::proposal::stats))
         .route("/api/proposal/list_self", get(api::proposal::list_self))
         .route("/api/proposal/replied", get(api::proposal::replied))
         .route("/api/reply/list", post(api::reply::list))
         .route("/api/like/list", post(api::like::list))
         .route("/api/vote/bind_list", get(api::vote::bind_list))
-        .route("/api/vote/weight", get(api::vote::weight))
         .route("/api/vote/voter_list", get(api::vote::voter_list))
         .route("/api/vote/proof", get(api::vote::proof))
-        .route(
-            "/api/vote/update_meta_tx_hash",
-            post(api::vote::update_meta_tx_hash),
-        )
-        .route("/api/vote/prepare", post(api::vote::prepare))
-        .route(
-            "/api/vote/update_vote_tx_hash",
-            post(api::vote::update_vote_tx_hash),
-        )
         .route("/api/vote/status", post(api::vote::status))
         .route("/api/vote/detail", get(api::vote::detail))
+        .route("/api/vote/result", get(api::vote::result))
+        .route("/api/vote/in_voter_list", get(api::vote::in_voter_list))
         .route("/api/vote/list_self", get(api::vote::list_self))
         .route("/api/timeline", get(api::timeline::get))
         .route("/api/task", get(api::task::get))
-        .route("/api/task/send_funds", post(api::task::send_funds))
-        .route(
-            "/api/task/submit_milestone_report",
-            post(api::task::submit_milestone_report),
+        .route("/api/task/by_proposal", get(api::task::by_proposal))
+        .route("/api/meeting", get(api::meeting::get))
+        .route("/api/stream/proposal/{*uri}", get(api::stream::proposal))
+        .route("/ready/voter_list", get(api::ready::voter_list));
+    let timeout = |secs| {
+        TimeoutLayer::with_status_code(
+            reqwest::StatusCode::REQUEST_TIMEOUT,
+            Duration::from_secs(secs),
         )
-        .route(
-            "/api/task/submit_delay_report",
-            post(api::task::submit_delay_report),
+    };
+    let router = router
+        .layer(timeout(args.request_timeout))
+        // cheap reads
+        .merge(
+            Router::new()
+                .route("/api/repo/profile", get(api::repo::profile))
+                .route("/api/repo/activity", get(api::repo::activity))
+                .layer(timeout(args.fast_request_timeout)),
         )
-        .route(
-            "/api/task/submit_meeting_report",
-            post(api::task::submit_meeting_report),
+        // fans out to CKB / indexer calls
+        .merge(
+            Router::new()
+                .route("/api/vote/weight", get(api::vote::weight))
+                .layer(timeout(args.slow_request_timeout)),
         )
-        .route("/api/task/create_meeting", post(api::task::create_meeting))
-        .route(
-            "/api/task/submit_acceptance_report",
-            post(api::task::submit_acceptance_report),
+        // signed votes, rate limited per client IP
+        .merge(
+            Router::new()
+                .route(
+                    "/api/proposal/initiation_vote",
+                    post(api::proposal::initiation_vote),
+                )
+                .route(
+                    "/api/vote/update_meta_tx_hash",
+                    post(api::vote::update_meta_tx_hash),
+                )
+                .route("/api/vote/prepare", post(api::vote::prepare))
+                .route(
+                    "/api/vote/update_vote_tx_hash",
+                    post(api::vote::update_vote_tx_hash),
+                )
+                .route(
+                    "/api/task/rectification_vote",
+                    post(api::task::rectification_vote),
+                )
+                .layer(middleware::from_fn_with_state(
+                    api::RateLimit::new(args.vote_rate_limit, args.trusted_proxy),
+                    api::rate_limit,
+                ))
+                .layer(timeout(args.request_timeout)),
         )
-        .route(
-            "/api/task/rectification_vote",
-            post(api::task::rectification_vote),
+        // other signed writes, rate limited per client IP
+        .merge(
+            Router::new()
+                .route(
+                    "/api/proposal/update_receiver_addr",
+                    post(api::proposal::update_receiver_addr),
+                )
+                .route("/api/vote/finalize", post(api::vote::finalize))
+                .route("/api/task/send_funds", post(api::task::send_funds))
+                .route("/api/task/claim", post(api::task::claim))
+                .route("/api/task/reassign", post(api::task::reassign))
+                .route(
+                    "/api/task/submit_milestone_report",
+                    post(api::task::submit_milestone_report),
+                )
+                .route(
+                    "/api/task/submit_delay_report",
+                    post(api::task::submit_delay_report),
+                )
+                .route(
+                    "/api/task/submit_meeting_report",
+                    post(api::task::submit_meeting_report),
+                )
+                .route("/api/task/cancel_meeting", post(api::task::cancel_meeting))
+                .route("/api/task/create_meeting", post(api::task::create_meeting))
+                .route(
+                    "/api/task/submit_acceptance_report",
+                    post(api::task::submit_acceptance_report),
+                )
+                .route("/api/task/rectification", post(api::task::rectification))
+                .route("/api/notification/list", post(api::notification::list))
+                .route("/api/notification/read", post(api::notification::read))
+                .route("/api/vote/verify_root", post(api::vote::verify_root))
+                .route("/api/admin/list", post(api::admin::list))
+                .route("/api/admin/add", post(api::admin::add))
+                .route("/api/admin/remove", post(api::admin::remove))
+                .layer(middleware::from_fn_with_state(
+                    api::RateLimit::new(args.write_rate_limit, args.trusted_proxy),
+                    api::rate_limit,
+                ))
+                .layer(timeout(args.request_timeout)),
         )
-        .route("/api/task/rectification", post(api::task::rectification))
-        .route("/api/meeting", get(api::meeting::get))
-        .layer(RequestBodyLimitLayer::new(1024 * 1024))
-        .layer((TimeoutLayer::with_status_code(
-            reqwest::StatusCode::REQUEST_TIMEOUT,
-            Duration::from_secs(10),
-        ),))
-        .layer(CorsLayer::permissive())
+        // signed admin job that pages through a whole PDS repo
+        .merge(
+            Router::new()
+                .route("/api/admin/reindex", post(api::admin::reindex))
+                .layer(middleware::from_fn_with_state(
+                    api::RateLimit::new(args.write_rate_limit, args.trusted_proxy),
+                    api::rate_limit,
+                ))
+                .layer(timeout(args.slow_request_timeout)),
+        );
+    let router = if args.api_snake_case {
+        router.layer(middleware::map_response(api::snake_case_response))
+    } else {
+        router
+    };
+    let origins = args
+        .cors_origin
+        .iter()
+        .map(|origin| {
+            HeaderValue::from_str(origin).map_err(|e| eyre!("invalid cors origin {origin}: {e}"))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if origins.is_empty() {
+        warn!("no --cors-origin configured, cross-origin requests are denied");
+    }
+    let cors = CorsLayer::new()
+        .allow_origin(AllowOrigin::list(origins))
+        .allow_methods([Method::GET, Method::POST])
+        .allow_headers([CONTENT_TYPE, AUTHORIZATION, api::X_REQUEST_ID])
+        .expose_headers([api::X_REQUEST_ID]);
+
+    let db = app.db.clone();
+    let router = router
+        .layer(RequestBodyLimitLayer::new(args.max_body_size))
+        .layer(cors)
+        .layer(middleware::from_fn(api::request_id))
         .with_state(app);
-    common_x::restful::http_serve(args.port, router)
+    let listener = tokio::net::TcpListener::bind(("0.0.0.0", args.port)).await?;
+    info!("listening on {}", listener.local_addr()?);
+    // stop accepting on SIGTERM/SIGINT and wait for in-flight requests;
+    // long-lived SSE connections never finish on their own, so draining is capped
+    let (stopping_tx, stopping_rx) = tokio::sync::oneshot::channel();
+    let server = axum::serve(
+        listener,
+        router.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(async move {
+        shutdown_signal().await;
+        stopping_tx.send(()).ok();
+    });
+    tokio::select! {
+        res = server => res.map_err(|e| eyre!("{e}"))?,
+        _ = async {
+            stopping_rx.await.ok();
+            tokio::time::sleep(SHUTDOWN_DRAIN_TIMEOUT).await;
+        } => warn!("in-flight requests not drained in {SHUTDOWN_DRAIN_TIMEOUT:?}, closing"),
+    }
+
+    info!("http server stopped, shutting down scheduler");
+    scheduler
+        .shutdown()
         .await
-        .map_err(|e| eyre!("{e}"))
+        .map_err(|e| error!("scheduler shutdown failed: {e}"))
+        .ok();
+    db.close().await;
+    info!("shutdown complete");
+    Ok(())
+}
+
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .map_err(|e| error!("install ctrl-c handler failed: {e}"))
+            .ok();
+    };
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                error!("install SIGTERM handler failed: {e}");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    tokio::select! {
+        _ = ctrl_c => info!("received SIGINT"),
+        _ = terminate => info!("received SIGTERM"),
+    }
 }
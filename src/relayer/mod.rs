@@ -7,7 +7,7 @@ use serde_json::Value;
 
 use crate::{
     AppView,
-    atproto::{NSID_LIKE, NSID_PROFILE, NSID_PROPOSAL, NSID_REPLY},
+    atproto::{KNOWN_COLLECTIONS, NSID_LIKE, NSID_PROFILE, NSID_PROPOSAL, NSID_REPLY},
     lexicon::{
         administrator::Administrator,
         cursor_state::CursorState,
@@ -15,8 +15,10 @@ use crate::{
         profile::Profile,
         proposal::Proposal,
         reply::Reply,
-        task::{Task, TaskRow, TaskState, TaskType},
+        schema,
+        task::{TaskRow, TaskState, TaskType},
         timeline::{Timeline, TimelineRow, TimelineType},
+        vote_meta::VoteMetaState,
     },
     relayer::subscription::CommitHandler,
 };
@@ -47,6 +49,10 @@ impl CommitHandler for AppView {
             }
             let mut s = op.path.split('/');
             let collection = s.next().expect("op.path is empty");
+            if !KNOWN_COLLECTIONS.contains(&collection) {
+                debug!("skip record of unknown collection {collection}");
+                continue;
+            }
 
             let repo_str = commit.repo.as_str();
             let uri = format!("at://{}/{}", repo_str, op.path);
@@ -55,6 +61,10 @@ impl CommitHandler for AppView {
                 "create" | "update" => {
                     if let Ok(Some(record)) = repo.get_raw::<Value>(&op.path).await {
                         debug!("Record: {:?}", record);
+                        if let Err(e) = schema::validate_record(collection, &record) {
+                            warn!("skip invalid record {uri}: {e:?}");
+                            continue;
+                        }
                         let cid =
                             format!("{}", op.cid.clone().map(|cid| cid.0).unwrap_or_default());
                         match collection {
@@ -64,6 +74,7 @@ impl CommitHandler for AppView {
                                     .await
                                     .map_err(|e| error!("Profile::insert failed: {e}"))
                                     .ok();
+                                self.author_cache.invalidate(repo_str);
                             }
                             NSID_PROPOSAL => {
                                 info!("{} proposal", op.action);
@@ -71,44 +82,46 @@ impl CommitHandler for AppView {
                                     .await
                                     .map_err(|e| error!("Proposal::insert failed: {e}"))
                                     .ok();
-                                let admins = Administrator::fetch_all(&self.db)
-                                    .await
-                                    .iter()
-                                    .map(|admin| admin.did.clone())
-                                    .collect();
-                                Task::insert(
-                                    &self.db,
-                                    &TaskRow {
-                                        id: 0,
-                                        task_type: TaskType::CreateAMA as i32,
-                                        message: "CreateAMA".to_string(),
-                                        target: uri.to_string(),
-                                        operators: admins,
-                                        processor: None,
-                                        deadline: chrono::Local::now() + chrono::Duration::days(21),
-                                        state: TaskState::Unread as i32,
-                                        updated: chrono::Local::now(),
-                                        created: chrono::Local::now(),
-                                    },
-                                )
-                                .await
-                                .map_err(|e| error!("insert task failed: {e}"))
-                                .ok();
-                                Task::insert(
-                                    &self.db,
-                                    &TaskRow {
-                                        id: 0,
-                                        task_type: TaskType::InitiationVote as i32,
-                                        message: "InitiationVote".to_string(),
-                                        target: uri.to_string(),
-                                        operators: vec![repo_str.to_string()],
-                                        processor: None,
-                                        deadline: chrono::Local::now() + chrono::Duration::days(21),
-                                        state: TaskState::Unread as i32,
-                                        updated: chrono::Local::now(),
-                                        created: chrono::Local::now(),
-                                    },
-                                )
+                                match Administrator::fetch_all(&self.db).await {
+                                    Ok(admins) => {
+                                        self.insert_task(&TaskRow {
+                                            id: 0,
+                                            task_type: TaskType::CreateAMA as i32,
+                                            message: "CreateAMA".to_string(),
+                                            target: uri.to_string(),
+                                            operators: admins
+                                                .into_iter()
+                                                .map(|admin| admin.did)
+                                                .collect(),
+                                            processor: None,
+                                            deadline: chrono::Local::now()
+                                                + chrono::Duration::days(21),
+                                            state: TaskState::Unread as i32,
+                                            updated: chrono::Local::now(),
+                                            created: chrono::Local::now(),
+                                            overdue: false,
+                                        })
+                                        .await
+                                        .map_err(|e| error!("insert task failed: {e}"))
+                                        .ok();
+                                    }
+                                    Err(e) => {
+                                        error!("fetch administrators failed, skip CreateAMA: {e}")
+                                    }
+                                }
+                                self.insert_task(&TaskRow {
+                                    id: 0,
+                                    task_type: TaskType::InitiationVote as i32,
+                                    message: "InitiationVote".to_string(),
+                                    target: uri.to_string(),
+                                    operators: vec![repo_str.to_string()],
+                                    processor: None,
+                                    deadline: chrono::Local::now() + chrono::Duration::days(21),
+                                    state: TaskState::Unread as i32,
+                                    updated: chrono::Local::now(),
+                                    created: chrono::Local::now(),
+                                    overdue: false,
+                                })
                                 .await
                                 .map_err(|e| error!("insert task failed: {e}"))
                                 .ok();
@@ -149,6 +162,7 @@ impl CommitHandler for AppView {
                 }
                 "delete" => match collection {
                     NSID_PROFILE => {
+                        self.author_cache.invalidate(repo_str);
                         profile_to_delete.push(repo_str);
                         info!("Marked profile for deletion: {}", uri);
                     }
@@ -183,15 +197,29 @@ impl CommitHandler for AppView {
         }
 
         if !proposal_to_delete.is_empty() {
+            // 投票进行中的提案保留，链上投票仍需要对应的提案记录
+            let proposal_uris = proposal_to_delete.clone();
             let (sql, values) = sea_query::Query::delete()
                 .from_table(Proposal::Table)
                 .and_where(Expr::col(Proposal::Uri).is_in(proposal_to_delete))
+                .and_where(Expr::cust_with_values(
+                    "not exists (select 1 from \"vote_meta\" where \"vote_meta\".\"proposal_uri\" = \"proposal\".\"uri\" and \"vote_meta\".\"state\" in ($1, $2))",
+                    [VoteMetaState::Waiting as i32, VoteMetaState::Committed as i32],
+                ))
+                .returning_col(Proposal::Uri)
                 .build_sqlx(PostgresQueryBuilder);
-            sqlx::query_with(&sql, values)
-                .execute(&self.db)
+            match sqlx::query_as_with::<_, (String,), _>(&sql, values)
+                .fetch_all(&self.db)
                 .await
-                .map_err(|e| error!("sql execute failed: {e}"))
-                .ok();
+            {
+                Ok(deleted) => {
+                    let deleted: Vec<String> = deleted.into_iter().map(|(uri,)| uri).collect();
+                    for uri in proposal_uris.iter().filter(|uri| !deleted.contains(uri)) {
+                        warn!("proposal {uri} deleted on PDS, kept: active vote or not indexed");
+                    }
+                }
+                Err(e) => error!("sql execute failed: {e}"),
+            }
         }
 
         if !reply_to_delete.is_empty() {
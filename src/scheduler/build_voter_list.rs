@@ -1,6 +1,10 @@
-use std::collections::{BTreeSet, HashSet};
+use std::{
+    collections::{BTreeSet, HashSet},
+    sync::atomic::{AtomicBool, Ordering},
+};
 
 use color_eyre::{Result, eyre::eyre};
+use futures::StreamExt;
 use sea_query::{Expr, ExprTrait, PostgresQueryBuilder};
 use sea_query_sqlx::SqlxBinder;
 use sqlx::query_as_with;
@@ -8,7 +12,10 @@ use tokio_cron_scheduler::{Job, JobScheduler};
 
 use crate::{
     AppView,
-    lexicon::voter_list::{VoterList, VoterListRow},
+    lexicon::{
+        voter_list::{VoterList, VoterListRow},
+        voter_proof::VoterProof,
+    },
     smt::{CkbSMT, SMT_VALUE},
 };
 
@@ -16,26 +23,18 @@ pub async fn job(scheduler: &JobScheduler, app: &AppView, cron: &str) -> Result<
     let app = app.clone();
     let mut job = Job::new_async(cron, move |_uuid, _scheduler| {
         Box::pin({
-            let db = app.db.clone();
-            let ckb_client = app.ckb_client.clone();
-            let ckb_net = app.ckb_net;
-            let indexer_bind_url = app.indexer_bind_url.clone();
-            let indexer_dao_url = app.indexer_dao_url.clone();
-            let indexer_did_url = app.indexer_did_url.clone();
-            let build_voter_list_interval = app.build_voter_list_interval;
+            let app = app.clone();
             async move {
-                build_voter_list(
-                    db,
-                    ckb_client,
-                    ckb_net,
-                    indexer_did_url,
-                    indexer_bind_url,
-                    indexer_dao_url,
-                    build_voter_list_interval,
-                )
-                .await
-                .map_err(|e| error!("job run failed: {e}"))
-                .ok();
+                build_voter_list(&app)
+                    .await
+                    // did cell 可能在两次名单之间变更，重建后清空地址缓存
+                    .map(|built| {
+                        if built {
+                            app.ckb_addr_cache.clear();
+                        }
+                    })
+                    .map_err(|e| error!("job run failed: {e}"))
+                    .ok();
             }
         })
     })?;
@@ -55,63 +54,91 @@ pub async fn job(scheduler: &JobScheduler, app: &AppView, cron: &str) -> Result<
     Ok(job)
 }
 
-pub async fn build_voter_list(
-    db: sqlx::Pool<sqlx::Postgres>,
-    ckb_client: ckb_sdk::CkbRpcAsyncClient,
-    ckb_net: ckb_sdk::NetworkType,
-    indexer_did_url: String,
-    indexer_bind_url: String,
-    indexer_dao_url: String,
-    build_voter_list_interval: u64,
-) -> Result<()> {
-    let block_number = Into::<u64>::into(ckb_client.get_tip_block_number().await?);
-
-    let block_number = block_number - (block_number % build_voter_list_interval);
+/// 构建耗时可能超过调度间隔，同一时间只允许一次构建
+static BUILDING: AtomicBool = AtomicBool::new(false);
+
+struct BuildingGuard;
+
+impl BuildingGuard {
+    fn acquire() -> Option<Self> {
+        BUILDING
+            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+            .ok()
+            .map(|_| Self)
+    }
+}
+
+impl Drop for BuildingGuard {
+    fn drop(&mut self) {
+        BUILDING.store(false, Ordering::Release);
+    }
+}
+
+pub async fn build_voter_list(app: &AppView) -> Result<bool> {
+    let Some(_guard) = BuildingGuard::acquire() else {
+        info!("voter list build already running, skipped");
+        return Ok(false);
+    };
+
+    let block_number = Into::<u64>::into(app.ckb_client.get_tip_block_number().await?);
+
+    let block_number = block_number - (block_number % app.build_voter_list_interval);
     let (sql, values) = VoterList::build_select()
         .and_where(Expr::col(VoterList::BlockNumber).eq(block_number as i64))
         .build_sqlx(PostgresQueryBuilder);
     let voter_list_row: Option<VoterListRow> = query_as_with(&sql, values.clone())
-        .fetch_one(&db)
+        .fetch_one(&app.db)
         .await
         .ok();
     if voter_list_row.is_some() {
-        return Ok(());
+        return Ok(false);
     }
 
-    let did_set = crate::indexer_did::did_set(&indexer_did_url, block_number).await?;
+    let did_set =
+        crate::indexer_did::did_set(&app.indexer_did_url, app.indexer_timeout, block_number)
+            .await?;
     let ckb_addrs: HashSet<String> = did_set.values().cloned().collect();
-    let mut voter_btree_set = BTreeSet::new();
-    for ckb_addr in ckb_addrs {
-        if let Ok(deposit) = crate::indexer_bind::get_weight(
-            ckb_net,
-            &indexer_bind_url,
-            &indexer_dao_url,
-            &ckb_addr,
-            Some(block_number),
-        )
-        .await
-        .map(|wp| wp.values().sum::<u64>())
-        {
-            if deposit > 0 {
-                info!(
-                    "CKB address: {} has weight: {}, added to voter list",
-                    ckb_addr, deposit
-                );
-                let address = crate::AddressParser::default()
-                    .set_network(ckb_net)
-                    .parse(&ckb_addr)
-                    .map_err(|e| eyre!(e))?;
-                let lock_script = ckb_types::packed::Script::from(address.payload());
-                let lock_hash_bytes = lock_script.calc_script_hash();
-                voter_btree_set.insert(lock_hash_bytes);
-            } else {
+    // 单个地址查询失败只跳过该地址；名单是集合，并发完成的顺序不影响 SMT 根
+    let voter_btree_set: BTreeSet<_> = futures::stream::iter(ckb_addrs)
+        .map(|ckb_addr| async move {
+            let weight = crate::indexer_bind::get_weight(
+                app.ckb_net,
+                &app.indexer_bind_url,
+                &app.indexer_dao_url,
+                app.indexer_timeout,
+                &ckb_addr,
+                Some(block_number),
+            )
+            .await;
+            (ckb_addr, weight)
+        })
+        .buffer_unordered(app.build_voter_list_concurrency)
+        .filter_map(|(ckb_addr, weight)| async move {
+            let deposit = weight
+                .map(|wp| wp.values().sum::<u64>())
+                .map_err(|e| warn!("get weight of {ckb_addr} failed, skipped: {e}"))
+                .ok()?;
+            if deposit == 0 {
                 info!(
                     "CKB address: {} has weight: {}, not qualified for voter list",
                     ckb_addr, deposit
                 );
+                return None;
             }
-        }
-    }
+            let address = crate::AddressParser::default()
+                .set_network(app.ckb_net)
+                .parse(&ckb_addr)
+                .map_err(|e| warn!("invalid CKB address {ckb_addr}, skipped: {e}"))
+                .ok()?;
+            info!(
+                "CKB address: {} has weight: {}, added to voter list",
+                ckb_addr, deposit
+            );
+            let lock_script = ckb_types::packed::Script::from(address.payload());
+            Some(lock_script.calc_script_hash())
+        })
+        .collect()
+        .await;
 
     let mut voter_list = vec![];
     let mut smt_tree = CkbSMT::default();
@@ -132,5 +159,43 @@ pub async fn build_voter_list(
         smt_root_hash,
         id
     );
-    VoterList::insert(&db, &id, voter_list, &smt_root_hash, block_number as i64).await
+    // 证明先落库，读取名单时即可直接取证明，不必重建整棵树
+    let proofs = crate::smt::compiled_proofs(&smt_tree, &voter_list)?;
+    VoterProof::insert_all(&app.db, &id, &proofs).await?;
+    VoterList::insert(
+        &app.db,
+        &id,
+        voter_list,
+        &smt_root_hash,
+        block_number as i64,
+    )
+    .await?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // BUILDING 是进程内共享状态，相关断言放在同一个测试中，避免并行测试互相干扰
+    #[tokio::test]
+    async fn only_one_build_runs_at_a_time() {
+        let guard = BuildingGuard::acquire();
+        assert!(guard.is_some());
+        assert!(BuildingGuard::acquire().is_none());
+        drop(guard);
+        assert!(BuildingGuard::acquire().is_some());
+
+        let build = || async {
+            let Some(_guard) = BuildingGuard::acquire() else {
+                return false;
+            };
+            // 让出执行权，另一次构建在此期间尝试获取
+            tokio::task::yield_now().await;
+            true
+        };
+        let (a, b) = tokio::join!(build(), build());
+        assert!(a ^ b, "exactly one build should run: {a} {b}");
+        assert!(BuildingGuard::acquire().is_some());
+    }
 }
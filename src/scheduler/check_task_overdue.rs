@@ -0,0 +1,48 @@
+use color_eyre::Result;
+use tokio_cron_scheduler::{Job, JobScheduler};
+
+use crate::{AppView, lexicon::task::Task, webhook::WebhookEvent};
+
+pub async fn job(scheduler: &JobScheduler, app: &AppView, cron: &str) -> Result<Job> {
+    let app = app.clone();
+    let mut job = Job::new_async(cron, move |_uuid, _scheduler| {
+        Box::pin({
+            let app = app.clone();
+            async move {
+                check_task_overdue(&app)
+                    .await
+                    .map_err(|e| error!("check task overdue failed: {e}"))
+                    .ok();
+            }
+        })
+    })?;
+
+    job.on_removed_notification_add(
+        scheduler,
+        Box::new(|job_id, notification_id, type_of_notification| {
+            Box::pin(async move {
+                info!(
+                    "Job {:?} was removed, notification {:?} ran ({:?})",
+                    job_id, notification_id, type_of_notification
+                );
+            })
+        }),
+    )
+    .await?;
+    Ok(job)
+}
+
+/// 标记过了截止时间仍未完成的任务，每个任务只通知一次
+async fn check_task_overdue(app: &AppView) -> Result<()> {
+    let rows = Task::mark_overdue(&app.db).await?;
+    for row in rows {
+        info!("task({}) of {} is overdue", row.id, row.target);
+        app.notify_webhook(WebhookEvent::TaskOverdue {
+            id: row.id,
+            task_type: row.task_type,
+            target: row.target,
+            operators: row.operators,
+        });
+    }
+    Ok(())
+}
@@ -14,10 +14,14 @@ use tokio_cron_scheduler::{Job, JobScheduler};
 
 use crate::{
     AppView,
-    api::proposal::calculate_vote_result,
+    api::{
+        proposal::calculate_vote_result,
+        stream::{ProposalEvent, publish},
+    },
     indexer_vote::all_votes,
     lexicon::{
         administrator::Administrator,
+        notification::{Notification, NotificationType},
         proposal::{Proposal, ProposalSample, ProposalState},
         task::{Task, TaskRow, TaskState, TaskType},
         timeline::{Timeline, TimelineRow, TimelineType},
@@ -70,402 +74,377 @@ pub async fn check_vote_meta_finished(state: AppView) -> Result<()> {
     if rows.is_empty() {
         return Ok(());
     }
-    let bn: u64 = state.ckb_client.get_tip_block_number().await?.into();
-    let current_epoch = state.ckb_client.get_current_epoch().await?;
-    let current_epoch_number: u64 = current_epoch.number.into();
-    let current_epoch_length: u64 = current_epoch.length.into();
-    let current_epoch_index: u64 = bn - Into::<u64>::into(current_epoch.start_number);
+    let (current_epoch_number, current_epoch_index, current_epoch_length) =
+        get_current_epoch(&state).await?;
     debug!(
         "start check vote_meta finished, current epoch: {}, length: {}, index: {}",
         current_epoch_number, current_epoch_length, current_epoch_index
     );
 
-    for VoteMetaRow {
-        id,
-        proposal_uri,
-        proposal_state,
-        block_number,
-        creator,
-        tx_hash,
-        candidates,
-        ..
-    } in rows
-    {
-        let block_number = if let Some(block_number) = block_number {
+    for row in rows {
+        let block_number = if let Some(block_number) = row.block_number {
             block_number as u64
         } else {
             continue;
         };
 
-        let end_time = get_vote_end_time(&state, proposal_state, block_number).await?;
+        let end_time = get_vote_end_time(&state, &row, block_number).await?;
         debug!(
             "check vote_meta id: {}, proposal_state: {}, end_time: {}",
-            id, proposal_state, end_time,
+            row.id, row.proposal_state, end_time,
         );
-        if end_time.number() > current_epoch_number
-            || (end_time.number() == current_epoch_number
-                && (end_time.index() as f64 / end_time.length() as f64)
-                    > (current_epoch_index as f64 / current_epoch_length as f64))
-        {
+        if !is_vote_ended(
+            end_time,
+            current_epoch_number,
+            current_epoch_index,
+            current_epoch_length,
+        ) {
             continue;
         }
 
-        let end_block_number = get_vote_end_block_number(&state, end_time).await?;
+        // 单条记录无法结算（如提案记录解析失败）时不影响其余投票
+        let id = row.id;
+        if let Err(e) = finalize_vote_meta(&state, row, end_time).await {
+            error!("finalize vote_meta id: {id} failed: {e}");
+        }
+    }
+    Ok(())
+}
 
-        let mut vote_results = build_vote_results(
-            &state,
-            tx_hash,
-            &candidates,
-            end_time,
-            end_block_number,
-            true,
-        )
-        .await?;
-
-        let (sql, value) = Proposal::build_sample()
-            .and_where(Expr::col(Proposal::Uri).eq(proposal_uri.clone()))
-            .build_sqlx(PostgresQueryBuilder);
-        let proposal_sample: ProposalSample =
-            query_as_with(&sql, value).fetch_one(&state.db).await?;
-        debug!("proposal_sample: {:?}", proposal_sample);
-        let proposal_type = proposal_sample
-            .record
-            .pointer("/data/proposalType")
-            .and_then(|t| t.as_str())
-            .ok_or_eyre("")?;
-        let vote_result = calculate_vote_result(
+/// 统计已结束投票的结果并推进提案流程；投票已被结算过时返回 `false`
+pub async fn finalize_vote_meta(
+    state: &AppView,
+    row: VoteMetaRow,
+    end_time: EpochNumberWithFraction,
+) -> Result<bool> {
+    let VoteMetaRow {
+        id,
+        proposal_uri,
+        proposal_state,
+        creator,
+        tx_hash,
+        candidates,
+        ..
+    } = row;
+    let end_block_number = get_vote_end_block_number(state, end_time).await?;
+
+    let mut vote_results = build_vote_results(
+        state,
+        tx_hash,
+        &candidates,
+        end_time,
+        end_block_number,
+        true,
+    )
+    .await?;
+
+    let (sql, value) = Proposal::build_sample()
+        .and_where(Expr::col(Proposal::Uri).eq(proposal_uri.clone()))
+        .build_sqlx(PostgresQueryBuilder);
+    let proposal_sample: ProposalSample = query_as_with(&sql, value).fetch_one(&state.db).await?;
+    debug!("proposal_sample: {:?}", proposal_sample);
+    let proposal_record = proposal_sample.parse_record()?;
+    let vote_result = calculate_vote_result(
+        proposal_state,
+        &proposal_record.data,
+        &candidates,
+        vote_results.clone(),
+        &state.governance,
+    );
+    vote_results.result = Some(vote_result.clone() as u32);
+    debug!("vote_result: {:?}", vote_results);
+    let admins: Vec<String> = Administrator::fetch_all(&state.db)
+        .await?
+        .into_iter()
+        .map(|admin| admin.did)
+        .collect();
+
+    // 结果与后续的状态、任务写入同一事务，任一步失败整体回滚，下一轮重试
+    let mut writes = FinalizeWrites {
+        tx: state.db.begin().await?,
+        new_state: None,
+        created_tasks: vec![],
+    };
+    if !VoteMeta::update_results(&mut *writes.tx, id, json!(vote_results)).await? {
+        info!("vote_meta id: {id} already finished, skip");
+        return Ok(false);
+    }
+
+    debug!(
+        "vote_meta id: {} finished with result: {:?}",
+        id, vote_result
+    );
+    let milestone = proposal_record
+        .data
+        .milestones
+        .get(proposal_sample.progress as usize);
+    let outcome = vote_outcome(
+        ProposalState::from(proposal_state),
+        &vote_result,
+        proposal_sample.progress,
+        milestone,
+    );
+    writes.apply(&proposal_uri, outcome, &admins).await?;
+
+    Timeline::insert(
+        &mut *writes.tx,
+        &TimelineRow {
+            id: 0,
+            timeline_type: TimelineType::VoteFinished as i32,
+            message: json!(vote_results).to_string(),
+            target: proposal_uri.clone(),
+            operator: creator,
+            timestamp: chrono::Local::now(),
+        },
+    )
+    .await?;
+
+    Notification::insert(
+        &mut *writes.tx,
+        &proposal_sample.repo,
+        NotificationType::VoteFinished,
+        json!({
+            "proposalUri": &proposal_uri,
+            "voteMetaId": id,
+            "proposalState": proposal_state,
+            "result": vote_results.result,
+        }),
+    )
+    .await?;
+
+    let FinalizeWrites {
+        tx,
+        new_state,
+        created_tasks,
+    } = writes;
+    tx.commit().await?;
+    if let Some(new_state) = new_state {
+        state.proposal_state_changed(&proposal_uri, new_state);
+    }
+    for (id, task) in &created_tasks {
+        state.task_created(*id, task).await;
+    }
+
+    let (sql, value) = Proposal::build_sample()
+        .and_where(Expr::col(Proposal::Uri).eq(proposal_uri.clone()))
+        .build_sqlx(PostgresQueryBuilder);
+    let proposal_state = query_as_with::<_, ProposalSample, _>(&sql, value)
+        .fetch_one(&state.db)
+        .await
+        .map(|p| p.state)
+        .ok();
+    publish(
+        &state.proposal_events,
+        ProposalEvent {
+            uri: proposal_uri,
+            vote_meta_id: id,
+            vote_meta_state: VoteMetaState::Finished as i32,
             proposal_state,
-            &proposal_sample,
-            vote_results.clone(),
-            proposal_type,
-        );
-        vote_results.result = Some(vote_result.clone() as u32);
-        debug!("vote_result: {:?}", vote_results);
-        // update vote_meta state
-        VoteMeta::update_results(&state.db, id, json!(vote_results)).await?;
+        },
+    );
+    Ok(true)
+}
 
-        debug!(
-            "vote_meta id: {} finished with result: {:?}",
-            id, vote_result
-        );
-        match vote_result {
-            VoteResult::Voting => {}
-            VoteResult::Agree => match ProposalState::from(proposal_state) {
-                ProposalState::InitiationVote => {
-                    Proposal::update_state(
-                        &state.db,
-                        &proposal_uri,
-                        ProposalState::WaitingForStartFund as i32,
-                    )
-                    .await?;
-
-                    let admins = Administrator::fetch_all(&state.db)
-                        .await
-                        .iter()
-                        .map(|admin| admin.did.clone())
-                        .collect();
-                    Task::insert(
-                        &state.db,
-                        &TaskRow {
-                            id: 0,
-                            task_type: TaskType::UpdateReceiverAddr as i32,
-                            message: "UpdateReceiverAddr".to_string(),
-                            target: proposal_uri.clone(),
-                            operators: admins,
-                            processor: None,
-                            deadline: chrono::Local::now() + chrono::Duration::days(21),
-                            state: TaskState::Unread as i32,
-                            updated: chrono::Local::now(),
-                            created: chrono::Local::now(),
-                        },
-                    )
-                    .await
-                    .map_err(|e| error!("insert task failed: {e}"))
-                    .ok();
+/// 结算事务中的写入；提案状态变更与新任务在提交后再推送事件
+struct FinalizeWrites {
+    tx: sqlx::Transaction<'static, sqlx::Postgres>,
+    new_state: Option<i32>,
+    created_tasks: Vec<(i32, TaskRow)>,
+}
 
-                    Task::complete(&state.db, &proposal_uri, TaskType::CreateAMA, "SYSTEM")
-                        .await
-                        .ok();
-                    Task::complete(
-                        &state.db,
-                        &proposal_uri,
-                        TaskType::SubmitAMAReport,
-                        "SYSTEM",
-                    )
-                    .await
-                    .ok();
-                }
-                ProposalState::MilestoneVote => {
-                    Proposal::update_state(
-                        &state.db,
-                        &proposal_uri,
-                        ProposalState::WaitingForMilestoneFund as i32,
-                    )
-                    .await?;
-
-                    let admins = Administrator::fetch_all(&state.db)
-                        .await
-                        .iter()
-                        .map(|admin| admin.did.clone())
-                        .collect();
-                    let milestone = proposal_sample
-                        .record
-                        .pointer("/data/milestones")
-                        .and_then(|m| m.as_array())
-                        .and_then(|ms| ms.get(proposal_sample.progress as usize));
-                    Task::insert(
-                        &state.db,
-                        &TaskRow {
-                            id: 0,
-                            task_type: TaskType::SendMilestoneFund as i32,
-                            message: milestone
-                                .map(|m| m.to_string())
-                                .unwrap_or("SendMilestoneFund".to_string()),
-                            target: proposal_uri.clone(),
-                            operators: admins,
-                            processor: None,
-                            deadline: chrono::Local::now() + chrono::Duration::days(21),
-                            state: TaskState::Unread as i32,
-                            updated: chrono::Local::now(),
-                            created: chrono::Local::now(),
-                        },
-                    )
-                    .await
-                    .map_err(|e| error!("insert task failed: {e}"))
-                    .ok();
-                }
-                ProposalState::DelayVote => {
-                    Proposal::update_state(
-                        &state.db,
-                        &proposal_uri,
-                        ProposalState::InProgress as i32,
-                    )
-                    .await?;
-                    let admins: Vec<String> = Administrator::fetch_all(&state.db)
-                        .await
-                        .iter()
-                        .map(|admin| admin.did.clone())
-                        .collect();
-                    Task::insert(
-                        &state.db,
-                        &TaskRow {
-                            id: 0,
-                            task_type: TaskType::SubmitMilestoneReport as i32,
-                            message: proposal_sample.progress.to_string(),
-                            target: proposal_uri.clone(),
-                            operators: admins.clone(),
-                            processor: None,
-                            deadline: chrono::Local::now() + chrono::Duration::days(7),
-                            state: TaskState::Unread as i32,
-                            updated: chrono::Local::now(),
-                            created: chrono::Local::now(),
-                        },
-                    )
-                    .await
-                    .map_err(|e| error!("insert task failed: {e}"))
-                    .ok();
-                    Task::insert(
-                        &state.db,
-                        &TaskRow {
-                            id: 0,
-                            task_type: TaskType::SubmitDelayReport as i32,
-                            message: proposal_sample.progress.to_string(),
-                            target: proposal_uri.clone(),
-                            operators: admins,
-                            processor: None,
-                            deadline: chrono::Local::now() + chrono::Duration::days(7),
-                            state: TaskState::Unread as i32,
-                            updated: chrono::Local::now(),
-                            created: chrono::Local::now(),
-                        },
-                    )
-                    .await
-                    .map_err(|e| error!("insert task failed: {e}"))
-                    .ok();
-                }
-                ProposalState::ReexamineVote | ProposalState::RectificationVote => {
-                    Proposal::update_state(
-                        &state.db,
-                        &proposal_uri,
-                        ProposalState::WaitingRectification as i32,
-                    )
-                    .await?;
-                    let admins = Administrator::fetch_all(&state.db)
-                        .await
-                        .iter()
-                        .map(|admin| admin.did.clone())
-                        .collect();
-                    Task::insert(
-                        &state.db,
-                        &TaskRow {
-                            id: 0,
-                            task_type: TaskType::Rectification as i32,
-                            message: "Rectification".to_string(),
-                            target: proposal_uri.clone(),
-                            operators: admins,
-                            processor: None,
-                            deadline: chrono::Local::now() + chrono::Duration::days(30),
-                            state: TaskState::Unread as i32,
-                            updated: chrono::Local::now(),
-                            created: chrono::Local::now(),
-                        },
-                    )
-                    .await
-                    .map_err(|e| error!("insert task failed: {e}"))
-                    .ok();
-                }
-                _ => {}
-            },
-            VoteResult::AgainstMoreThan51PCT | VoteResult::AgainstMoreThan67PCT => {
-                match ProposalState::from(proposal_state) {
-                    ProposalState::MilestoneVote | ProposalState::DelayVote => {
-                        Proposal::update_state(
-                            &state.db,
-                            &proposal_uri,
-                            ProposalState::WaitingReexamine as i32,
-                        )
-                        .await?;
-
-                        let admins = Administrator::fetch_all(&state.db)
-                            .await
-                            .iter()
-                            .map(|admin| admin.did.clone())
-                            .collect();
-                        Task::insert(
-                            &state.db,
-                            &TaskRow {
-                                id: 0,
-                                task_type: TaskType::CreateReexamineMeeting as i32,
-                                message: "CreateReexamineMeeting".to_string(),
-                                target: proposal_uri.clone(),
-                                operators: admins,
-                                processor: None,
-                                deadline: chrono::Local::now() + chrono::Duration::days(2),
-                                state: TaskState::Unread as i32,
-                                updated: chrono::Local::now(),
-                                created: chrono::Local::now(),
-                            },
-                        )
-                        .await
-                        .map_err(|e| error!("insert task failed: {e}"))
-                        .ok();
-
-                        Task::complete(
-                            &state.db,
-                            &proposal_uri,
-                            TaskType::SubmitMilestoneReport,
-                            "SYSTEM",
-                        )
-                        .await
-                        .ok();
-                        Task::complete(
-                            &state.db,
-                            &proposal_uri,
-                            TaskType::SubmitDelayReport,
-                            "SYSTEM",
-                        )
-                        .await
-                        .ok();
-                    }
-                    _ => {}
-                }
-            }
-            VoteResult::AgreeLessThan51PCT | VoteResult::AgreeLessThan67PCT => {
-                match ProposalState::from(proposal_state) {
-                    ProposalState::InitiationVote => {
-                        Proposal::update_state(&state.db, &proposal_uri, ProposalState::End as i32)
-                            .await?;
-                        Task::complete(&state.db, &proposal_uri, TaskType::CreateAMA, "SYSTEM")
-                            .await
-                            .ok();
-                        Task::complete(
-                            &state.db,
-                            &proposal_uri,
-                            TaskType::SubmitAMAReport,
-                            "SYSTEM",
-                        )
-                        .await
-                        .ok();
-                    }
-                    ProposalState::ReexamineVote => {
-                        Proposal::update_state(&state.db, &proposal_uri, ProposalState::End as i32)
-                            .await?;
-                    }
-                    ProposalState::RectificationVote => {
-                        Proposal::update_state(&state.db, &proposal_uri, ProposalState::End as i32)
-                            .await?;
-                    }
-                    _ => {}
-                }
-            }
-            VoteResult::TotalLessThan185000000CKB | VoteResult::TotalLessThan3X => {
-                match ProposalState::from(proposal_state) {
-                    ProposalState::InitiationVote => {
-                        Proposal::update_state(&state.db, &proposal_uri, ProposalState::End as i32)
-                            .await?;
-                        Task::complete(&state.db, &proposal_uri, TaskType::CreateAMA, "SYSTEM")
-                            .await
-                            .ok();
-                        Task::complete(
-                            &state.db,
-                            &proposal_uri,
-                            TaskType::SubmitAMAReport,
-                            "SYSTEM",
-                        )
-                        .await
-                        .ok();
-                    }
-                    ProposalState::ReexamineVote => {
-                        let admins = Administrator::fetch_all(&state.db)
-                            .await
-                            .iter()
-                            .map(|admin| admin.did.clone())
-                            .collect();
-                        Task::insert(
-                            &state.db,
-                            &TaskRow {
-                                id: 0,
-                                task_type: TaskType::RectificationVote as i32,
-                                message: "RectificationVote".to_string(),
-                                target: proposal_uri.clone(),
-                                operators: admins,
-                                processor: None,
-                                deadline: chrono::Local::now() + chrono::Duration::days(30),
-                                state: TaskState::Unread as i32,
-                                updated: chrono::Local::now(),
-                                created: chrono::Local::now(),
-                            },
-                        )
-                        .await
-                        .map_err(|e| error!("insert task failed: {e}"))
-                        .ok();
-                    }
-                    ProposalState::RectificationVote => {
-                        Proposal::update_state(&state.db, &proposal_uri, ProposalState::End as i32)
-                            .await?;
-                    }
-                    _ => {}
-                }
-            }
-            VoteResult::Failed => {}
+impl FinalizeWrites {
+    async fn update_state(&mut self, proposal_uri: &str, state: i32) -> Result<()> {
+        if Proposal::update_state(&mut *self.tx, proposal_uri, state).await? > 0 {
+            self.new_state = Some(state);
         }
+        Ok(())
+    }
+
+    async fn insert_task(&mut self, task: TaskRow) -> Result<()> {
+        let id = Task::insert(&mut *self.tx, &task).await?;
+        self.created_tasks.push((id, task));
+        Ok(())
+    }
+
+    async fn complete_task(&mut self, proposal_uri: &str, task_type: TaskType) -> Result<()> {
+        Task::complete(&mut *self.tx, proposal_uri, task_type, "SYSTEM").await?;
+        Ok(())
+    }
 
-        Timeline::insert(
-            &state.db,
-            &TimelineRow {
+    async fn apply(
+        &mut self,
+        proposal_uri: &str,
+        outcome: VoteOutcome,
+        admins: &[String],
+    ) -> Result<()> {
+        if let Some(new_state) = outcome.new_state {
+            self.update_state(proposal_uri, new_state as i32).await?;
+        }
+        for task in outcome.new_tasks {
+            let now = chrono::Local::now();
+            self.insert_task(TaskRow {
                 id: 0,
-                timeline_type: TimelineType::VoteFinished as i32,
-                message: json!(vote_results).to_string(),
-                target: proposal_uri.clone(),
-                operator: creator,
-                timestamp: chrono::Local::now(),
-            },
-        )
-        .await
-        .map_err(|e| error!("insert timeline failed: {e}"))
-        .ok();
+                task_type: task.task_type as i32,
+                message: task.message,
+                target: proposal_uri.to_string(),
+                operators: admins.to_vec(),
+                processor: None,
+                deadline: now + chrono::Duration::days(task.deadline_days),
+                state: TaskState::Unread as i32,
+                updated: now,
+                created: now,
+                overdue: false,
+            })
+            .await?;
+        }
+        for task_type in outcome.completed_tasks {
+            self.complete_task(proposal_uri, task_type).await?;
+        }
+        Ok(())
+    }
+}
+
+/// 投票结束后提案的流转：新状态、要创建与要完成的任务
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct VoteOutcome {
+    pub new_state: Option<ProposalState>,
+    pub new_tasks: Vec<NewTask>,
+    pub completed_tasks: Vec<TaskType>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct NewTask {
+    pub task_type: TaskType,
+    pub message: String,
+    /// 截止时间距创建的天数
+    pub deadline_days: i64,
+}
+
+impl NewTask {
+    fn new(task_type: TaskType, message: impl Into<String>, deadline_days: i64) -> Self {
+        Self {
+            task_type,
+            message: message.into(),
+            deadline_days,
+        }
+    }
+}
+
+/// 按投票阶段与计票结果决定提案流转，不涉及数据库
+pub fn vote_outcome(
+    proposal_state: ProposalState,
+    vote_result: &VoteResult,
+    progress: i32,
+    milestone: Option<&serde_json::Value>,
+) -> VoteOutcome {
+    let end_initiation = || VoteOutcome {
+        new_state: Some(ProposalState::End),
+        new_tasks: vec![],
+        completed_tasks: vec![TaskType::CreateAMA, TaskType::SubmitAMAReport],
+    };
+    let end = || VoteOutcome {
+        new_state: Some(ProposalState::End),
+        ..Default::default()
+    };
+    match (vote_result, proposal_state) {
+        (VoteResult::Agree, ProposalState::InitiationVote) => VoteOutcome {
+            new_state: Some(ProposalState::WaitingForStartFund),
+            new_tasks: vec![NewTask::new(
+                TaskType::UpdateReceiverAddr,
+                "UpdateReceiverAddr",
+                21,
+            )],
+            completed_tasks: vec![TaskType::CreateAMA, TaskType::SubmitAMAReport],
+        },
+        (VoteResult::Agree, ProposalState::MilestoneVote) => VoteOutcome {
+            new_state: Some(ProposalState::WaitingForMilestoneFund),
+            new_tasks: vec![NewTask::new(
+                TaskType::SendMilestoneFund,
+                milestone
+                    .map(|m| m.to_string())
+                    .unwrap_or("SendMilestoneFund".to_string()),
+                21,
+            )],
+            completed_tasks: vec![],
+        },
+        (VoteResult::Agree, ProposalState::DelayVote) => VoteOutcome {
+            new_state: Some(ProposalState::InProgress),
+            new_tasks: vec![
+                NewTask::new(TaskType::SubmitMilestoneReport, progress.to_string(), 7),
+                NewTask::new(TaskType::SubmitDelayReport, progress.to_string(), 7),
+            ],
+            completed_tasks: vec![],
+        },
+        (VoteResult::Agree, ProposalState::ReexamineVote | ProposalState::RectificationVote) => {
+            VoteOutcome {
+                new_state: Some(ProposalState::WaitingRectification),
+                new_tasks: vec![NewTask::new(TaskType::Rectification, "Rectification", 30)],
+                completed_tasks: vec![],
+            }
+        }
+        (
+            VoteResult::AgainstMoreThan51PCT | VoteResult::AgainstMoreThan67PCT,
+            ProposalState::MilestoneVote | ProposalState::DelayVote,
+        ) => VoteOutcome {
+            new_state: Some(ProposalState::WaitingReexamine),
+            new_tasks: vec![NewTask::new(
+                TaskType::CreateReexamineMeeting,
+                "CreateReexamineMeeting",
+                2,
+            )],
+            completed_tasks: vec![TaskType::SubmitMilestoneReport, TaskType::SubmitDelayReport],
+        },
+        // 立项投票除通过外的结果（含计票失败）都结束提案
+        (VoteResult::Voting, _) => VoteOutcome::default(),
+        (_, ProposalState::InitiationVote) => end_initiation(),
+        (
+            VoteResult::AgreeLessThan51PCT | VoteResult::AgreeLessThan67PCT,
+            ProposalState::ReexamineVote | ProposalState::RectificationVote,
+        ) => end(),
+        (
+            VoteResult::TotalLessThan185000000CKB | VoteResult::TotalLessThan3X,
+            ProposalState::ReexamineVote,
+        ) => VoteOutcome {
+            new_state: None,
+            new_tasks: vec![NewTask::new(
+                TaskType::RectificationVote,
+                "RectificationVote",
+                30,
+            )],
+            completed_tasks: vec![],
+        },
+        (
+            VoteResult::TotalLessThan185000000CKB | VoteResult::TotalLessThan3X,
+            ProposalState::RectificationVote,
+        ) => end(),
+        _ => VoteOutcome::default(),
     }
-    Ok(())
+}
+
+/// 当前 epoch 的 (number, index, length)
+pub async fn get_current_epoch(state: &AppView) -> Result<(u64, u64, u64)> {
+    // 先取 epoch 再取 tip，两次调用之间切换 epoch 时 tip 也不会落在 start_number 之前
+    let current_epoch = state.ckb_client.get_current_epoch().await?;
+    let bn: u64 = state.ckb_client.get_tip_block_number().await?.into();
+    let current_epoch_index = bn.saturating_sub(current_epoch.start_number.into());
+    Ok((
+        current_epoch.number.into(),
+        current_epoch_index,
+        current_epoch.length.into(),
+    ))
+}
+
+/// 当前 epoch 是否已到达投票结束时间
+pub fn is_vote_ended(
+    end_time: EpochNumberWithFraction,
+    current_epoch_number: u64,
+    current_epoch_index: u64,
+    current_epoch_length: u64,
+) -> bool {
+    !(end_time.number() > current_epoch_number
+        || (end_time.number() == current_epoch_number
+            && (end_time.index() as f64 / end_time.length() as f64)
+                > (current_epoch_index as f64 / current_epoch_length as f64)))
 }
 
 pub async fn get_vote_end_block_number(
@@ -489,7 +468,7 @@ pub async fn get_vote_end_block_number(
 
 pub async fn get_vote_end_time(
     state: &AppView,
-    proposal_state: i32,
+    vote_meta: &VoteMetaRow,
     block_number: u64,
 ) -> Result<EpochNumberWithFraction> {
     let begin_epoch = EpochNumberWithFraction::from_full_value(
@@ -503,10 +482,7 @@ pub async fn get_vote_end_time(
             .epoch
             .into(),
     );
-    let duration_days = match ProposalState::from(proposal_state) {
-        ProposalState::MilestoneVote | ProposalState::DelayVote => 3,
-        _ => 7,
-    };
+    let duration_days = vote_meta.vote_duration_days(&state.governance);
     let end_time = EpochNumberWithFraction::new(
         Into::<u64>::into(begin_epoch.number()) + (6 * duration_days),
         begin_epoch.index(),
@@ -538,6 +514,7 @@ pub async fn build_vote_results(
     debug!("args: {}", args);
     let vote_result = all_votes(
         &state.indexer_vote_url,
+        state.indexer_timeout,
         &args,
         end_time.number() as i64,
         end_time.index() as i64,
@@ -579,6 +556,7 @@ pub async fn build_vote_results(
             state.ckb_net,
             &state.indexer_bind_url,
             &state.indexer_dao_url,
+            state.indexer_timeout,
             &voter_ckb_addr,
             Some(end_block_number),
         )
@@ -617,12 +595,16 @@ pub async fn build_vote_results(
             *weight_sum += weight;
         }
         if detail && let Some(valid_vote) = valid_votes.get_mut(vote_index) {
-            let did = crate::indexer_did::ckb_did(&state.indexer_did_url, &voter_ckb_addr)
-                .await
-                .unwrap_or_default()
-                .first()
-                .cloned()
-                .unwrap_or_default();
+            let did = crate::indexer_did::ckb_did(
+                &state.indexer_did_url,
+                state.indexer_timeout,
+                &voter_ckb_addr,
+            )
+            .await
+            .unwrap_or_default()
+            .first()
+            .cloned()
+            .unwrap_or_default();
             let author = crate::api::build_author(state, &format!("did:ckb:{did}")).await;
             valid_vote.push(VoteView {
                 ckb_addr: voter_ckb_addr.to_string(),
@@ -642,3 +624,121 @@ pub async fn build_vote_results(
         result: None,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vote_ends_at_close_boundary() {
+        let end_time = EpochNumberWithFraction::new(100, 900, 1800);
+        assert!(!is_vote_ended(end_time, 100, 899, 1800));
+        assert!(is_vote_ended(end_time, 100, 900, 1800));
+        assert!(is_vote_ended(end_time, 100, 901, 1800));
+        assert!(is_vote_ended(end_time, 101, 0, 1800));
+    }
+
+    #[test]
+    fn vote_not_ended_in_earlier_epoch() {
+        let end_time = EpochNumberWithFraction::new(100, 0, 1800);
+        assert!(!is_vote_ended(end_time, 99, 1799, 1800));
+        assert!(is_vote_ended(end_time, 100, 0, 1800));
+    }
+
+    #[test]
+    fn vote_end_compares_fractions_across_epoch_lengths() {
+        // 结束于 epoch 100 的一半处，当前 epoch 长度与开始时不同
+        let end_time = EpochNumberWithFraction::new(100, 900, 1800);
+        assert!(!is_vote_ended(end_time, 100, 999, 2000));
+        assert!(is_vote_ended(end_time, 100, 1000, 2000));
+        assert!(!is_vote_ended(end_time, 100, 799, 1600));
+        assert!(is_vote_ended(end_time, 100, 800, 1600));
+    }
+
+    #[test]
+    fn initiation_agree_waits_for_start_fund() {
+        let outcome = vote_outcome(ProposalState::InitiationVote, &VoteResult::Agree, 0, None);
+        assert_eq!(outcome.new_state, Some(ProposalState::WaitingForStartFund));
+        assert_eq!(
+            outcome.new_tasks,
+            vec![NewTask::new(
+                TaskType::UpdateReceiverAddr,
+                "UpdateReceiverAddr",
+                21
+            )]
+        );
+        assert_eq!(
+            outcome.completed_tasks,
+            vec![TaskType::CreateAMA, TaskType::SubmitAMAReport]
+        );
+    }
+
+    #[test]
+    fn initiation_not_passed_ends_proposal() {
+        for vote_result in [
+            VoteResult::AgreeLessThan51PCT,
+            VoteResult::AgreeLessThan67PCT,
+            VoteResult::AgainstMoreThan51PCT,
+            VoteResult::TotalLessThan3X,
+            VoteResult::TotalLessThan185000000CKB,
+            VoteResult::Failed,
+        ] {
+            let outcome = vote_outcome(ProposalState::InitiationVote, &vote_result, 0, None);
+            assert_eq!(
+                outcome,
+                VoteOutcome {
+                    new_state: Some(ProposalState::End),
+                    new_tasks: vec![],
+                    completed_tasks: vec![TaskType::CreateAMA, TaskType::SubmitAMAReport],
+                },
+                "{vote_result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn milestone_against_waits_for_reexamine() {
+        let outcome = vote_outcome(
+            ProposalState::MilestoneVote,
+            &VoteResult::AgainstMoreThan51PCT,
+            1,
+            None,
+        );
+        assert_eq!(outcome.new_state, Some(ProposalState::WaitingReexamine));
+        assert_eq!(
+            outcome.completed_tasks,
+            vec![TaskType::SubmitMilestoneReport, TaskType::SubmitDelayReport]
+        );
+    }
+
+    #[test]
+    fn milestone_agree_uses_milestone_as_task_message() {
+        let milestone = json!({"title": "m1"});
+        let outcome = vote_outcome(
+            ProposalState::MilestoneVote,
+            &VoteResult::Agree,
+            0,
+            Some(&milestone),
+        );
+        assert_eq!(
+            outcome.new_state,
+            Some(ProposalState::WaitingForMilestoneFund)
+        );
+        assert_eq!(
+            outcome.new_tasks,
+            vec![NewTask::new(
+                TaskType::SendMilestoneFund,
+                milestone.to_string(),
+                21
+            )]
+        );
+    }
+
+    #[test]
+    fn still_voting_changes_nothing() {
+        assert_eq!(
+            vote_outcome(ProposalState::InitiationVote, &VoteResult::Voting, 0, None),
+            VoteOutcome::default()
+        );
+    }
+}
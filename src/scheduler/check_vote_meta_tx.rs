@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use color_eyre::Result;
 use molecule::prelude::Entity;
 use sea_query::{Expr, ExprTrait, PostgresQueryBuilder};
@@ -6,9 +8,13 @@ use tokio_cron_scheduler::{Job, JobScheduler};
 
 use crate::{
     AppView,
-    api::vote,
+    api::{
+        stream::{ProposalEvent, publish},
+        vote,
+    },
+    ckb::{self, TxHash, TxStatusResult, get_transactions},
     lexicon::{
-        proposal::{Proposal, ProposalState},
+        proposal::ProposalState,
         timeline::{Timeline, TimelineRow, TimelineType},
         vote_meta::{VoteMeta, VoteMetaRow, VoteMetaState},
     },
@@ -18,10 +24,9 @@ pub async fn job(scheduler: &JobScheduler, app: &AppView, cron: &str) -> Result<
     let app = app.clone();
     let mut job = Job::new_async(cron, move |_uuid, _scheduler| {
         Box::pin({
-            let db = app.db.clone();
-            let ckb_client = app.ckb_client.clone();
+            let app = app.clone();
             async move {
-                check_vote_meta_tx(db, ckb_client).await;
+                check_vote_meta_tx(&app).await;
             }
         })
     })?;
@@ -41,17 +46,14 @@ pub async fn job(scheduler: &JobScheduler, app: &AppView, cron: &str) -> Result<
     Ok(job)
 }
 
-pub async fn check_vote_meta_tx(
-    db: sqlx::Pool<sqlx::Postgres>,
-    ckb_client: ckb_sdk::CkbRpcAsyncClient,
-) {
+pub async fn check_vote_meta_tx(app: &AppView) {
     let (sql, values) = VoteMeta::build_select()
         .and_where(Expr::col(VoteMeta::State).eq(VoteMetaState::Waiting as i32))
         .build_sqlx(PostgresQueryBuilder);
 
     #[allow(clippy::type_complexity)]
     let rows: Option<Vec<VoteMetaRow>> = sqlx::query_as_with(&sql, values.clone())
-        .fetch_all(&db)
+        .fetch_all(&app.db)
         .await
         .map_err(|e| {
             error!("{e}");
@@ -59,69 +61,86 @@ pub async fn check_vote_meta_tx(
         })
         .ok();
     if let Some(rows) = rows {
+        // query all pending txs up front so the tick isn't serialized on RPC round trips
+        let txs = rows
+            .iter()
+            .filter_map(|row| match row.tx_hash.as_ref()?.parse::<TxHash>() {
+                Ok(tx_hash) => Some((row.id, tx_hash)),
+                Err(e) => {
+                    error!("VoteMeta({}) has {e}", row.id);
+                    None
+                }
+            });
+        let mut txs = get_transactions(&app.ckb_client, txs.collect::<Vec<_>>())
+            .await
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+        // 查询失败时本轮不确认任何交易
+        let tip = ckb::confirmation_tip(&app.ckb_client, app.tx_confirmations)
+            .await
+            .map_err(|e| error!("get tip block number failed: {e}"))
+            .unwrap_or(Some(0));
+
         for row in rows {
             let (meta_state, tx_status) = if let Some(tx_hash) = &row.tx_hash {
-                let tx = ckb_client
-                    .get_transaction(ckb_types::H256(
-                        hex::decode(tx_hash.strip_prefix("0x").unwrap_or(tx_hash))
-                            .unwrap()
-                            .try_into()
-                            .unwrap(),
-                    ))
-                    .await;
-                if let Ok(Some((tx_status, tx))) = tx.map(|t| {
-                    t.map(|t| {
-                        (
-                            t.tx_status,
-                            t.transaction.and_then(|t| {
-                                if let ckb_jsonrpc_types::Either::Left(tx) = t.inner {
-                                    Some(tx)
-                                } else {
-                                    None
-                                }
-                            }),
-                        )
-                    })
-                }) {
-                    debug!("VoteMeta({}) tx {tx_hash} status: {tx_status:?}", row.id);
-                    let meta_state = match tx_status.status {
-                        ckb_jsonrpc_types::Status::Committed => {
-                            let proposal_hash = ckb_hash::blake2b_256(
-                                serde_json::to_vec(&row.proposal_uri).unwrap(),
-                            );
-                            if let Ok(vote_meta) =
-                                vote::build_vote_meta(&db, &row, &proposal_hash).await
-                            {
-                                let vote_meta_bytes = vote_meta.as_bytes().to_vec();
+                let Some(tx) = txs.remove(&row.id) else {
+                    continue;
+                };
+                let tx = match tx {
+                    Ok(tx) => tx,
+                    Err(e) => {
+                        error!("VoteMeta({}) {e}, retry next tick", row.id);
+                        continue;
+                    }
+                };
+                let tx_status = tx.as_ref().map(|t| t.tx_status.clone());
+                let status = TxStatusResult::from_tx_status(tx_status.as_ref());
+                debug!("VoteMeta({}) tx {tx_hash} status: {status:?}", row.id);
+                let meta_state = match status {
+                    TxStatusResult::Committed(block_number) => {
+                        if !ckb::is_confirmed(block_number, tip, app.tx_confirmations) {
+                            debug!("VoteMeta({}) tx {tx_hash} not confirmed yet", row.id);
+                            continue;
+                        }
+                        let tx = tx.and_then(|t| t.transaction).and_then(|t| {
+                            if let ckb_jsonrpc_types::Either::Left(tx) = t.inner {
+                                Some(tx)
+                            } else {
+                                None
+                            }
+                        });
+                        let proposal_hash =
+                            ckb_hash::blake2b_256(serde_json::to_vec(&row.proposal_uri).unwrap());
+                        if let Ok(vote_meta) =
+                            vote::build_vote_meta(&app.db, &row, &proposal_hash).await
+                        {
+                            let vote_meta_bytes = vote_meta.as_bytes().to_vec();
 
-                                if let Some(tx) = tx {
-                                    if tx.inner.outputs_data[0].as_bytes() == vote_meta_bytes {
-                                        VoteMetaState::Committed
-                                    } else {
-                                        VoteMetaState::Changed
-                                    }
+                            if let Some(tx) = tx {
+                                if tx.inner.outputs_data[0].as_bytes() == vote_meta_bytes {
+                                    VoteMetaState::Committed
                                 } else {
                                     VoteMetaState::Changed
                                 }
                             } else {
                                 VoteMetaState::Changed
                             }
+                        } else {
+                            VoteMetaState::Changed
                         }
-                        ckb_jsonrpc_types::Status::Pending => continue,
-                        ckb_jsonrpc_types::Status::Proposed => continue,
-                        ckb_jsonrpc_types::Status::Unknown => {
-                            if (chrono::Local::now() - row.created) > chrono::Duration::minutes(3) {
-                                VoteMetaState::Timeout
-                            } else {
-                                continue;
-                            }
+                    }
+                    TxStatusResult::Pending | TxStatusResult::Proposed => continue,
+                    // not indexed by the node yet, keep polling until the timeout
+                    TxStatusResult::NotFound | TxStatusResult::Unknown => {
+                        if (chrono::Local::now() - row.created) > app.vote_meta_tx_timeout {
+                            VoteMetaState::Timeout
+                        } else {
+                            continue;
                         }
-                        ckb_jsonrpc_types::Status::Rejected => VoteMetaState::Rejected,
-                    };
-                    (meta_state, Some(tx_status))
-                } else {
-                    continue;
-                }
+                    }
+                    TxStatusResult::Rejected => VoteMetaState::Rejected,
+                };
+                (meta_state, tx_status)
             } else {
                 if (chrono::Local::now() - row.created) > chrono::Duration::minutes(30) {
                     (VoteMetaState::Timeout, None)
@@ -144,34 +163,43 @@ pub async fn check_vote_meta_tx(
                 ])
                 .and_where(Expr::col(VoteMeta::Id).eq(row.id))
                 .build_sqlx(PostgresQueryBuilder);
-            sqlx::query_with(&sql, values).execute(&db).await.ok();
+            sqlx::query_with(&sql, values).execute(&app.db).await.ok();
 
             debug!("VoteMeta({}) marked as {:?}", row.id, meta_state);
 
+            let mut proposal_state = None;
             match meta_state {
                 VoteMetaState::Committed => {
-                    // update proposal state
-                    let lines = Proposal::update_state(&db, &row.proposal_uri, row.proposal_state)
+                    // the proposal enters the voting state this vote_meta was created for
+                    let target_state = ProposalState::from(row.proposal_state);
+                    let timeline_type = match target_state {
+                        ProposalState::InitiationVote => TimelineType::InitiationVote,
+                        ProposalState::MilestoneVote => TimelineType::MilestoneVote,
+                        ProposalState::DelayVote => TimelineType::DelayVote,
+                        ProposalState::ReexamineVote => TimelineType::ReexamineVote,
+                        ProposalState::RectificationVote => TimelineType::RectificationVote,
+                        _ => {
+                            error!(
+                                "VoteMeta({}) has non-vote proposal_state {:?}",
+                                row.id, target_state
+                            );
+                            continue;
+                        }
+                    };
+                    let lines = app
+                        .update_proposal_state(&row.proposal_uri, target_state as i32)
                         .await
                         .map_err(|e| error!("update proposal state failed: {e}"))
                         .unwrap_or(0);
                     if lines > 0 {
                         debug!(
                             "Proposal({}) marked as {:?}",
-                            row.proposal_uri,
-                            ProposalState::from(row.proposal_state)
+                            row.proposal_uri, target_state
                         );
-                        let timeline_type = match ProposalState::from(row.proposal_state) {
-                            ProposalState::InitiationVote => TimelineType::InitiationVote,
-                            ProposalState::MilestoneVote => TimelineType::MilestoneVote,
-                            ProposalState::DelayVote => TimelineType::DelayVote,
-                            ProposalState::ReexamineVote => TimelineType::ReexamineVote,
-                            ProposalState::RectificationVote => TimelineType::RectificationVote,
-                            _ => continue,
-                        };
+                        proposal_state = Some(target_state as i32);
 
                         Timeline::insert(
-                            &db,
+                            &app.db,
                             &TimelineRow {
                                 id: 0,
                                 timeline_type: timeline_type as i32,
@@ -192,20 +220,21 @@ pub async fn check_vote_meta_tx(
                         row.id,
                         row.tx_hash.clone().unwrap_or_default()
                     );
-                    let lines =
-                        Proposal::update_state(&db, &row.proposal_uri, ProposalState::End as i32)
-                            .await
-                            .map_err(|e| error!("update proposal state failed: {e}"))
-                            .unwrap_or(0);
+                    let lines = app
+                        .update_proposal_state(&row.proposal_uri, ProposalState::End as i32)
+                        .await
+                        .map_err(|e| error!("update proposal state failed: {e}"))
+                        .unwrap_or(0);
                     if lines > 0 {
                         debug!(
                             "Proposal({}) marked as {:?}",
                             row.proposal_uri,
                             ProposalState::End
                         );
+                        proposal_state = Some(ProposalState::End as i32);
 
                         Timeline::insert(
-                            &db,
+                            &app.db,
                             &TimelineRow {
                                 id: 0,
                                 timeline_type: TimelineType::VoteMetaTxChanged as i32,
@@ -226,20 +255,21 @@ pub async fn check_vote_meta_tx(
                 }
                 VoteMetaState::Timeout => {
                     error!("VoteMeta({}) is timeout, tx not committed in time", row.id);
-                    let lines =
-                        Proposal::update_state(&db, &row.proposal_uri, ProposalState::End as i32)
-                            .await
-                            .map_err(|e| error!("update proposal state failed: {e}"))
-                            .unwrap_or(0);
+                    let lines = app
+                        .update_proposal_state(&row.proposal_uri, ProposalState::End as i32)
+                        .await
+                        .map_err(|e| error!("update proposal state failed: {e}"))
+                        .unwrap_or(0);
                     if lines > 0 {
                         debug!(
                             "Proposal({}) marked as {:?}",
                             row.proposal_uri,
                             ProposalState::End
                         );
+                        proposal_state = Some(ProposalState::End as i32);
 
                         Timeline::insert(
-                            &db,
+                            &app.db,
                             &TimelineRow {
                                 id: 0,
                                 timeline_type: TimelineType::VoteMetaTxTimeout as i32,
@@ -259,6 +289,16 @@ pub async fn check_vote_meta_tx(
                 }
                 _ => (),
             }
+
+            publish(
+                &app.proposal_events,
+                ProposalEvent {
+                    uri: row.proposal_uri,
+                    vote_meta_id: row.id,
+                    vote_meta_state: meta_state as i32,
+                    proposal_state,
+                },
+            );
         }
     }
 }
@@ -6,7 +6,7 @@ use tokio_cron_scheduler::{Job, JobScheduler};
 
 use crate::{
     AppView,
-    ckb::get_tx_status,
+    ckb::{self, TxHash, TxStatusResult, get_tx_statuses},
     lexicon::vote::{Vote, VoteState},
 };
 
@@ -14,10 +14,9 @@ pub async fn job(scheduler: &JobScheduler, app: &AppView, cron: &str) -> Result<
     let app = app.clone();
     let mut job = Job::new_async(cron, move |_uuid, _scheduler| {
         Box::pin({
-            let db = app.db.clone();
-            let ckb_client = app.ckb_client.clone();
+            let app = app.clone();
             async move {
-                check_vote_tx(db, ckb_client).await;
+                check_vote_tx(&app).await;
             }
         })
     })?;
@@ -37,7 +36,7 @@ pub async fn job(scheduler: &JobScheduler, app: &AppView, cron: &str) -> Result<
     Ok(job)
 }
 
-pub async fn check_vote_tx(db: sqlx::Pool<sqlx::Postgres>, ckb_client: ckb_sdk::CkbRpcAsyncClient) {
+pub async fn check_vote_tx(app: &AppView) {
     let (sql, values) = sea_query::Query::select()
         .columns([
             (Vote::Table, Vote::Id),
@@ -51,7 +50,7 @@ pub async fn check_vote_tx(db: sqlx::Pool<sqlx::Postgres>, ckb_client: ckb_sdk::
     #[allow(clippy::type_complexity)]
     let rows: Option<Vec<(i32, Option<String>, DateTime<Local>)>> =
         sqlx::query_as_with(&sql, values.clone())
-            .fetch_all(&db)
+            .fetch_all(&app.db)
             .await
             .map_err(|e| {
                 error!("{e}");
@@ -59,33 +58,56 @@ pub async fn check_vote_tx(db: sqlx::Pool<sqlx::Postgres>, ckb_client: ckb_sdk::
             })
             .ok();
     if let Some(rows) = rows {
-        for (id, tx_hash, created) in rows {
-            if let Some(tx_hash) = tx_hash {
-                let tx_status = get_tx_status(&ckb_client, &tx_hash).await;
-                if let Ok(tx_status) = tx_status {
-                    debug!("Vote({id}) tx {tx_hash} status: {tx_status:?}");
-                    let meta_state = match tx_status {
-                        ckb_jsonrpc_types::Status::Committed => VoteState::Committed,
-                        ckb_jsonrpc_types::Status::Pending => continue,
-                        ckb_jsonrpc_types::Status::Proposed => continue,
-                        ckb_jsonrpc_types::Status::Unknown => {
-                            if (chrono::Local::now() - created) > chrono::Duration::minutes(3) {
-                                VoteState::Timeout
-                            } else {
-                                continue;
-                            }
-                        }
-                        ckb_jsonrpc_types::Status::Rejected => VoteState::Rejected,
-                    };
-                    let (sql, values) = sea_query::Query::update()
-                        .table(Vote::Table)
-                        .value(Vote::State, meta_state as i32)
-                        .and_where(Expr::col(Vote::Id).eq(id))
-                        .build_sqlx(PostgresQueryBuilder);
-                    sqlx::query_with(&sql, values).execute(&db).await.ok();
-                    debug!("Vote({}) tx {} marked as {:?}", id, tx_hash, meta_state);
+        let txs = rows
+            .into_iter()
+            .filter_map(|(id, tx_hash, created)| match tx_hash?.parse::<TxHash>() {
+                Ok(tx_hash) => Some(((id, created, tx_hash.to_string()), tx_hash)),
+                Err(e) => {
+                    error!("Vote({id}) has {e}");
+                    None
                 }
-            }
+            })
+            .collect::<Vec<_>>();
+
+        // 查询失败时本轮不确认任何交易
+        let tip = ckb::confirmation_tip(&app.ckb_client, app.tx_confirmations)
+            .await
+            .map_err(|e| error!("get tip block number failed: {e}"))
+            .unwrap_or(Some(0));
+        for ((id, created, tx_hash), tx_status) in get_tx_statuses(&app.ckb_client, txs).await {
+            let tx_status = match tx_status {
+                Ok(tx_status) => tx_status,
+                Err(e) => {
+                    error!("Vote({id}) {e}, retry next tick");
+                    continue;
+                }
+            };
+            debug!("Vote({id}) tx {tx_hash} status: {tx_status:?}");
+            let meta_state = match tx_status {
+                TxStatusResult::Committed(block_number) => {
+                    if !ckb::is_confirmed(block_number, tip, app.tx_confirmations) {
+                        continue;
+                    }
+                    VoteState::Committed
+                }
+                TxStatusResult::Pending | TxStatusResult::Proposed => continue,
+                // not indexed by the node yet, keep polling until the timeout
+                TxStatusResult::NotFound | TxStatusResult::Unknown => {
+                    if (chrono::Local::now() - created) > app.vote_tx_timeout {
+                        VoteState::Timeout
+                    } else {
+                        continue;
+                    }
+                }
+                TxStatusResult::Rejected => VoteState::Rejected,
+            };
+            let (sql, values) = sea_query::Query::update()
+                .table(Vote::Table)
+                .value(Vote::State, meta_state as i32)
+                .and_where(Expr::col(Vote::Id).eq(id))
+                .build_sqlx(PostgresQueryBuilder);
+            sqlx::query_with(&sql, values).execute(&app.db).await.ok();
+            debug!("Vote({}) tx {} marked as {:?}", id, tx_hash, meta_state);
         }
     }
 }
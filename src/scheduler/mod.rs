@@ -1,33 +1,118 @@
 pub mod build_voter_list;
+mod check_task_overdue;
 pub mod check_vote_finished;
 mod check_vote_meta_tx;
 mod check_vote_tx;
+pub mod prune_orphans;
 
 use color_eyre::{Result, eyre::eyre};
 use tokio_cron_scheduler::JobScheduler;
 
 use crate::AppView;
 
-pub async fn init_task_scheduler(app: &AppView) -> Result<()> {
-    let mut scheduler = JobScheduler::new().await?;
+/// cron 表达式（6 位，含秒）相邻两次触发的最长间隔，取接下来若干次触发估算
+pub fn cron_period(cron: &str) -> Result<chrono::Duration> {
+    let cron = croner::Cron::new(cron).with_seconds_required().parse()?;
+    let mut tick = cron.find_next_occurrence(&chrono::Utc::now(), false)?;
+    let mut period = chrono::Duration::zero();
+    for _ in 0..16 {
+        let next = cron.find_next_occurrence(&tick, false)?;
+        period = period.max(next - tick);
+        tick = next;
+    }
+    Ok(period)
+}
 
-    let job = build_voter_list::job(&scheduler, app, "0 * * * * *").await?;
-    scheduler.add(job).await?;
+/// 可通过 `--disable-job` 关闭的定时任务
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum JobName {
+    BuildVoterList,
+    CheckVoteMetaTx,
+    CheckVoteTx,
+    CheckVoteFinished,
+    PruneOrphans,
+    CheckTaskOverdue,
+}
 
-    let job = check_vote_meta_tx::job(&scheduler, app, "1/10 * * * * *").await?;
-    scheduler.add(job).await?;
+/// 各定时任务的 cron 表达式（6 位，含秒）
+#[derive(Debug, Clone)]
+pub struct SchedulerConfig {
+    pub build_voter_list_cron: String,
+    pub vote_meta_tx_cron: String,
+    pub vote_tx_cron: String,
+    pub vote_finished_cron: String,
+    pub prune_orphans_cron: String,
+    pub task_overdue_cron: String,
+    pub disabled_jobs: Vec<JobName>,
+}
 
-    let job = check_vote_tx::job(&scheduler, app, "1/15 * * * * *").await?;
-    scheduler.add(job).await?;
+/// 返回已启动的调度器，退出时由调用方 `shutdown`
+pub async fn init_task_scheduler(app: &AppView, config: &SchedulerConfig) -> Result<JobScheduler> {
+    let mut scheduler = JobScheduler::new().await?;
 
-    let job = check_vote_finished::job(&scheduler, app, "0 * * * * *").await?;
-    scheduler.add(job).await?;
+    let jobs = [
+        (JobName::BuildVoterList, &config.build_voter_list_cron),
+        (JobName::CheckVoteMetaTx, &config.vote_meta_tx_cron),
+        (JobName::CheckVoteTx, &config.vote_tx_cron),
+        (JobName::CheckVoteFinished, &config.vote_finished_cron),
+        (JobName::PruneOrphans, &config.prune_orphans_cron),
+        (JobName::CheckTaskOverdue, &config.task_overdue_cron),
+    ];
+    for (name, cron) in jobs {
+        if config.disabled_jobs.contains(&name) {
+            info!("job {name:?} disabled");
+            continue;
+        }
+        let job = match name {
+            JobName::BuildVoterList => build_voter_list::job(&scheduler, app, cron).await,
+            JobName::CheckVoteMetaTx => check_vote_meta_tx::job(&scheduler, app, cron).await,
+            JobName::CheckVoteTx => check_vote_tx::job(&scheduler, app, cron).await,
+            JobName::CheckVoteFinished => check_vote_finished::job(&scheduler, app, cron).await,
+            JobName::PruneOrphans => prune_orphans::job(&scheduler, app, cron).await,
+            JobName::CheckTaskOverdue => check_task_overdue::job(&scheduler, app, cron).await,
+        }
+        .map_err(|e| eyre!("invalid cron \"{cron}\" for job {name:?}: {e}"))?;
+        scheduler.add(job).await?;
+        info!("job {name:?} scheduled with \"{cron}\"");
+    }
 
     scheduler.set_shutdown_handler(Box::new(|| {
         Box::pin(async move {
-            error!("scheduler shut down");
+            info!("scheduler shut down");
         })
     }));
 
-    scheduler.start().await.map_err(|e| eyre!(e))
+    scheduler.start().await.map_err(|e| eyre!(e))?;
+    Ok(scheduler)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cron_period_of_fixed_schedules() {
+        assert_eq!(
+            cron_period("0 * * * * *").unwrap(),
+            chrono::Duration::minutes(1)
+        );
+        assert_eq!(
+            cron_period("0 30 3 * * *").unwrap(),
+            chrono::Duration::days(1)
+        );
+    }
+
+    #[test]
+    fn cron_period_takes_the_longest_gap() {
+        // 每天 3 点与 4 点触发，最长间隔为 23 小时
+        assert_eq!(
+            cron_period("0 0 3,4 * * *").unwrap(),
+            chrono::Duration::hours(23)
+        );
+    }
+
+    #[test]
+    fn cron_period_rejects_invalid_cron() {
+        assert!(cron_period("not a cron").is_err());
+    }
 }
@@ -0,0 +1,142 @@
+use std::{collections::HashSet, time::Duration};
+
+use color_eyre::Result;
+use sea_query::{Expr, ExprTrait, Iden, PostgresQueryBuilder};
+use sea_query_sqlx::SqlxBinder;
+use tokio_cron_scheduler::{Job, JobScheduler};
+
+use crate::{
+    AppView,
+    atproto::{NSID_LIKE, NSID_REPLY, list_record_uris},
+    lexicon::{like::Like, reply::Reply},
+};
+
+pub async fn job(scheduler: &JobScheduler, app: &AppView, cron: &str) -> Result<Job> {
+    let app = app.clone();
+    let mut job = Job::new_async(cron, move |_uuid, _scheduler| {
+        Box::pin({
+            let db = app.db.clone();
+            let pds = app.pds.clone();
+            let pds_timeout = app.pds_timeout;
+            async move {
+                prune_orphans(
+                    &db,
+                    &pds,
+                    pds_timeout,
+                    Like::Table,
+                    Like::Repo,
+                    Like::Uri,
+                    NSID_LIKE,
+                )
+                .await
+                .map_err(|e| error!("prune orphaned likes failed: {e}"))
+                .ok();
+                prune_orphans(
+                    &db,
+                    &pds,
+                    pds_timeout,
+                    Reply::Table,
+                    Reply::Repo,
+                    Reply::Uri,
+                    NSID_REPLY,
+                )
+                .await
+                .map_err(|e| error!("prune orphaned replies failed: {e}"))
+                .ok();
+            }
+        })
+    })?;
+
+    job.on_removed_notification_add(
+        scheduler,
+        Box::new(|job_id, notification_id, type_of_notification| {
+            Box::pin(async move {
+                info!(
+                    "Job {:?} was removed, notification {:?} ran ({:?})",
+                    job_id, notification_id, type_of_notification
+                );
+            })
+        }),
+    )
+    .await?;
+    Ok(job)
+}
+
+/// 删除 PDS 上已不存在的本地记录（错过了 relayer 的 delete 事件）
+pub async fn prune_orphans<T: Iden + Copy + 'static>(
+    db: &sqlx::Pool<sqlx::Postgres>,
+    pds: &str,
+    pds_timeout: Duration,
+    table: T,
+    repo_col: T,
+    uri_col: T,
+    nsid: &str,
+) -> Result<()> {
+    let (sql, values) = sea_query::Query::select()
+        .distinct()
+        .column(repo_col)
+        .from(table)
+        .build_sqlx(PostgresQueryBuilder);
+    let repos: Vec<(String,)> = sqlx::query_as_with(&sql, values).fetch_all(db).await?;
+
+    for (repo,) in repos {
+        // skip the repo when the listing fails, a partial listing would prune live rows
+        let remote = match list_record_uris(pds, pds_timeout, &repo, nsid).await {
+            Ok(uris) => uris.into_iter().collect::<HashSet<_>>(),
+            Err(e) => {
+                warn!("list {nsid} of {repo} failed: {e}");
+                continue;
+            }
+        };
+
+        let (sql, values) = sea_query::Query::select()
+            .column(uri_col)
+            .from(table)
+            .and_where(Expr::col(repo_col).eq(&repo))
+            .build_sqlx(PostgresQueryBuilder);
+        let local: Vec<(String,)> = sqlx::query_as_with(&sql, values).fetch_all(db).await?;
+        let orphans = orphaned_uris(local.into_iter().map(|(uri,)| uri), &remote);
+        if orphans.is_empty() {
+            continue;
+        }
+
+        info!("prune {} orphaned {nsid} of {repo}", orphans.len());
+        let (sql, values) = sea_query::Query::delete()
+            .from_table(table)
+            .and_where(Expr::col(uri_col).is_in(orphans))
+            .build_sqlx(PostgresQueryBuilder);
+        sqlx::query_with(&sql, values).execute(db).await?;
+    }
+    Ok(())
+}
+
+/// 本地存在而 PDS 上已不存在的 uri
+fn orphaned_uris(local: impl IntoIterator<Item = String>, remote: &HashSet<String>) -> Vec<String> {
+    local
+        .into_iter()
+        .filter(|uri| !remote.contains(uri))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn like_missing_on_pds_is_orphaned() {
+        let kept = "at://did:plc:a/app.dao.like/1".to_string();
+        let deleted = "at://did:plc:a/app.dao.like/2".to_string();
+        let remote = HashSet::from([kept.clone(), "at://did:plc:a/app.dao.like/3".to_string()]);
+        assert_eq!(
+            orphaned_uris([kept, deleted.clone()], &remote),
+            vec![deleted]
+        );
+    }
+
+    #[test]
+    fn nothing_is_orphaned_when_all_present() {
+        let uri = "at://did:plc:a/app.dao.reply/1".to_string();
+        let remote = HashSet::from([uri.clone()]);
+        assert!(orphaned_uris([uri], &remote).is_empty());
+    }
+}
@@ -3,6 +3,7 @@
 // we have different PERSONALIZATION
 
 use blake2b_ref::{Blake2b, Blake2bBuilder};
+use color_eyre::{Result, eyre::eyre};
 use sparse_merkle_tree::{H256, SparseMerkleTree, default_store::DefaultStore, traits::Hasher};
 
 pub const SMT_VALUE: [u8; 32] = [
@@ -40,3 +41,43 @@ impl Hasher for Blake2bHasher {
         hash.into()
     }
 }
+
+/// 由投票名单（lock hash hex）重建 SMT，非法条目忽略
+pub fn build_tree(list: &[String]) -> CkbSMT {
+    let mut smt_tree = CkbSMT::default();
+    for lock_hash in list.iter() {
+        if let Ok(lock_hash) = hex::decode(lock_hash)
+            && let Ok(key) = TryInto::<[u8; 32]>::try_into(lock_hash.as_slice())
+        {
+            smt_tree.update(key.into(), SMT_VALUE.into()).ok();
+        }
+    }
+    smt_tree
+}
+
+/// 单个 key 的 compiled proof，并用 root 校验一次
+pub fn compiled_proof(smt_tree: &CkbSMT, key: [u8; 32]) -> Result<Vec<u8>> {
+    let proof = smt_tree
+        .merkle_proof(vec![key.into()])
+        .map_err(|e| eyre!(e))?;
+    let compiled_proof = proof.compile(vec![key.into()]).map_err(|e| eyre!(e))?;
+    let ret = compiled_proof
+        .verify::<Blake2bHasher>(smt_tree.root(), vec![(key.into(), SMT_VALUE.into())])
+        .unwrap_or(false);
+    if ret {
+        Ok(compiled_proof.0)
+    } else {
+        Err(eyre!("Not in the voter_list"))
+    }
+}
+
+/// 名单内所有 key 的 compiled proof：(lock_hash, proof)，均为 hex
+pub fn compiled_proofs(smt_tree: &CkbSMT, list: &[String]) -> Result<Vec<(String, String)>> {
+    let mut proofs = Vec::with_capacity(list.len());
+    for lock_hash in list.iter() {
+        let key: [u8; 32] = hex::decode(lock_hash)?.as_slice().try_into()?;
+        let proof = compiled_proof(smt_tree, key)?;
+        proofs.push((lock_hash.clone(), hex::encode(proof)));
+    }
+    Ok(proofs)
+}
@@ -0,0 +1,91 @@
+use std::time::Duration;
+
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+
+/// 单次请求超时
+const WEBHOOK_TIMEOUT_SECS: u64 = 5;
+/// 最多尝试次数（含首次）
+const WEBHOOK_MAX_ATTEMPTS: u32 = 3;
+
+#[derive(Debug, Clone)]
+pub struct Webhook {
+    pub url: String,
+    /// 非空时对请求体做 HMAC-SHA256 签名，放在 `X-Signature` 头
+    pub secret: Option<String>,
+    client: reqwest::Client,
+}
+
+/// 推送给管理员的事件
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "camelCase")]
+pub enum WebhookEvent {
+    #[serde(rename_all = "camelCase")]
+    TaskCreated {
+        id: i32,
+        task_type: i32,
+        target: String,
+        operators: Vec<String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    ProposalStateChanged { uri: String, state: i32 },
+    #[serde(rename_all = "camelCase")]
+    TaskOverdue {
+        id: i32,
+        task_type: i32,
+        target: String,
+        operators: Vec<String>,
+    },
+}
+
+fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("hmac accepts any key length");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+impl Webhook {
+    pub fn new(url: String, secret: Option<String>) -> Self {
+        Self {
+            url,
+            secret,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// 后台推送，不阻塞调用方
+    pub fn notify(&self, event: WebhookEvent) {
+        let body = match serde_json::to_vec(&event) {
+            Ok(body) => body,
+            Err(e) => {
+                error!("encode webhook event failed: {e}");
+                return;
+            }
+        };
+        let signature = self.secret.as_deref().map(|s| sign(s, &body));
+        let webhook = self.clone();
+        tokio::spawn(async move {
+            for attempt in 1..=WEBHOOK_MAX_ATTEMPTS {
+                let mut request = webhook
+                    .client
+                    .post(&webhook.url)
+                    .header("Content-Type", "application/json; charset=utf-8")
+                    .timeout(Duration::from_secs(WEBHOOK_TIMEOUT_SECS))
+                    .body(body.clone());
+                if let Some(signature) = &signature {
+                    request = request.header("X-Signature", signature);
+                }
+                match request.send().await.and_then(|r| r.error_for_status()) {
+                    Ok(_) => return,
+                    Err(e) => warn!("webhook attempt {attempt}/{WEBHOOK_MAX_ATTEMPTS} failed: {e}"),
+                }
+                if attempt < WEBHOOK_MAX_ATTEMPTS {
+                    tokio::time::sleep(Duration::from_secs(1 << (attempt - 1))).await;
+                }
+            }
+            error!("webhook gave up after {WEBHOOK_MAX_ATTEMPTS} attempts: {event:?}");
+        });
+    }
+}